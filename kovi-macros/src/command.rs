@@ -0,0 +1,262 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{
+    Error, Expr, ExprLit, FnArg, GenericArgument, ItemFn, Lit, LitStr, Meta, Pat, PathArguments,
+    Result, Token, Type,
+};
+
+/// `#[kovi::command("name", alias = "x", about = "...", admin)]` 的参数
+pub(crate) struct CommandAttr {
+    name: Option<LitStr>,
+    aliases: Vec<LitStr>,
+    about: Option<LitStr>,
+    permission: Option<syn::Ident>,
+}
+
+impl Parse for CommandAttr {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut attr = CommandAttr {
+            name: None,
+            aliases: Vec::new(),
+            about: None,
+            permission: None,
+        };
+
+        if input.peek(LitStr) {
+            let name: LitStr = input.parse()?;
+            check_word(&name, "command name")?;
+            attr.name = Some(name);
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        for meta in Punctuated::<Meta, Token![,]>::parse_terminated(input)? {
+            match &meta {
+                Meta::Path(path) if path.is_ident("admin") || path.is_ident("main_admin") => {
+                    if attr.permission.is_some() {
+                        return Err(Error::new(path.span(), "permission is already set"));
+                    }
+                    attr.permission = path.get_ident().cloned();
+                }
+                Meta::NameValue(nv) if nv.path.is_ident("alias") => {
+                    let alias = lit_str(&nv.value)?;
+                    check_word(&alias, "alias")?;
+                    if attr.aliases.iter().any(|a| a.value() == alias.value()) {
+                        return Err(Error::new(alias.span(), "duplicate alias"));
+                    }
+                    attr.aliases.push(alias);
+                }
+                Meta::NameValue(nv) if nv.path.is_ident("about") => {
+                    attr.about = Some(lit_str(&nv.value)?);
+                }
+                _ => {
+                    return Err(Error::new(
+                        meta.span(),
+                        "expected `\"name\"`, `alias = \"...\"`, `about = \"...\"`, `admin` or `main_admin`",
+                    ));
+                }
+            }
+        }
+
+        Ok(attr)
+    }
+}
+
+fn lit_str(expr: &Expr) -> Result<LitStr> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(lit), ..
+        }) => Ok(lit.clone()),
+        _ => Err(Error::new(expr.span(), "expected a string literal")),
+    }
+}
+
+/// 命令名与别名不能为空，也不能包含空白
+fn check_word(lit: &LitStr, what: &str) -> Result<()> {
+    let value = lit.value();
+    if value.is_empty() || value.chars().any(char::is_whitespace) {
+        return Err(Error::new(
+            lit.span(),
+            format!("{what} must be a single non-empty word"),
+        ));
+    }
+    Ok(())
+}
+
+/// 取出 `Option<T>`、`Arc<T>` 这类类型的最后一段名字与第一个泛型参数
+fn last_segment(ty: &Type) -> Option<(String, Option<&Type>)> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    let inner = match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        }),
+        _ => None,
+    };
+    Some((segment.ident.to_string(), inner))
+}
+
+/// 命令名默认取函数名，说明默认取文档注释的第一段
+pub(crate) fn expand(attr: CommandAttr, mut input: ItemFn) -> Result<TokenStream> {
+    let sig = &input.sig;
+    if sig.asyncness.is_none() {
+        return Err(Error::new(
+            sig.fn_token.span(),
+            "command handler must be an async fn",
+        ));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(Error::new(
+            sig.generics.span(),
+            "command handler cannot be generic",
+        ));
+    }
+
+    let mut params = sig.inputs.iter();
+    let event_ty = match params.next() {
+        Some(FnArg::Typed(event)) => match last_segment(&event.ty) {
+            Some((name, Some(inner))) if name == "Arc" => inner.clone(),
+            _ => {
+                return Err(Error::new(
+                    event.ty.span(),
+                    "the first parameter must be the event, e.g. `event: Arc<MsgEvent>`",
+                ));
+            }
+        },
+        Some(other) => return Err(Error::new(other.span(), "command handler cannot take self")),
+        None => {
+            return Err(Error::new(
+                sig.inputs.span(),
+                "the first parameter must be the event, e.g. `event: Arc<MsgEvent>`",
+            ));
+        }
+    };
+
+    let mut arg_specs = Vec::new();
+    let mut arg_values = Vec::new();
+    let mut arg_idents = Vec::new();
+    let mut seen_optional = false;
+    let mut seen_rest = false;
+    for param in params {
+        let FnArg::Typed(param) = param else {
+            return Err(Error::new(param.span(), "command handler cannot take self"));
+        };
+        let Pat::Ident(ident) = &*param.pat else {
+            return Err(Error::new(
+                param.pat.span(),
+                "command arguments must be plain identifiers",
+            ));
+        };
+        let name = ident.ident.to_string();
+        let name = name.trim_start_matches('_');
+        let ty = &param.ty;
+
+        if seen_rest {
+            return Err(Error::new(
+                ident.span(),
+                format!("argument `{name}` follows a `Rest` argument"),
+            ));
+        }
+        let (optional, inner) = match last_segment(ty) {
+            Some((outer, Some(inner))) if outer == "Option" => (true, inner),
+            _ => (false, &**ty),
+        };
+        if seen_optional && !optional {
+            return Err(Error::new(
+                ident.span(),
+                format!("required argument `{name}` follows an optional argument"),
+            ));
+        }
+        seen_optional |= optional;
+        seen_rest = matches!(last_segment(inner), Some((ty, _)) if ty == "Rest");
+
+        arg_specs.push(quote! {
+            .arg(kovi::command::args::Arg::of::<#ty>(#name))
+        });
+        arg_values.push(quote! {
+            let Some(#ident) = <#ty as kovi::command::args::ArgType>::from_args(&args, #name) else {
+                return;
+            };
+        });
+        arg_idents.push(ident.ident.clone());
+    }
+
+    let fn_name = sig.ident.clone();
+    let vis = input.vis.clone();
+    let name = attr
+        .name
+        .map(|name| name.value())
+        .unwrap_or_else(|| fn_name.to_string());
+    let aliases = &attr.aliases;
+    let about = attr
+        .about
+        .map(|about| about.value())
+        .unwrap_or_else(|| doc_summary(&input));
+    let permission = match attr.permission {
+        Some(ident) if ident == "main_admin" => quote! { MainAdmin },
+        Some(_) => quote! { Admin },
+        None => quote! { Everyone },
+    };
+    let docs: Vec<_> = input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .cloned()
+        .collect();
+
+    input.sig.ident = syn::Ident::new("__kovi_command_handler", fn_name.span());
+    input.vis = syn::Visibility::Inherited;
+
+    Ok(quote! {
+        #(#docs)*
+        #vis fn #fn_name() -> kovi::command::CommandDef<#event_ty> {
+            #input
+
+            let command = kovi::command::Command::new(#name)
+                #(.alias(#aliases))*
+                .about(#about)
+                #(#arg_specs)*
+                .permission(kovi::command::Permission::#permission);
+
+            kovi::command::CommandDef::new(
+                command,
+                |event: std::sync::Arc<#event_ty>, args: kovi::command::args::Args| async move {
+                    #(#arg_values)*
+                    __kovi_command_handler(event, #(#arg_idents),*).await;
+                },
+            )
+        }
+    })
+}
+
+/// 文档注释的第一段，多行合并为一行
+fn doc_summary(input: &ItemFn) -> String {
+    let mut lines = Vec::new();
+    for attr in &input.attrs {
+        let Meta::NameValue(nv) = &attr.meta else {
+            continue;
+        };
+        if !nv.path.is_ident("doc") {
+            continue;
+        }
+        let Ok(line) = lit_str(&nv.value) else {
+            continue;
+        };
+        let line = line.value().trim().to_string();
+        if line.is_empty() {
+            if lines.is_empty() {
+                continue;
+            }
+            break;
+        }
+        lines.push(line);
+    }
+    lines.join(" ")
+}
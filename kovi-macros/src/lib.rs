@@ -1,3 +1,6 @@
+mod command;
+mod test;
+
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{ItemFn, parse_macro_input};
@@ -49,3 +52,49 @@ pub fn plugin(_attr: TokenStream, item: TokenStream) -> TokenStream {
 
     TokenStream::from(expanded)
 }
+
+/// 把 async 函数声明为命令，生成返回 `kovi::command::CommandDef` 的同名函数。
+///
+/// 第一个参数为事件 `Arc<E>`，其余参数按类型解析：`i64`、`String`、`ID`、
+/// `kovi::command::args::Rest`，`Option<T>` 为可选参数。命令名默认为函数名，说明取文档注释的第一段。
+///
+/// ```ignore
+/// /// 禁言群成员
+/// #[kovi::command("ban", alias = "mute", admin)]
+/// async fn ban(event: Arc<MsgEvent>, user: ID, minutes: Option<i64>) {}
+///
+/// PluginBuilder::commands(CommandSet::new().register(ban()));
+/// ```
+#[proc_macro_attribute]
+pub fn command(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attr = parse_macro_input!(attr as command::CommandAttr);
+    let input = parse_macro_input!(item as ItemFn);
+
+    command::expand(attr, input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// 在 Bot 中运行插件测试，需要启用 kovi 的 `testing` feature。
+///
+/// 测试在插件的作用域中执行，可以直接运行插件的 `main`。函数可以不带参数，
+/// 或以唯一的参数接收 `kovi::testing::PluginTest`。`driver` 默认为不解析消息事件的
+/// `kovi::testing::MockDriver`，`plugin` 默认为当前包名。
+///
+/// ```ignore
+/// #[kovi::test(driver = kovi_onebot::testing::mock_driver())]
+/// async fn replies_hello(test: PluginTest) {
+///     crate::main().await;
+///     test.driver().send(kovi_onebot::testing::private_msg(2, "hi")).await;
+///     test.api().wait("send_msg", 1).await;
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attr = parse_macro_input!(attr as test::TestAttr);
+    let input = parse_macro_input!(item as ItemFn);
+
+    test::expand(attr, input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
@@ -0,0 +1,107 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Error, Expr, FnArg, ItemFn, LitStr, Meta, Result, Token};
+
+/// `#[kovi::test(driver = ..., plugin = "...")]` 的参数
+pub(crate) struct TestAttr {
+    driver: Option<Expr>,
+    plugin: Option<LitStr>,
+}
+
+impl Parse for TestAttr {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut attr = TestAttr {
+            driver: None,
+            plugin: None,
+        };
+
+        for meta in Punctuated::<Meta, Token![,]>::parse_terminated(input)? {
+            match meta {
+                Meta::NameValue(nv) if nv.path.is_ident("driver") => {
+                    attr.driver = Some(nv.value);
+                }
+                Meta::NameValue(nv) if nv.path.is_ident("plugin") => {
+                    let Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(lit),
+                        ..
+                    }) = nv.value
+                    else {
+                        return Err(Error::new(nv.value.span(), "expected a string literal"));
+                    };
+                    attr.plugin = Some(lit);
+                }
+                _ => {
+                    return Err(Error::new(
+                        meta.span(),
+                        "expected `driver = <expr>` or `plugin = \"...\"`",
+                    ));
+                }
+            }
+        }
+
+        Ok(attr)
+    }
+}
+
+/// 测试函数可以不带参数，或以唯一的参数接收 `kovi::testing::PluginTest`
+pub(crate) fn expand(attr: TestAttr, input: ItemFn) -> Result<TokenStream> {
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = input;
+
+    if sig.asyncness.is_none() {
+        return Err(Error::new(
+            sig.fn_token.span(),
+            "kovi::test must be an async fn",
+        ));
+    }
+    if sig.inputs.len() > 1 {
+        return Err(Error::new(
+            sig.inputs.span(),
+            "kovi::test takes at most one argument: kovi::testing::PluginTest",
+        ));
+    }
+    let bind = match sig.inputs.first() {
+        Some(FnArg::Typed(arg)) => {
+            let pat = &arg.pat;
+            let ty = &arg.ty;
+            quote! { let #pat: #ty = __kovi_test; }
+        }
+        Some(FnArg::Receiver(receiver)) => {
+            return Err(Error::new(receiver.span(), "kovi::test cannot take self"));
+        }
+        None => quote! { let _ = __kovi_test; },
+    };
+
+    let driver = match attr.driver {
+        Some(driver) => quote! { #driver },
+        None => quote! { ::kovi::testing::MockDriver::without_message_event() },
+    };
+    let plugin = match attr.plugin {
+        Some(plugin) => quote! { #plugin },
+        None => quote! { env!("CARGO_PKG_NAME") },
+    };
+
+    let name = &sig.ident;
+    let output = &sig.output;
+
+    Ok(quote! {
+        #(#attrs)*
+        #[::kovi::tokio::test(crate = "::kovi::tokio", flavor = "multi_thread", worker_threads = 2)]
+        #vis async fn #name() #output {
+            ::kovi::testing::PluginTest::start(#plugin, #driver)
+                .await
+                .run(|__kovi_test| async move {
+                    #bind
+                    #block
+                })
+                .await
+        }
+    })
+}
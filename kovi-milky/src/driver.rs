@@ -5,6 +5,7 @@ use crate::driver::config::{MilkyDriverConfig, Server};
 use http::header::{AUTHORIZATION, CONTENT_TYPE};
 use kovi::bot::SendApi;
 use kovi::driver::{Driver, DriverEvent, MessageEventRegister};
+use kovi::event::id::ID;
 use kovi::futures_util;
 use log::{error, info};
 use std::sync::Arc;
@@ -84,6 +85,16 @@ impl Driver for MilkyDriver {
     fn message_event_register(&self) -> MessageEventRegister {
         MessageEventRegister::register::<MsgEvent>()
     }
+
+    fn private_message_api(&self, user_id: &ID, text: &str) -> Option<SendApi> {
+        Some(SendApi::new(
+            "send_private_message",
+            serde_json::json!({
+                "user_id": user_id,
+                "message": [{"type": "text", "data": {"text": text}}],
+            }),
+        ))
+    }
 }
 
 impl MilkyDriver {
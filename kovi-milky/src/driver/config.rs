@@ -1,10 +1,11 @@
 use dialoguer::theme::ColorfulTheme;
 use dialoguer::{Input, Select};
+use kovi::config::overrides::{ConfigField, ConfigOverrides, FieldKind};
+use kovi::config::paths::{KoviPaths, lock_conf_file, write_conf_file};
 use kovi::error::BotBuildError;
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 use std::fs;
-use std::io::Write as _;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::path::Path;
 
@@ -36,7 +37,9 @@ impl MilkyDriverConfig {
 pub struct Server {
     pub host: Host,
     pub port: u16,
+    #[serde(default)]
     pub access_token: String,
+    #[serde(default)]
     pub secure: bool,
     /// path route to ws
     #[serde(default = "default_path")]
@@ -236,6 +239,7 @@ fn config_file_write_and_return(file_path: &Path) -> Result<MilkyDriverConfig, s
         },
     };
 
+    let _lock = lock_conf_file();
     let mut doc = match fs::read_to_string(file_path) {
         Ok(content) => match content.parse::<toml_edit::DocumentMut>() {
             Ok(d) => d,
@@ -260,45 +264,59 @@ fn config_file_write_and_return(file_path: &Path) -> Result<MilkyDriverConfig, s
     doc["server"]["secure"] = toml_edit::value(config.server.secure);
     doc["server"]["path"] = toml_edit::value(&config.server.path);
 
-    let file = fs::File::create(file_path)?;
-    let mut writer = std::io::BufWriter::new(file);
-    writer.write_all(doc.to_string().as_bytes())?;
+    write_conf_file(file_path, &doc.to_string())?;
 
     Ok(config)
 }
 
-/// 读取本地Kovi.conf.toml文件
+/// 可以由环境变量与命令行参数设置的 `[server]` 配置项，见 [`kovi::config::overrides`]
+pub const SERVER_FIELDS: &[ConfigField] = &[
+    ConfigField::new(
+        "host",
+        "KOVI_SERVER_HOST",
+        "--server-host",
+        FieldKind::String,
+    ),
+    ConfigField::new(
+        "port",
+        "KOVI_SERVER_PORT",
+        "--server-port",
+        FieldKind::Integer,
+    ),
+    ConfigField::new(
+        "access_token",
+        "KOVI_SERVER_ACCESS_TOKEN",
+        "--server-access-token",
+        FieldKind::String,
+    ),
+    ConfigField::new(
+        "secure",
+        "KOVI_SERVER_SECURE",
+        "--server-secure",
+        FieldKind::Bool,
+    ),
+    ConfigField::new(
+        "path",
+        "KOVI_SERVER_PATH",
+        "--server-path",
+        FieldKind::String,
+    ),
+];
+
+/// 读取本地Kovi.conf.toml文件，位置见 [`KoviPaths`]
+///
+/// 文件中的配置会被环境变量与命令行参数覆盖，见 [`SERVER_FIELDS`]。
+///
+/// 文件中只有一个 `[server]`，多账号的 Bot 只能用它配置一个账号，其他账号请在代码中构造配置。
+/// 设置了覆盖时再次调用会返回 [`BotBuildError::SharedOverrides`]，因为覆盖会作用于所有账号。
 pub fn load_local_conf() -> Result<MilkyDriverConfig, BotBuildError> {
-    let path = Path::new("kovi.conf.toml");
-    let kovi_conf_file_exist = fs::metadata(path).is_ok();
-
-    #[derive(Deserialize, Serialize, Debug, Clone)]
-    struct TempKoviConfig {
-        server: Option<Server>,
+    let path = &KoviPaths::current().conf_file();
+    let overrides = ConfigOverrides::from_env_and_args();
+    overrides.claim_table("server", SERVER_FIELDS)?;
+
+    match overrides.load_table::<Server>(path, "server", SERVER_FIELDS)? {
+        Some(server) => Ok(MilkyDriverConfig { server }),
+        None => config_file_write_and_return(path)
+            .map_err(|e| BotBuildError::FileCreateError(e.to_string())),
     }
-
-    let conf_json: MilkyDriverConfig = if kovi_conf_file_exist {
-        match fs::read_to_string(path) {
-            Ok(v) => match toml::from_str::<TempKoviConfig>(&v) {
-                Ok(conf) => match conf.server {
-                    Some(server) => MilkyDriverConfig { server },
-                    None => config_file_write_and_return(path)
-                        .map_err(|e| BotBuildError::FileCreateError(e.to_string()))?,
-                },
-                Err(err) => {
-                    eprintln!("Configuration file parsing error: {err}");
-                    config_file_write_and_return(path)
-                        .map_err(|e| BotBuildError::FileCreateError(e.to_string()))?
-                }
-            },
-            Err(err) => {
-                return Err(BotBuildError::FileReadError(err.to_string()));
-            }
-        }
-    } else {
-        config_file_write_and_return(path)
-            .map_err(|e| BotBuildError::FileCreateError(e.to_string()))?
-    };
-
-    Ok(conf_json)
 }
@@ -133,7 +133,7 @@ pub struct FriendCategoryEntity {
     pub category_name: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(bound(serialize = "T: Serialize", deserialize = "T: DeserializeOwned"))]
 pub struct MilkyEvent<T = serde_json::Value>
 where
@@ -155,7 +155,7 @@ impl Event for MilkyEvent<serde_json::Value> {
         Self: Sized,
     {
         if let kovi::event::InternalEvent::DriverEvent(data) = event {
-            serde_json::from_value(data.clone()).ok()
+            Self::deserialize(&**data).ok()
         } else {
             None
         }
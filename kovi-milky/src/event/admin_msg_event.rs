@@ -6,12 +6,14 @@ use kovi::bot::runtimebot::{CanSendApi, send_api_request_with_forget};
 use kovi::bot::{BotInformation, SendApi};
 use kovi::error::EventBuildError;
 use kovi::event::id::ref_id::RefID;
-use kovi::event::{Event, InternalEvent, MessageEventTrait, MessageEventUtil, RepliableEvent};
+use kovi::event::{
+    Event, InternalEvent, MessageEventTrait, MessageEventUtil, RepliableEvent, parse_cached,
+};
 use kovi::message::Message as KoviMessage;
 use kovi::types::ApiAndOptOneshot;
 use log::info;
 use serde::Serialize;
-use serde_json::{self, Value, json};
+use serde_json::{self, json};
 use tokio::sync::mpsc;
 
 #[derive(Debug, Clone)]
@@ -93,11 +95,9 @@ impl Event for AdminMsgEvent {
         bot_info: &BotInformation,
         api_tx: &mpsc::Sender<ApiAndOptOneshot>,
     ) -> Option<Self> {
-        let InternalEvent::DriverEvent(json) = event else {
-            return None;
-        };
+        let msg_event = parse_cached::<MsgEvent>(event, bot_info, api_tx)?;
 
-        Self::new(api_tx, json, bot_info).ok()
+        Self::from_msg_event(&msg_event, bot_info).ok()
     }
 }
 
@@ -131,20 +131,18 @@ impl TryFrom<MsgEvent> for AdminMsgEvent {
 }
 
 impl AdminMsgEvent {
-    pub(crate) fn new(
-        api_tx: &mpsc::Sender<ApiAndOptOneshot>,
-        temp: &Value,
+    /// 从已经解析好的 [`MsgEvent`] 构造
+    pub(crate) fn from_msg_event(
+        event: &MsgEvent,
         bot_info: &BotInformation,
     ) -> Result<AdminMsgEvent, EventBuildError> {
-        let event = MsgEvent::new(api_tx, temp)?;
-
         if !bot_info.any_admins_contains(RefID::new(&event.data.sender_id)) {
             return Err(EventBuildError::ParseError(
                 "message sender is not an admin".to_string(),
             ));
         }
 
-        let event = AdminMsgEvent::try_from(event)?;
+        let event = AdminMsgEvent::try_from(event.clone())?;
 
         Ok(event)
     }
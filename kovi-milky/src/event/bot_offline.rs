@@ -31,7 +31,7 @@ impl Event for BotOfflineEvent {
 
 impl BotOfflineEvent {
     pub(crate) fn new(temp: &Value) -> Result<BotOfflineEvent, EventBuildError> {
-        let event: BotOfflineEvent = serde_json::from_value(temp.clone())
+        let event = BotOfflineEvent::deserialize(temp)
             .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
         debug!("{event:?}");
 
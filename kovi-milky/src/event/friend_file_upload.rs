@@ -43,7 +43,7 @@ impl Event for FriendFileUploadEvent {
 
 impl FriendFileUploadEvent {
     pub(crate) fn new(temp: &Value) -> Result<FriendFileUploadEvent, EventBuildError> {
-        let event: FriendFileUploadEvent = serde_json::from_value(temp.clone())
+        let event = FriendFileUploadEvent::deserialize(temp)
             .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
         debug!("{event:?}");
 
@@ -6,12 +6,14 @@ use kovi::bot::runtimebot::{CanSendApi, send_api_request_with_forget};
 use kovi::bot::{BotInformation, SendApi};
 use kovi::error::EventBuildError;
 use kovi::event::id::ref_id::RefID;
-use kovi::event::{Event, InternalEvent, MessageEventTrait, MessageEventUtil, RepliableEvent};
+use kovi::event::{
+    Event, InternalEvent, MessageEventTrait, MessageEventUtil, RepliableEvent, parse_cached,
+};
 use kovi::message::Message as KoviMessage;
 use kovi::types::ApiAndOptOneshot;
 use log::info;
 use serde::Serialize;
-use serde_json::{self, Value, json};
+use serde_json::{self, json};
 use tokio::sync::mpsc;
 
 #[derive(Debug, Clone)]
@@ -70,14 +72,12 @@ impl MessageEventTrait for FriendMsgEvent {
 impl Event for FriendMsgEvent {
     fn de(
         event: &InternalEvent,
-        _: &BotInformation,
+        bot_info: &BotInformation,
         api_tx: &mpsc::Sender<ApiAndOptOneshot>,
     ) -> Option<Self> {
-        let InternalEvent::DriverEvent(json) = event else {
-            return None;
-        };
+        let msg_event = parse_cached::<MsgEvent>(event, bot_info, api_tx)?;
 
-        Self::new(api_tx, json).ok()
+        Self::from_msg_event(&msg_event).ok()
     }
 }
 
@@ -111,19 +111,15 @@ impl TryFrom<MsgEvent> for FriendMsgEvent {
 }
 
 impl FriendMsgEvent {
-    pub(crate) fn new(
-        api_tx: &mpsc::Sender<ApiAndOptOneshot>,
-        temp: &Value,
-    ) -> Result<FriendMsgEvent, EventBuildError> {
-        let event = MsgEvent::new(api_tx, temp)?;
-
+    /// 从已经解析好的 [`MsgEvent`] 构造
+    pub(crate) fn from_msg_event(event: &MsgEvent) -> Result<FriendMsgEvent, EventBuildError> {
         if event.data.message_scene != MessageScene::Friend {
             return Err(EventBuildError::ParseError(
                 "message_scene must be Friend".to_string(),
             ));
         }
 
-        let event = FriendMsgEvent::try_from(event)?;
+        let event = FriendMsgEvent::try_from(event.clone())?;
 
         Ok(event)
     }
@@ -43,7 +43,7 @@ impl Event for FriendNudgeEvent {
 
 impl FriendNudgeEvent {
     pub(crate) fn new(temp: &Value) -> Result<FriendNudgeEvent, EventBuildError> {
-        let event: FriendNudgeEvent = serde_json::from_value(temp.clone())
+        let event = FriendNudgeEvent::deserialize(temp)
             .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
         debug!("{event:?}");
 
@@ -39,7 +39,7 @@ impl Event for FriendRequestEvent {
 
 impl FriendRequestEvent {
     pub(crate) fn new(temp: &Value) -> Result<FriendRequestEvent, EventBuildError> {
-        let event: FriendRequestEvent = serde_json::from_value(temp.clone())
+        let event = FriendRequestEvent::deserialize(temp)
             .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
         debug!("{event:?}");
 
@@ -39,7 +39,7 @@ impl Event for GroupAdminChangeEvent {
 
 impl GroupAdminChangeEvent {
     pub(crate) fn new(temp: &Value) -> Result<GroupAdminChangeEvent, EventBuildError> {
-        let event: GroupAdminChangeEvent = serde_json::from_value(temp.clone())
+        let event = GroupAdminChangeEvent::deserialize(temp)
             .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
         debug!("{event:?}");
 
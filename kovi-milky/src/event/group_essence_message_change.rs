@@ -39,7 +39,7 @@ impl Event for GroupEssenceMessageChangeEvent {
 
 impl GroupEssenceMessageChangeEvent {
     pub(crate) fn new(temp: &Value) -> Result<GroupEssenceMessageChangeEvent, EventBuildError> {
-        let event: GroupEssenceMessageChangeEvent = serde_json::from_value(temp.clone())
+        let event = GroupEssenceMessageChangeEvent::deserialize(temp)
             .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
         debug!("{event:?}");
 
@@ -41,7 +41,7 @@ impl Event for GroupFileUploadEvent {
 
 impl GroupFileUploadEvent {
     pub(crate) fn new(temp: &Value) -> Result<GroupFileUploadEvent, EventBuildError> {
-        let event: GroupFileUploadEvent = serde_json::from_value(temp.clone())
+        let event = GroupFileUploadEvent::deserialize(temp)
             .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
         debug!("{event:?}");
 
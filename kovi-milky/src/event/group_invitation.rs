@@ -40,7 +40,7 @@ impl Event for GroupInvitationEvent {
 
 impl GroupInvitationEvent {
     pub(crate) fn new(temp: &Value) -> Result<GroupInvitationEvent, EventBuildError> {
-        let event: GroupInvitationEvent = serde_json::from_value(temp.clone())
+        let event = GroupInvitationEvent::deserialize(temp)
             .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
         debug!("{event:?}");
 
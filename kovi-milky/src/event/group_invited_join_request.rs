@@ -39,7 +39,7 @@ impl Event for GroupInvitedJoinRequestEvent {
 
 impl GroupInvitedJoinRequestEvent {
     pub(crate) fn new(temp: &Value) -> Result<GroupInvitedJoinRequestEvent, EventBuildError> {
-        let event: GroupInvitedJoinRequestEvent = serde_json::from_value(temp.clone())
+        let event = GroupInvitedJoinRequestEvent::deserialize(temp)
             .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
         debug!("{event:?}");
 
@@ -41,7 +41,7 @@ impl Event for GroupJoinRequestEvent {
 
 impl GroupJoinRequestEvent {
     pub(crate) fn new(temp: &Value) -> Result<GroupJoinRequestEvent, EventBuildError> {
-        let event: GroupJoinRequestEvent = serde_json::from_value(temp.clone())
+        let event = GroupJoinRequestEvent::deserialize(temp)
             .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
         debug!("{event:?}");
 
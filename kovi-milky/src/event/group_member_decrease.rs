@@ -38,7 +38,7 @@ impl Event for GroupMemberDecreaseEvent {
 
 impl GroupMemberDecreaseEvent {
     pub(crate) fn new(temp: &Value) -> Result<GroupMemberDecreaseEvent, EventBuildError> {
-        let event: GroupMemberDecreaseEvent = serde_json::from_value(temp.clone())
+        let event = GroupMemberDecreaseEvent::deserialize(temp)
             .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
         debug!("{event:?}");
 
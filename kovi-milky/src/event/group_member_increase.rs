@@ -41,7 +41,7 @@ impl Event for GroupMemberIncreaseEvent {
 
 impl GroupMemberIncreaseEvent {
     pub(crate) fn new(temp: &Value) -> Result<GroupMemberIncreaseEvent, EventBuildError> {
-        let event: GroupMemberIncreaseEvent = serde_json::from_value(temp.clone())
+        let event = GroupMemberIncreaseEvent::deserialize(temp)
             .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
         debug!("{event:?}");
 
@@ -53,7 +53,7 @@ impl Event for GroupMessageReactionEvent {
 
 impl GroupMessageReactionEvent {
     pub(crate) fn new(temp: &Value) -> Result<GroupMessageReactionEvent, EventBuildError> {
-        let event: GroupMessageReactionEvent = serde_json::from_value(temp.clone())
+        let event = GroupMessageReactionEvent::deserialize(temp)
             .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
         debug!("{event:?}");
 
@@ -39,7 +39,7 @@ impl Event for GroupMuteEvent {
 
 impl GroupMuteEvent {
     pub(crate) fn new(temp: &Value) -> Result<GroupMuteEvent, EventBuildError> {
-        let event: GroupMuteEvent = serde_json::from_value(temp.clone())
+        let event = GroupMuteEvent::deserialize(temp)
             .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
         debug!("{event:?}");
 
@@ -37,7 +37,7 @@ impl Event for GroupNameChangeEvent {
 
 impl GroupNameChangeEvent {
     pub(crate) fn new(temp: &Value) -> Result<GroupNameChangeEvent, EventBuildError> {
-        let event: GroupNameChangeEvent = serde_json::from_value(temp.clone())
+        let event = GroupNameChangeEvent::deserialize(temp)
             .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
         debug!("{event:?}");
 
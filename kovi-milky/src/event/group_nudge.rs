@@ -43,7 +43,7 @@ impl Event for GroupNudgeEvent {
 
 impl GroupNudgeEvent {
     pub(crate) fn new(temp: &Value) -> Result<GroupNudgeEvent, EventBuildError> {
-        let event: GroupNudgeEvent = serde_json::from_value(temp.clone())
+        let event = GroupNudgeEvent::deserialize(temp)
             .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
         debug!("{event:?}");
 
@@ -37,7 +37,7 @@ impl Event for GroupWholeMuteEvent {
 
 impl GroupWholeMuteEvent {
     pub(crate) fn new(temp: &Value) -> Result<GroupWholeMuteEvent, EventBuildError> {
-        let event: GroupWholeMuteEvent = serde_json::from_value(temp.clone())
+        let event = GroupWholeMuteEvent::deserialize(temp)
             .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
         debug!("{event:?}");
 
@@ -44,7 +44,7 @@ impl Event for MessageRecallEvent {
 
 impl MessageRecallEvent {
     pub(crate) fn new(temp: &Value) -> Result<MessageRecallEvent, EventBuildError> {
-        let event: MessageRecallEvent = serde_json::from_value(temp.clone())
+        let event = MessageRecallEvent::deserialize(temp)
             .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
         debug!("{event:?}");
 
@@ -146,7 +146,7 @@ impl MsgEvent {
 
         type TempMsgEvent = MilkyEvent<TempMessageReceiveEventData>;
 
-        let temp_msg_event: TempMsgEvent = serde_json::from_value(temp.clone())
+        let temp_msg_event = TempMsgEvent::deserialize(temp)
             .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
 
         debug!("{temp_msg_event:?}");
@@ -38,7 +38,7 @@ impl Event for PeerPinChangeEvent {
 
 impl PeerPinChangeEvent {
     pub(crate) fn new(temp: &Value) -> Result<PeerPinChangeEvent, EventBuildError> {
-        let event: PeerPinChangeEvent = serde_json::from_value(temp.clone())
+        let event = PeerPinChangeEvent::deserialize(temp)
             .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
         debug!("{event:?}");
 
@@ -1,10 +1,11 @@
 use crate::event::{AdminMsgEvent, FriendMsgEvent, GroupMsgEvent, MsgEvent};
 use kovi::PluginBuilder;
+use kovi::plugin::listener::ListenerHandle;
 use std::sync::Arc;
 
 pub trait EventRegistrar {
     /// 注册事件处理函数。
-    fn on_msg<F, Fut>(handler: F)
+    fn on_msg<F, Fut>(handler: F) -> ListenerHandle
     where
         F: Fn(Arc<MsgEvent>) -> Fut + Send + Sync + 'static,
         Fut: Future + Send,
@@ -14,7 +15,7 @@ pub trait EventRegistrar {
     }
 
     /// 注册事件处理函数。
-    fn on_admin_msg<F, Fut>(handler: F)
+    fn on_admin_msg<F, Fut>(handler: F) -> ListenerHandle
     where
         F: Fn(Arc<AdminMsgEvent>) -> Fut + Send + Sync + 'static,
         Fut: Future + Send,
@@ -24,7 +25,7 @@ pub trait EventRegistrar {
     }
 
     /// 注册事件处理函数。
-    fn on_private_msg<F, Fut>(handler: F)
+    fn on_private_msg<F, Fut>(handler: F) -> ListenerHandle
     where
         F: Fn(Arc<FriendMsgEvent>) -> Fut + Send + Sync + 'static,
         Fut: Future + Send,
@@ -34,7 +35,7 @@ pub trait EventRegistrar {
     }
 
     /// 注册事件处理函数。
-    fn on_group_msg<F, Fut>(handler: F)
+    fn on_group_msg<F, Fut>(handler: F) -> ListenerHandle
     where
         F: Fn(Arc<GroupMsgEvent>) -> Fut + Send + Sync + 'static,
         Fut: Future + Send,
@@ -44,5 +45,4 @@ pub trait EventRegistrar {
     }
 }
 
-impl EventRegistrar for PluginBuilder {
-}
+impl EventRegistrar for PluginBuilder {}
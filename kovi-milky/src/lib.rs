@@ -4,6 +4,8 @@ pub mod event_registrar;
 pub mod message_trait;
 pub mod milky_api;
 pub mod milky_message;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 // ── Driver ──
 pub use driver::MilkyDriver;
@@ -0,0 +1,91 @@
+//! 用 `kovi::testing` 测试 Milky 插件
+//!
+//! 需要启用 `testing` feature。
+
+use crate::MsgEvent;
+use kovi::testing::{MockDriver, MockHandle};
+use serde_json::{Value, json};
+use std::sync::atomic::{AtomicI64, Ordering};
+
+/// 假事件中机器人自己的账号
+pub const SELF_ID: i64 = 10000;
+
+static MESSAGE_SEQ: AtomicI64 = AtomicI64::new(1);
+
+/// 以 Milky 的 [`MsgEvent`] 作为消息事件的 [`MockDriver`]
+pub fn mock_driver() -> (MockDriver, MockHandle) {
+    MockDriver::new::<MsgEvent>()
+}
+
+/// 好友发来的一条纯文本消息
+pub fn friend_msg(user_id: i64, text: &str) -> Value {
+    msg(
+        json!({
+            "message_scene": "friend",
+            "peer_id": user_id,
+            "sender_id": user_id,
+            "friend": {
+                "user_id": user_id,
+                "nickname": format!("user{user_id}"),
+                "sex": "unknown",
+                "qid": "",
+                "remark": "",
+                "category": { "category_id": 0, "category_name": "" },
+            },
+        }),
+        text,
+    )
+}
+
+/// 群成员发来的一条纯文本消息
+pub fn group_msg(group_id: i64, user_id: i64, text: &str) -> Value {
+    msg(
+        json!({
+            "message_scene": "group",
+            "peer_id": group_id,
+            "sender_id": user_id,
+            "group": {
+                "group_id": group_id,
+                "group_name": format!("group{group_id}"),
+                "member_count": 2,
+                "max_member_count": 200,
+                "remark": "",
+                "created_time": 0,
+                "description": "",
+                "question": "",
+                "announcement": "",
+            },
+            "group_member": {
+                "user_id": user_id,
+                "nickname": format!("user{user_id}"),
+                "sex": "unknown",
+                "group_id": group_id,
+                "card": "",
+                "title": "",
+                "level": 1,
+                "role": "member",
+                "join_time": 0,
+                "last_sent_time": 0,
+            },
+        }),
+        text,
+    )
+}
+
+fn msg(fields: Value, text: &str) -> Value {
+    let time = chrono::Utc::now().timestamp();
+    let mut data = json!({
+        "time": time,
+        "message_seq": MESSAGE_SEQ.fetch_add(1, Ordering::Relaxed),
+        "segments": [{ "type": "text", "data": { "text": text } }],
+    });
+    if let (Some(data), Value::Object(fields)) = (data.as_object_mut(), fields) {
+        data.extend(fields);
+    }
+    json!({
+        "event_type": "message_receive",
+        "time": time,
+        "self_id": SELF_ID,
+        "data": data,
+    })
+}
@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use kovi::PluginBuilder;
+use kovi::testing::PluginTest;
+use kovi_milky::testing::{friend_msg, group_msg, mock_driver};
+use kovi_milky::{FriendMsgEvent, GroupMsgEvent};
+
+/// 被测试的插件：把收到的文字原样发回
+async fn main() {
+    PluginBuilder::on(|event: Arc<GroupMsgEvent>| async move {
+        event.reply(format!("group: {}", event.get_text()));
+    });
+    PluginBuilder::on(|event: Arc<FriendMsgEvent>| async move {
+        event.reply(format!("friend: {}", event.get_text()));
+    });
+}
+
+fn text(api: &kovi::bot::SendApi) -> &str {
+    api.params["message"][0]["data"]["text"]
+        .as_str()
+        .unwrap_or_default()
+}
+
+/// 假的好友与群消息都能解析为 Milky 事件。
+#[kovi::test(driver = mock_driver())]
+async fn plugin_replies_to_fake_events(test: PluginTest) {
+    main().await;
+
+    test.driver().send(group_msg(100, 2, "hi")).await;
+    let sent = test.api().wait("send_group_message", 1).await;
+    assert_eq!(sent[0].params["group_id"], 100);
+    assert_eq!(text(&sent[0]), "group: hi");
+
+    test.driver().send(friend_msg(2, "yo")).await;
+    let sent = test.api().wait("send_private_message", 1).await;
+    assert_eq!(sent[0].params["user_id"], 2);
+    assert_eq!(text(&sent[0]), "friend: yo");
+}
@@ -122,6 +122,7 @@ impl MockMilky {
         }
     }
 
+    #[allow(clippy::result_large_err)]
     async fn handle_ws(&self, stream: TcpStream) {
         let mut path = String::new();
         let ws = match accept_hdr_async(stream, |req: &Request, res: Response| {
@@ -291,11 +292,18 @@ async fn run_event_ws(mut ws: WebSocketStream<TcpStream>, mut cmd_rx: mpsc::Rece
     }
 }
 
+/// 测试中的配置与数据都在当前目录中
+fn use_current_dir() {
+    kovi::config::paths::KoviPaths::in_dir(".").set_current();
+}
+
 pub(crate) fn conf() -> KoviConf {
+    use_current_dir();
     KoviConf::new(ID::new(1i64), None, false)
 }
 
 pub(crate) fn status_file_guard() -> StatusFileGuard {
+    use_current_dir();
     StatusFileGuard {
         plugin_existed: std::path::Path::new("kovi.plugin.toml").exists(),
         conf_existed: std::path::Path::new("kovi.conf.toml").exists(),
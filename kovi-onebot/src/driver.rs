@@ -1,10 +1,12 @@
 use std::sync::Arc;
 
-use crate::driver::config::{OneBotDriverConfig, Server};
-use crate::driver::connect::api_cnt::{OneBotApiOneshotSender, OneBotSendApi};
+use crate::driver::config::{OneBotDriverConfig, Server, ServerMode};
+use crate::driver::connect::api_cnt::{OneBotApiOneshotSender, OneBotApiTx, OneBotSendApi};
+use crate::driver::connect::reverse_ws::ReverseWsState;
 use crate::event::MsgEvent;
 use kovi::bot::SendApi;
 use kovi::driver::{Driver, DriverEvent, MessageEventRegister};
+use kovi::event::id::ID;
 use kovi::futures_util;
 use log::{error, info};
 use tokio::sync::{Mutex, OnceCell, mpsc};
@@ -26,7 +28,7 @@ impl Drop for AbortOnDrop {
 
 /// 初始化一次后持有的上下文：写端 sender + 后台任务句柄
 pub(crate) struct ApiContext {
-    pub(crate) api_tx: mpsc::Sender<(OneBotSendApi, Option<OneBotApiOneshotSender>)>,
+    pub(crate) api_tx: OneBotApiTx,
     /// 字段名以 _ 开头，只用于 Drop 时自动 abort 任务
     _tasks: Vec<AbortOnDrop>,
 }
@@ -38,16 +40,27 @@ pub struct OneBotDriver {
     /// 异步 OnceCell：保证并发时只初始化一次
     ctx: Arc<OnceCell<ApiContext>>,
     pub(crate) event_tx: EventTx,
+    /// 启用重连时持有事件 WS 的后台任务，反向 WS 模式下为监听任务
+    pub(crate) event_task: parking_lot::Mutex<Option<AbortOnDrop>>,
+    /// 反向 WS 模式下的连接状态
+    pub(crate) reverse: Arc<ReverseWsState>,
+    /// HTTP 模式下调用 API 的 client，无法创建时为错误信息，在连接时返回
+    http_client: Option<Result<reqwest::Client, String>>,
 }
 
 impl OneBotDriver {
     pub fn new(config: OneBotDriverConfig) -> Self {
         let config = OneBotDriverConfig::normalize_path(config);
+        let http_client = (config.server.mode == ServerMode::Http)
+            .then(|| connect::http_api::http_client(&config.server));
 
         Self {
             server: Arc::new(config.server),
             ctx: Arc::new(OnceCell::new()),
             event_tx: Arc::new(Mutex::new(None)),
+            event_task: parking_lot::Mutex::new(None),
+            reverse: Arc::new(ReverseWsState::default()),
+            http_client,
         }
     }
 }
@@ -70,6 +83,27 @@ impl Driver for OneBotDriver {
             *guard = Some(event_tx);
         }
 
+        // 反向 WS 由 OneBot 实现主动连接，此时还没有可用的 API 连接
+        if self.server.mode == ServerMode::WsReverse {
+            return self.ws_reverse_listen(event_rx).await;
+        }
+
+        if let Some(Err(e)) = &self.http_client {
+            error!("{e}");
+            return Err(e.clone().into());
+        }
+
+        // HTTP 模式先开始监听上报，避免错过获取登录信息期间的事件
+        if self.server.mode == ServerMode::Http {
+            let stream = self.http_post_listen(event_rx).await?;
+            if self.handler_lifecycle_log_bot_enable().await.is_err() {
+                self.event_task.lock().take();
+                log::error!("Failed to initialize onebot connection");
+                return Err("Failed to initialize onebot connection".into());
+            }
+            return Ok(stream);
+        }
+
         match self.handler_lifecycle_log_bot_enable().await {
             Ok(_) => {}
             Err(_) => {
@@ -78,7 +112,7 @@ impl Driver for OneBotDriver {
             }
         };
 
-        OneBotDriver::ws_event_connect((*self.server).clone(), event_rx).await
+        self.ws_event_connect(event_rx).await
     }
 
     fn api_handler(
@@ -94,6 +128,33 @@ impl Driver for OneBotDriver {
                 > + Send,
         >,
     > {
+        if self.server.mode == ServerMode::WsReverse {
+            let api_tx = self.reverse.api_tx.lock().clone();
+            return Box::pin(async move {
+                let api_tx = api_tx.ok_or_else(|| {
+                    kovi::driver::AnyError::from(
+                        "No OneBot client is connected to the reverse WebSocket server",
+                    )
+                })?;
+                OneBotDriver::send_api_inner(api_tx, value).await
+            });
+        }
+
+        match &self.http_client {
+            Some(Ok(client)) => {
+                let client = client.clone();
+                let server = Arc::clone(&self.server);
+                return Box::pin(async move {
+                    OneBotDriver::send_api_http(value, client, server).await
+                });
+            }
+            Some(Err(e)) => {
+                let e = e.clone();
+                return Box::pin(async move { Err(e.into()) });
+            }
+            None => {}
+        }
+
         if self.ctx.initialized() {
             let ctx = Arc::clone(&self.ctx);
             Box::pin(async move {
@@ -120,6 +181,17 @@ impl Driver for OneBotDriver {
     fn message_event_register(&self) -> MessageEventRegister {
         MessageEventRegister::register::<MsgEvent>()
     }
+
+    fn private_message_api(&self, user_id: &ID, text: &str) -> Option<SendApi> {
+        Some(SendApi::new(
+            "send_msg",
+            serde_json::json!({
+                "message_type": "private",
+                "user_id": user_id,
+                "message": [{"type": "text", "data": {"text": text}}],
+            }),
+        ))
+    }
 }
 
 impl std::fmt::Display for OneBotSendApi {
@@ -135,7 +207,10 @@ impl OneBotDriver {
         let res = match self.api_handler(api_msg).await {
             Ok(v) => v,
             Err(err) => {
-                let server_url = self.server.ws_url("api");
+                let server_url = match self.server.mode {
+                    ServerMode::Http => self.server.http_url("get_login_info"),
+                    _ => self.server.ws_url("api"),
+                };
                 error!("failed to initialize api_handler (server url: {server_url}): {err}");
                 return Err(());
             }
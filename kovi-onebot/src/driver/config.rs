@@ -1,10 +1,11 @@
 use dialoguer::theme::ColorfulTheme;
 use dialoguer::{Input, Select};
+use kovi::config::overrides::{ConfigField, ConfigOverrides, FieldKind};
+use kovi::config::paths::{KoviPaths, lock_conf_file, write_conf_file};
 use kovi::error::BotBuildError;
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 use std::fs;
-use std::io::Write as _;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::path::Path;
 
@@ -27,6 +28,9 @@ impl OneBotDriverConfig {
                     format!("{}/", self.server.path)
                 },
                 all_in_one: self.server.all_in_one,
+                mode: self.server.mode,
+                reconnect: self.server.reconnect,
+                http_post: self.server.http_post,
             },
         }
     }
@@ -37,7 +41,9 @@ impl OneBotDriverConfig {
 pub struct Server {
     pub host: Host,
     pub port: u16,
+    #[serde(default)]
     pub access_token: String,
+    #[serde(default)]
     pub secure: bool,
     /// path route to ws
     #[serde(default = "default_path")]
@@ -46,6 +52,183 @@ pub struct Server {
     /// all in one single "/" endpoint
     #[serde(default)]
     pub all_in_one: bool,
+
+    /// 连接方式，默认为正向 WebSocket
+    #[serde(default)]
+    pub mode: ServerMode,
+
+    /// 断线重连策略，默认不重连。只对正向 WebSocket 生效
+    #[serde(default)]
+    pub reconnect: ReconnectConfig,
+
+    /// HTTP POST 事件上报的监听配置。只对 `mode = "http"` 生效
+    #[serde(default)]
+    pub http_post: HttpPostConfig,
+}
+
+/// 与 OneBot 服务端的连接方式
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerMode {
+    /// 正向 WebSocket：Kovi 连接 OneBot 服务端的 `/api` 与 `/event`
+    #[default]
+    Ws,
+    /// 反向 WebSocket：Kovi 在 `host:port` 上监听，由 OneBot 实现连接 Kovi
+    ///
+    /// 接受 `{path}`（Universal）、`{path}api`、`{path}event` 三种连接，
+    /// 优先以 `X-Client-Role` 请求头判断连接角色。
+    /// 设置了 `access_token` 时校验 `Authorization` 请求头或 `access_token` 查询参数，
+    /// 所有连接都必须带有一致的 `X-Self-ID` 请求头。
+    ///
+    /// 不支持 TLS，`secure` 会被忽略，需要时请使用反向代理。
+    WsReverse,
+    /// HTTP：API 以 `POST {path}<action>` 调用 OneBot 服务端，
+    /// 事件由 OneBot 实现 POST 到 Kovi 内置的 HTTP 服务（见 [`HttpPostConfig`]）
+    ///
+    /// 不维持长连接，适合无法保持长连接的网络环境。
+    Http,
+}
+
+impl ServerMode {
+    /// 配置文件中的写法
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ServerMode::Ws => "ws",
+            ServerMode::WsReverse => "ws_reverse",
+            ServerMode::Http => "http",
+        }
+    }
+}
+
+/// 断线重连策略
+///
+/// 启用后，事件 WS 与 API WS 断开时不会再让 Bot 退出，而是按指数退避重新建立连接。
+///
+/// 第 n 次重试前等待 `min(initial_delay_ms * multiplier^(n-1), max_delay_ms)`，
+/// 再在此基础上随机浮动 `±jitter` 的比例。
+///
+/// ```toml
+/// [server.reconnect]
+/// enable = true
+/// initial_delay_ms = 1000
+/// max_delay_ms = 60000
+/// multiplier = 2.0
+/// jitter = 0.2
+/// # max_retries = 10
+/// ```
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ReconnectConfig {
+    /// 是否启用断线重连
+    #[serde(default)]
+    pub enable: bool,
+    /// 第一次重试前的等待时间（毫秒）
+    #[serde(default = "default_initial_delay_ms")]
+    pub initial_delay_ms: u64,
+    /// 两次重试之间最长的等待时间（毫秒）
+    #[serde(default = "default_max_delay_ms")]
+    pub max_delay_ms: u64,
+    /// 每次重试后等待时间的倍率
+    #[serde(default = "default_multiplier")]
+    pub multiplier: f64,
+    /// 随机浮动比例，取值 `0.0..=1.0`
+    #[serde(default = "default_jitter")]
+    pub jitter: f64,
+    /// 单次断线后最多重试多少次，`None` 为无限重试。重试耗尽后 Bot 退出
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+}
+
+fn default_initial_delay_ms() -> u64 {
+    1000
+}
+
+fn default_max_delay_ms() -> u64 {
+    60_000
+}
+
+fn default_multiplier() -> f64 {
+    2.0
+}
+
+fn default_jitter() -> f64 {
+    0.2
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        ReconnectConfig {
+            enable: false,
+            initial_delay_ms: default_initial_delay_ms(),
+            max_delay_ms: default_max_delay_ms(),
+            multiplier: default_multiplier(),
+            jitter: default_jitter(),
+            max_retries: None,
+        }
+    }
+}
+
+impl ReconnectConfig {
+    /// 启用重连，其余参数使用默认值
+    pub fn enabled() -> Self {
+        ReconnectConfig {
+            enable: true,
+            ..Default::default()
+        }
+    }
+
+    /// 第 `attempt` 次重试（从 1 开始）前不含随机浮动的等待时间
+    pub fn base_delay(&self, attempt: u32) -> std::time::Duration {
+        let exp = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let delay = self.initial_delay_ms as f64 * self.multiplier.max(1.0).powi(exp);
+        let delay = delay.min(self.max_delay_ms as f64).max(0.0);
+        std::time::Duration::from_millis(delay as u64)
+    }
+}
+
+/// HTTP POST 事件上报的监听配置
+///
+/// 设置了 `secret` 时，会以 HMAC-SHA1 校验请求体与 `X-Signature` 请求头，不一致的上报会被拒绝。
+///
+/// ```toml
+/// [server.http_post]
+/// host = "127.0.0.1"
+/// port = 8080
+/// path = "/"
+/// secret = ""
+/// ```
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct HttpPostConfig {
+    /// 监听地址
+    #[serde(default = "default_http_post_host")]
+    pub host: Host,
+    /// 监听端口
+    #[serde(default = "default_http_post_port")]
+    pub port: u16,
+    /// 接收上报的路径
+    #[serde(default = "default_path")]
+    pub path: String,
+    /// 与 OneBot 实现约定的签名密钥，为空时不校验
+    #[serde(default)]
+    pub secret: String,
+}
+
+fn default_http_post_host() -> Host {
+    Host::IpAddr(IpAddr::V4(Ipv4Addr::LOCALHOST))
+}
+
+fn default_http_post_port() -> u16 {
+    8080
+}
+
+impl Default for HttpPostConfig {
+    fn default() -> Self {
+        HttpPostConfig {
+            host: default_http_post_host(),
+            port: default_http_post_port(),
+            path: default_path(),
+            secret: String::new(),
+        }
+    }
 }
 
 /// when not specified, use "/" instead.
@@ -69,8 +252,29 @@ impl Server {
             secure,
             path,
             all_in_one,
+            mode: ServerMode::default(),
+            reconnect: ReconnectConfig::default(),
+            http_post: HttpPostConfig::default(),
         }
     }
+
+    /// 设置连接方式
+    pub fn with_mode(mut self, mode: ServerMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// 设置断线重连策略
+    pub fn with_reconnect(mut self, reconnect: ReconnectConfig) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    /// 设置 HTTP POST 事件上报的监听配置
+    pub fn with_http_post(mut self, http_post: HttpPostConfig) -> Self {
+        self.http_post = http_post;
+        self
+    }
 }
 
 impl Server {
@@ -101,6 +305,24 @@ impl Server {
     }
 }
 
+impl Server {
+    /// 构建 HTTP API 的 URL，例如 `http_url("send_msg")` → `http://host:port/send_msg`
+    pub fn http_url(&self, action: &str) -> String {
+        let protocol = if self.secure { "https" } else { "http" };
+        let host = match &self.host {
+            Host::IpAddr(std::net::IpAddr::V6(ip)) => format!("[{ip}]"),
+            Host::IpAddr(ip) => ip.to_string(),
+            Host::Domain(d) => d.clone(),
+        };
+
+        format!(
+            "{protocol}://{host}:{self_port}{self_path}{action}",
+            self_port = self.port,
+            self_path = self.path,
+        )
+    }
+}
+
 impl AsRef<OneBotDriverConfig> for OneBotDriverConfig {
     fn as_ref(&self) -> &OneBotDriverConfig {
         self
@@ -212,6 +434,8 @@ fn config_file_write_and_return(file_path: &Path) -> Result<OneBotDriverConfig,
 
     let mut secure = false;
     let mut all_in_one = false;
+    let mut mode = ServerMode::default();
+    let mut http_post = HttpPostConfig::default();
     if more {
         fn select_bool(prompt: &str) -> bool {
             let items = ["No", "Yes"];
@@ -224,8 +448,43 @@ fn config_file_write_and_return(file_path: &Path) -> Result<OneBotDriverConfig,
 
             select == 1
         }
-        secure = select_bool("Enable secure connection? (WSS)");
-        all_in_one = select_bool("Use single ws api endpoint?");
+        mode = {
+            let items = [
+                "WebSocket",
+                "Reverse WebSocket (OneBot connects to Kovi)",
+                "HTTP API and HTTP POST",
+            ];
+            let select = Select::with_theme(&ColorfulTheme::default())
+                .with_prompt("How does Kovi connect to the OneBot server?")
+                .items(&items)
+                .default(0)
+                .interact()
+                .expect("unreachable");
+
+            match select {
+                0 => ServerMode::Ws,
+                1 => ServerMode::WsReverse,
+                2 => ServerMode::Http,
+                _ => unreachable!(),
+            }
+        };
+        secure = select_bool("Enable secure connection? (WSS / HTTPS)");
+        if mode != ServerMode::Http {
+            all_in_one = select_bool("Use single ws api endpoint?");
+        }
+        if mode == ServerMode::Http {
+            http_post.port = Input::with_theme(&ColorfulTheme::default())
+                .with_prompt("Which port should Kovi listen on for HTTP POST events?")
+                .default(http_post.port)
+                .interact_text()
+                .expect("unreachable");
+            http_post.secret = Input::with_theme(&ColorfulTheme::default())
+                .with_prompt("What is the secret of HTTP POST? (Optional)")
+                .default("".to_string())
+                .show_default(false)
+                .interact_text()
+                .expect("unreachable");
+        }
     }
 
     let config = OneBotDriverConfig {
@@ -236,9 +495,13 @@ fn config_file_write_and_return(file_path: &Path) -> Result<OneBotDriverConfig,
             secure,
             path,
             all_in_one,
+            mode,
+            reconnect: ReconnectConfig::default(),
+            http_post,
         },
     };
 
+    let _lock = lock_conf_file();
     let mut doc = match fs::read_to_string(file_path) {
         Ok(content) => match content.parse::<toml_edit::DocumentMut>() {
             Ok(d) => d,
@@ -263,46 +526,81 @@ fn config_file_write_and_return(file_path: &Path) -> Result<OneBotDriverConfig,
     doc["server"]["secure"] = toml_edit::value(config.server.secure);
     doc["server"]["path"] = toml_edit::value(&config.server.path);
     doc["server"]["all_in_one"] = toml_edit::value(config.server.all_in_one);
+    doc["server"]["mode"] = toml_edit::value(config.server.mode.as_str());
+    if config.server.mode == ServerMode::Http {
+        let http_post = &config.server.http_post;
+        doc["server"]["http_post"] = toml_edit::table();
+        doc["server"]["http_post"]["host"] = toml_edit::value(http_post.host.to_string());
+        doc["server"]["http_post"]["port"] = toml_edit::value(http_post.port as i64);
+        doc["server"]["http_post"]["path"] = toml_edit::value(&http_post.path);
+        doc["server"]["http_post"]["secret"] = toml_edit::value(&http_post.secret);
+    }
 
-    let file = fs::File::create(file_path)?;
-    let mut writer = std::io::BufWriter::new(file);
-    writer.write_all(doc.to_string().as_bytes())?;
+    write_conf_file(file_path, &doc.to_string())?;
 
     Ok(config)
 }
 
-/// 读取本地Kovi.conf.toml文件
+/// 可以由环境变量与命令行参数设置的 `[server]` 配置项，见 [`kovi::config::overrides`]
+pub const SERVER_FIELDS: &[ConfigField] = &[
+    ConfigField::new(
+        "host",
+        "KOVI_SERVER_HOST",
+        "--server-host",
+        FieldKind::String,
+    ),
+    ConfigField::new(
+        "port",
+        "KOVI_SERVER_PORT",
+        "--server-port",
+        FieldKind::Integer,
+    ),
+    ConfigField::new(
+        "access_token",
+        "KOVI_SERVER_ACCESS_TOKEN",
+        "--server-access-token",
+        FieldKind::String,
+    ),
+    ConfigField::new(
+        "secure",
+        "KOVI_SERVER_SECURE",
+        "--server-secure",
+        FieldKind::Bool,
+    ),
+    ConfigField::new(
+        "path",
+        "KOVI_SERVER_PATH",
+        "--server-path",
+        FieldKind::String,
+    ),
+    ConfigField::new(
+        "all_in_one",
+        "KOVI_SERVER_ALL_IN_ONE",
+        "--server-all-in-one",
+        FieldKind::Bool,
+    ),
+    ConfigField::new(
+        "mode",
+        "KOVI_SERVER_MODE",
+        "--server-mode",
+        FieldKind::String,
+    ),
+];
+
+/// 读取本地Kovi.conf.toml文件，位置见 [`KoviPaths`]
+///
+/// 文件中的配置会被环境变量与命令行参数覆盖，见 [`SERVER_FIELDS`]。
+///
+/// 文件中只有一个 `[server]`，多账号的 Bot 只能用它配置一个账号，其他账号请在代码中构造配置。
+/// 设置了覆盖时再次调用会返回 [`BotBuildError::SharedOverrides`]，因为覆盖会作用于所有账号。
 pub fn load_local_conf() -> Result<OneBotDriverConfig, BotBuildError> {
-    let path = Path::new("kovi.conf.toml");
-    let kovi_conf_file_exist = fs::metadata(path).is_ok();
-
-    #[derive(Deserialize, Serialize, Debug, Clone)]
-    struct TempKoviConfig {
-        server: Option<Server>,
+    let path = &KoviPaths::current().conf_file();
+    let overrides = ConfigOverrides::from_env_and_args();
+    overrides.claim_table("server", SERVER_FIELDS)?;
+
+    match overrides.load_table::<Server>(path, "server", SERVER_FIELDS)? {
+        Some(server) => Ok(OneBotDriverConfig { server }),
+        None => config_file_write_and_return(path)
+            .map_err(|e| BotBuildError::FileCreateError(e.to_string())),
     }
-
-    let conf_json: OneBotDriverConfig = if kovi_conf_file_exist {
-        match fs::read_to_string(path) {
-            Ok(v) => match toml::from_str::<TempKoviConfig>(&v) {
-                Ok(conf) => match conf.server {
-                    Some(server) => OneBotDriverConfig { server },
-                    None => config_file_write_and_return(path)
-                        .map_err(|e| BotBuildError::FileCreateError(e.to_string()))?,
-                },
-                Err(err) => {
-                    eprintln!("Configuration file parsing error: {err}");
-                    config_file_write_and_return(path)
-                        .map_err(|e| BotBuildError::FileCreateError(e.to_string()))?
-                }
-            },
-            Err(err) => {
-                return Err(BotBuildError::FileReadError(err.to_string()));
-            }
-        }
-    } else {
-        config_file_write_and_return(path)
-            .map_err(|e| BotBuildError::FileCreateError(e.to_string()))?
-    };
-
-    Ok(conf_json)
 }
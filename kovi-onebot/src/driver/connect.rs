@@ -1,2 +1,53 @@
+use crate::driver::EventTx;
+use crate::driver::config::Server;
+use http::HeaderValue;
+use kovi::driver::{AnyError, DriverEvent};
+use log::debug;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
+
 pub mod api_cnt;
 pub mod event_cnt;
+pub mod http_api;
+pub(crate) mod http_post;
+pub(crate) mod reconnect;
+pub(crate) mod reverse_ws;
+
+pub(crate) type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// 连接 `server.ws_url(path)`，有 access_token 时带上 Authorization 头
+pub(crate) async fn connect_ws(server: &Server, path: &str) -> Result<WsStream, AnyError> {
+    let mut request = server.ws_url(path).into_client_request()?;
+
+    if !server.access_token.is_empty() {
+        let auth = HeaderValue::from_str(&format!("Bearer {}", server.access_token))
+            .map_err(|e| format!("Invalid access_token: {e}"))?;
+        request.headers_mut().insert("Authorization", auth);
+    }
+
+    let (ws_stream, _) = connect_async(request).await?;
+    Ok(ws_stream)
+}
+
+/// 往事件通道注入一个事件，通道未初始化或已关闭时忽略
+pub(crate) async fn send_driver_event(event_tx: &EventTx, event: DriverEvent) -> bool {
+    let tx = {
+        let guard = event_tx.lock().await;
+        guard.as_ref().cloned()
+    };
+
+    match tx {
+        Some(tx) => tx.send(Ok(event)).await.is_ok(),
+        None => {
+            debug!("Event channel is not initialized, drop driver event");
+            false
+        }
+    }
+}
+
+pub(crate) async fn send_exit_event(event_tx: &EventTx) {
+    if !send_driver_event(event_tx, DriverEvent::Exit).await {
+        debug!("Failed to forward DriveEvent::Exit to event channel");
+    }
+}
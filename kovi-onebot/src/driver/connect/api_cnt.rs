@@ -1,9 +1,11 @@
 use crate::driver::config::Server;
+use crate::driver::connect::reconnect::reconnect_with_backoff;
+use crate::driver::connect::{WsStream, connect_ws, send_driver_event, send_exit_event};
 use crate::driver::{self, AbortOnDrop, EventTx, OneshotTxMap};
+use crate::event::ConnectionChannel;
 use ahash::RandomState;
 use futures_util::stream::{SplitSink, SplitStream};
 use futures_util::{SinkExt, StreamExt};
-use http::HeaderValue;
 use kovi::bot::SendApi;
 use kovi::driver::{AnyError, DriverEvent};
 use kovi::{ApiReturn, futures_util};
@@ -11,14 +13,16 @@ use log::{debug, error, warn};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::sync::Arc;
-use tokio::net::TcpStream;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::sync::{mpsc, oneshot};
+use tokio_tungstenite::WebSocketStream;
 use tokio_tungstenite::tungstenite::Message;
-use tokio_tungstenite::tungstenite::client::IntoClientRequest;
-use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
 
 pub(crate) type OneBotApiOneshotSender = oneshot::Sender<Result<OneBotApiReturn, OneBotApiReturn>>;
 type OneBotApiOneshotReceiver = oneshot::Receiver<Result<OneBotApiReturn, OneBotApiReturn>>;
+/// 把 API 请求交给连接写任务的通道
+pub(crate) type OneBotApiTx = mpsc::Sender<(OneBotSendApi, Option<OneBotApiOneshotSender>)>;
+type OneBotApiRx = mpsc::Receiver<(OneBotSendApi, Option<OneBotApiOneshotSender>)>;
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct OneBotSendApi {
@@ -65,7 +69,7 @@ pub fn rand_echo() -> String {
 impl driver::OneBotDriver {
     /// api_handler 热路径：直接接收已就绪的 Sender，不再持有 server / tasks
     pub(crate) async fn send_api_inner(
-        api_tx: mpsc::Sender<(OneBotSendApi, Option<OneBotApiOneshotSender>)>,
+        api_tx: OneBotApiTx,
         send_api: SendApi,
     ) -> Result<Result<ApiReturn, ApiReturn>, AnyError> {
         let (temp_tx, temp_rx): (OneBotApiOneshotSender, OneBotApiOneshotReceiver) =
@@ -95,47 +99,15 @@ impl driver::OneBotDriver {
         server: Arc<Server>,
         event_tx: EventTx,
     ) -> Result<driver::ApiContext, AnyError> {
-        let mut request = server
-            .ws_url("api")
-            .into_client_request()
-            .expect("invalid WS URL");
-
-        if !server.access_token.is_empty() {
-            request.headers_mut().insert(
-                "Authorization",
-                HeaderValue::from_str(&format!("Bearer {}", server.access_token))
-                    .expect("unreachable"),
-            );
-        }
-
-        let (ws_stream, _) = connect_async(request).await?;
-        let (write, read) = ws_stream.split();
+        let ws_stream = connect_ws(&server, "api").await?;
 
-        // echo -> oneshot sender 映射表，读写任务共享
-        let tx_map: OneshotTxMap = Arc::new(parking_lot::Mutex::new(ahash::HashMap::default()));
-
-        // mpsc channel：send_api_inner 把请求放进来，写任务消费
+        // mpsc channel：send_api_inner 把请求放进来，连接任务消费。断线重连时通道不变
         let (api_tx, api_rx) = mpsc::channel::<(OneBotSendApi, Option<OneBotApiOneshotSender>)>(64);
 
-        // 控制通道：读 task 通过它发送 Close / Pong 等控制帧给写 task
-        let (ctrl_tx, ctrl_rx) = mpsc::unbounded_channel::<Message>();
-
         // 后台任务句柄存入 ApiContext，随 OnceCell 一起存活，Drop 时自动 abort
-        let tasks = vec![
-            AbortOnDrop(tokio::spawn(ws_read_task(
-                read,
-                ctrl_tx,
-                Arc::clone(&tx_map),
-                Arc::clone(&event_tx),
-            ))),
-            AbortOnDrop(tokio::spawn(ws_write_task(
-                write,
-                api_rx,
-                ctrl_rx,
-                tx_map,
-                Arc::clone(&event_tx),
-            ))),
-        ];
+        let tasks = vec![AbortOnDrop(tokio::spawn(ws_api_task(
+            server, ws_stream, api_rx, event_tx,
+        )))];
 
         Ok(driver::ApiContext {
             api_tx,
@@ -144,23 +116,101 @@ impl driver::OneBotDriver {
     }
 }
 
+/// 连接任务：驱动读写任务，断线时让等待中的 API 失败，并按配置重连或通知框架退出
+async fn ws_api_task(
+    server: Arc<Server>,
+    ws_stream: WsStream,
+    mut api_rx: OneBotApiRx,
+    event_tx: EventTx,
+) {
+    // echo -> oneshot sender 映射表，读写任务共享
+    let tx_map: OneshotTxMap = Arc::new(parking_lot::Mutex::new(ahash::HashMap::default()));
+
+    let mut ws_stream = ws_stream;
+    loop {
+        let (write, read) = ws_stream.split();
+
+        // 控制通道：读 task 通过它发送 Close / Pong 等控制帧给写 task，读 task 结束时通道关闭
+        let (ctrl_tx, ctrl_rx) = mpsc::unbounded_channel::<Message>();
+
+        let read_task = AbortOnDrop(tokio::spawn(ws_read_task(
+            read,
+            ctrl_tx,
+            Arc::clone(&tx_map),
+            None,
+        )));
+        let write_exit = ws_write_task(write, &mut api_rx, ctrl_rx, Arc::clone(&tx_map)).await;
+        drop(read_task);
+
+        fail_pending_api(&tx_map);
+
+        if let WriteExit::ApiClosed = write_exit {
+            return;
+        }
+
+        if !server.reconnect.enable {
+            send_exit_event(&event_tx).await;
+            return;
+        }
+
+        ws_stream = match reconnect_with_backoff(
+            &server.reconnect,
+            ConnectionChannel::Api,
+            &event_tx,
+            || connect_ws(&server, "api"),
+        )
+        .await
+        {
+            Some(ws) => ws,
+            None => {
+                send_exit_event(&event_tx).await;
+                return;
+            }
+        };
+    }
+}
+
+/// 让所有还在等待返回的 API 立即失败，避免调用方永久挂起
+pub(crate) fn fail_pending_api(tx_map: &OneshotTxMap) {
+    let pending: Vec<_> = tx_map.lock().drain().collect();
+    if pending.is_empty() {
+        return;
+    }
+
+    warn!(
+        "API WS disconnected, {} pending API call(s) failed",
+        pending.len()
+    );
+    for (echo, sender) in pending {
+        let _ = sender.send(Err(OneBotApiReturn {
+            status: "failed".to_string(),
+            retcode: -500,
+            data: Value::Null,
+            echo,
+        }));
+    }
+}
+
 /// 读任务：从 WS 收到消息，按 echo 找到对应的 oneshot sender 并发送结果
-async fn ws_read_task(
-    read: SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+///
+/// 传入 `event_tx` 时，带有 `post_type` 的消息会作为事件转发（反向 WS 的 Universal / Event 连接）
+pub(crate) async fn ws_read_task<S>(
+    read: SplitStream<WebSocketStream<S>>,
     ctrl_tx: mpsc::UnboundedSender<Message>,
     tx_map: OneshotTxMap,
-    event_tx: EventTx,
-) {
+    event_tx: Option<EventTx>,
+) where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
     read.for_each(|msg| {
         let ctrl_tx = ctrl_tx.clone();
         let tx_map = tx_map.clone();
-        let event_tx = Arc::clone(&event_tx);
+        let event_tx = event_tx.clone();
         async move {
             let msg = match msg {
                 Ok(m) => m,
                 Err(e) => {
                     error!("WS read error: {e}");
-                    send_exit_event(&event_tx).await;
                     return;
                 }
             };
@@ -170,13 +220,26 @@ async fn ws_read_task(
                     warn!("API WS connection closed by remote");
                     // 回发 Close 完成关闭握手
                     let _ = ctrl_tx.send(Message::Close(frame));
-                    send_exit_event(&event_tx).await;
                 }
                 Message::Ping(data) => {
                     // 即使 tungstenite 内部已自动回复，兜底处理
                     let _ = ctrl_tx.send(Message::Pong(data));
                 }
                 Message::Text(text) => {
+                    if let Some(event_tx) = &event_tx {
+                        match serde_json::from_str::<Value>(&text) {
+                            Ok(value) if value.get("post_type").is_some() => {
+                                send_driver_event(event_tx, DriverEvent::Normal(value)).await;
+                                return;
+                            }
+                            Ok(_) => {}
+                            Err(e) => {
+                                log::warn!("Ignored non-JSON event payload: {e}; payload={text}");
+                                return;
+                            }
+                        }
+                    }
+
                     debug!("api recv: {text}");
 
                     let ret: OneBotApiReturn = match serde_json::from_str(&text) {
@@ -213,21 +276,34 @@ async fn ws_read_task(
     })
     .await;
 
-    // 对端异常断开时流直接结束，没有 Close 帧，同样要通知框架退出
-    send_exit_event(&event_tx).await;
+    // 对端异常断开时流直接结束，没有 Close 帧。ctrl_tx 在这里被 drop，写任务据此得知连接已断开
+}
+
+/// 写任务的结束原因
+pub(crate) enum WriteExit {
+    /// 连接已断开
+    Disconnected,
+    /// 驱动已销毁，不再有 API 请求
+    ApiClosed,
 }
 
 /// 写任务：从 mpsc 收到请求 / 控制帧，写请求入 map 后通过 WS 发出
-async fn ws_write_task(
-    mut write: SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>,
-    mut api_rx: mpsc::Receiver<(OneBotSendApi, Option<OneBotApiOneshotSender>)>,
+pub(crate) async fn ws_write_task<S>(
+    mut write: SplitSink<WebSocketStream<S>, Message>,
+    api_rx: &mut OneBotApiRx,
     mut ctrl_rx: mpsc::UnboundedReceiver<Message>,
     tx_map: OneshotTxMap,
-    event_tx: EventTx,
-) {
+) -> WriteExit
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
     loop {
         tokio::select! {
-            Some((api_msg, return_tx)) = api_rx.recv() => {
+            api = api_rx.recv() => {
+                let Some((api_msg, return_tx)) = api else {
+                    return WriteExit::ApiClosed;
+                };
+
                 debug!("api send: {api_msg}");
 
                 if let Some(tx) = return_tx {
@@ -236,31 +312,20 @@ async fn ws_write_task(
 
                 if let Err(e) = write.send(Message::text(api_msg.to_string())).await {
                     error!("WS write error: {e}");
-                    send_exit_event(&event_tx).await;
-                    return;
+                    return WriteExit::Disconnected;
                 }
             }
-            Some(msg) = ctrl_rx.recv() => {
+            msg = ctrl_rx.recv() => {
+                // 读任务已结束，说明连接已断开
+                let Some(msg) = msg else {
+                    return WriteExit::Disconnected;
+                };
+
                 if let Err(e) = write.send(msg).await {
                     error!("WS write error (control): {e}");
-                    send_exit_event(&event_tx).await;
-                    return;
+                    return WriteExit::Disconnected;
                 }
             }
-            else => break,
         }
     }
 }
-
-async fn send_exit_event(event_tx: &EventTx) {
-    let tx = {
-        let guard = event_tx.lock().await;
-        guard.as_ref().cloned()
-    };
-
-    if let Some(tx) = tx
-        && tx.send(Ok(DriverEvent::Exit)).await.is_err()
-    {
-        debug!("Failed to forward DriveEvent::Exit to event channel");
-    }
-}
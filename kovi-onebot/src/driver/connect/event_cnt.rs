@@ -1,18 +1,20 @@
 use crate::driver::config::Server;
-use crate::driver::{self};
+use crate::driver::connect::reconnect::reconnect_with_backoff;
+use crate::driver::connect::{WsStream, connect_ws, send_driver_event, send_exit_event};
+use crate::driver::{self, AbortOnDrop, EventTx};
+use crate::event::ConnectionChannel;
 use futures_util::stream::Select;
 use futures_util::{SinkExt, StreamExt, stream};
-use http::HeaderValue;
 use kovi::driver::{AnyError, DriverEvent};
 use kovi::futures_util;
+use log::warn;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
-use tokio::net::TcpStream;
-use tokio_tungstenite::tungstenite::client::IntoClientRequest;
-use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async, tungstenite};
+use tokio_tungstenite::tungstenite;
 
 struct WsEventStream {
-    ws: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    ws: WsStream,
     closed: bool,
 }
 
@@ -72,7 +74,7 @@ impl futures_util::Stream for WsEventStream {
 
 impl driver::OneBotDriver {
     pub(crate) async fn ws_event_connect(
-        server: Server,
+        &self,
         event_rx: tokio::sync::mpsc::Receiver<Result<DriverEvent, AnyError>>,
     ) -> Result<
         std::pin::Pin<
@@ -82,31 +84,73 @@ impl driver::OneBotDriver {
         >,
         kovi::driver::AnyError,
     > {
-        let mut request = server
-            .ws_url("event")
-            .into_client_request()
-            .expect("invalid WS URL");
+        let ws_stream = connect_ws(&self.server, "event").await?;
 
-        if !server.access_token.is_empty() {
-            request.headers_mut().insert(
-                "Authorization",
-                HeaderValue::from_str(&format!("Bearer {}", server.access_token))
-                    .expect("unreachable"),
-            );
-        }
+        let injected_stream = stream::unfold(event_rx, |mut rx| async move {
+            rx.recv().await.map(|item| (item, rx))
+        });
+
+        // 启用重连时由后台任务持有 WS，断线后重连，事件统一经注入通道转发
+        if self.server.reconnect.enable {
+            let task = tokio::spawn(ws_event_reconnect_task(
+                Arc::clone(&self.server),
+                ws_stream,
+                Arc::clone(&self.event_tx),
+            ));
+            *self.event_task.lock() = Some(AbortOnDrop(task));
 
-        let (ws_stream, _) = connect_async(request).await?;
+            return Ok(Box::pin(injected_stream));
+        }
 
         let ws_stream = WsEventStream {
             ws: ws_stream,
             closed: false,
         };
 
-        let injected_stream = stream::unfold(event_rx, |mut rx| async move {
-            rx.recv().await.map(|item| (item, rx))
-        });
         let stream: Select<_, _> = stream::select(ws_stream, injected_stream);
 
         Ok(Box::pin(stream))
     }
 }
+
+/// 重连任务：转发事件 WS 的消息，断线后按退避策略重连，重试耗尽时通知框架退出
+async fn ws_event_reconnect_task(server: Arc<Server>, ws_stream: WsStream, event_tx: EventTx) {
+    let mut ws_stream = ws_stream;
+    loop {
+        let mut stream = WsEventStream {
+            ws: ws_stream,
+            closed: false,
+        };
+
+        while let Some(event) = stream.next().await {
+            match event {
+                Ok(DriverEvent::Normal(value)) => {
+                    if !send_driver_event(&event_tx, DriverEvent::Normal(value)).await {
+                        // 事件通道已关闭，没有人再消费事件
+                        return;
+                    }
+                }
+                Ok(DriverEvent::Exit) => break,
+                Err(e) => {
+                    warn!("Event WS error: {e}");
+                    break;
+                }
+            }
+        }
+
+        ws_stream = match reconnect_with_backoff(
+            &server.reconnect,
+            ConnectionChannel::Event,
+            &event_tx,
+            || connect_ws(&server, "event"),
+        )
+        .await
+        {
+            Some(ws) => ws,
+            None => {
+                send_exit_event(&event_tx).await;
+                return;
+            }
+        };
+    }
+}
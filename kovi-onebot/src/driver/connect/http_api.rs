@@ -0,0 +1,123 @@
+use crate::driver;
+use crate::driver::config::Server;
+use http::header::{AUTHORIZATION, CONTENT_TYPE};
+use kovi::ApiReturn;
+use kovi::bot::SendApi;
+use kovi::driver::AnyError;
+use log::{debug, error, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// HTTP API 的返回，与 WS 不同，没有 echo
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OneBotHttpApiReturn {
+    pub status: String,
+    pub retcode: i32,
+    #[serde(default)]
+    pub data: Value,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+impl From<OneBotHttpApiReturn> for ApiReturn {
+    fn from(value: OneBotHttpApiReturn) -> Self {
+        Self {
+            status: value.status,
+            retcode: value.retcode,
+            message: value.message,
+            data: value.data,
+        }
+    }
+}
+
+/// 构建 HTTP API 使用的 client，有 access_token 时默认带上 Authorization 头
+///
+/// access_token 不能作为请求头（如含有换行）时返回错误。
+pub(crate) fn http_client(server: &Server) -> Result<reqwest::Client, String> {
+    use reqwest::header;
+    let mut headers = header::HeaderMap::new();
+    headers.insert(
+        CONTENT_TYPE,
+        header::HeaderValue::from_static("application/json"),
+    );
+    if !server.access_token.is_empty() {
+        let auth = format!("Bearer {}", server.access_token);
+        let auth = header::HeaderValue::from_str(&auth)
+            .map_err(|e| format!("Invalid access_token: {e}"))?;
+        headers.insert(AUTHORIZATION, auth);
+    }
+
+    reqwest::Client::builder()
+        .default_headers(headers)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {e}"))
+}
+
+impl driver::OneBotDriver {
+    /// 以 `POST {path}<action>` 调用 API，每次调用都是独立的请求
+    pub(crate) async fn send_api_http(
+        send_api: SendApi,
+        client: reqwest::Client,
+        server: Arc<Server>,
+    ) -> Result<Result<ApiReturn, ApiReturn>, AnyError> {
+        let url = server.http_url(&send_api.action);
+        debug!("api send: {} {}", url, send_api.params);
+
+        let response = client
+            .post(&url)
+            .json(&send_api.params)
+            .send()
+            .await
+            .map_err(|e| {
+                format!(
+                    "API request failed [{}]: cannot connect to server (url: {}), error: {}",
+                    send_api.action, url, e
+                )
+            })?;
+
+        let status = response.status();
+        let body_bytes = response.bytes().await.map_err(|e| {
+            format!(
+                "API response read failed [{}]: cannot read response body (url: {}, status: {}), error: {}",
+                send_api.action, url, status, e
+            )
+        })?;
+
+        let res: OneBotHttpApiReturn = match serde_json::from_slice(&body_bytes) {
+            Ok(v) => v,
+            Err(decode_err) => {
+                // OneBot v11 对未知 API 返回 404，对鉴权失败返回 401 / 403，这些情况下没有 JSON
+                if !status.is_success() {
+                    warn!("Api return error [{}]: HTTP {}", send_api.action, status);
+                    return Ok(Err(ApiReturn {
+                        status: "failed".to_string(),
+                        retcode: status.as_u16() as i32,
+                        message: Some(status.to_string()),
+                        data: Value::Null,
+                    }));
+                }
+                error!(
+                    "API response parse failed [{}]: HTTP {}, body: {}",
+                    send_api.action,
+                    status,
+                    String::from_utf8_lossy(&body_bytes)
+                );
+                return Err(format!(
+                    "API response JSON parse failed [{}]: HTTP {}, url: {}, serde: {}",
+                    send_api.action, status, url, decode_err
+                )
+                .into());
+            }
+        };
+
+        debug!("api recv: {}", String::from_utf8_lossy(&body_bytes));
+
+        if res.status.to_lowercase() == "ok" {
+            Ok(Ok(ApiReturn::from(res)))
+        } else {
+            warn!("Api return error: {}", String::from_utf8_lossy(&body_bytes));
+            Ok(Err(ApiReturn::from(res)))
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use crate::driver::config::{Host, Server};
+use crate::driver::connect::send_driver_event;
+use crate::driver::{self, AbortOnDrop, EventTx};
+use futures_util::stream;
+use hmac::{Hmac, Mac};
+use http::{Method, StatusCode};
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Request, Response};
+use hyper_util::rt::TokioIo;
+use kovi::driver::{AnyError, DriverEvent};
+use kovi::futures_util;
+use log::{debug, error, info, warn};
+use serde_json::Value;
+use sha1::Sha1;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+/// 上报请求体的大小上限，超过时返回 413
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+impl driver::OneBotDriver {
+    /// 在 `http_post.host:http_post.port` 上接收 HTTP POST 上报，事件统一经注入通道转发
+    pub(crate) async fn http_post_listen(
+        &self,
+        event_rx: mpsc::Receiver<Result<DriverEvent, AnyError>>,
+    ) -> Result<
+        std::pin::Pin<
+            Box<
+                dyn futures_util::Stream<Item = Result<DriverEvent, kovi::driver::AnyError>> + Send,
+            >,
+        >,
+        kovi::driver::AnyError,
+    > {
+        let config = &self.server.http_post;
+        let listener = match &config.host {
+            Host::IpAddr(ip) => TcpListener::bind((*ip, config.port)).await?,
+            Host::Domain(domain) => TcpListener::bind((domain.as_str(), config.port)).await?,
+        };
+
+        info!(
+            "OneBot HTTP POST server listening on http://{}{}",
+            listener.local_addr()?,
+            config.path
+        );
+
+        let task = tokio::spawn(accept_loop(
+            listener,
+            Arc::clone(&self.server),
+            Arc::clone(&self.event_tx),
+        ));
+        *self.event_task.lock() = Some(AbortOnDrop(task));
+
+        let injected_stream = stream::unfold(event_rx, |mut rx| async move {
+            rx.recv().await.map(|item| (item, rx))
+        });
+
+        Ok(Box::pin(injected_stream))
+    }
+}
+
+/// 接受连接的任务。连接任务放在 JoinSet 中，本任务被 abort 时一并结束
+async fn accept_loop(listener: TcpListener, server: Arc<Server>, event_tx: EventTx) {
+    let mut conns = JoinSet::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, addr) = match accepted {
+                    Ok(v) => v,
+                    Err(e) => {
+                        error!("HTTP POST accept error: {e}");
+                        continue;
+                    }
+                };
+                conns.spawn(serve_conn(
+                    stream,
+                    addr,
+                    Arc::clone(&server),
+                    Arc::clone(&event_tx),
+                ));
+            }
+            Some(_) = conns.join_next() => {}
+        }
+    }
+}
+
+async fn serve_conn(stream: TcpStream, addr: SocketAddr, server: Arc<Server>, event_tx: EventTx) {
+    let service =
+        service_fn(move |req| handle_post(req, Arc::clone(&server), Arc::clone(&event_tx)));
+
+    if let Err(e) = http1::Builder::new()
+        .serve_connection(TokioIo::new(stream), service)
+        .await
+    {
+        debug!("HTTP POST connection with {addr} failed: {e}");
+    }
+}
+
+/// 校验路径与签名后把上报转为 `DriverEvent::Normal`，不支持快速操作，成功时返回 204
+async fn handle_post(
+    req: Request<Incoming>,
+    server: Arc<Server>,
+    event_tx: EventTx,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let config = &server.http_post;
+
+    if req.uri().path().trim_end_matches('/') != config.path.trim_end_matches('/') {
+        return Ok(response(StatusCode::NOT_FOUND));
+    }
+    if req.method() != Method::POST {
+        return Ok(response(StatusCode::METHOD_NOT_ALLOWED));
+    }
+
+    let signature = req
+        .headers()
+        .get("X-Signature")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    let body = match Limited::new(req.into_body(), MAX_BODY_SIZE).collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(e) if e.is::<LengthLimitError>() => {
+            warn!("Rejected HTTP POST event: body exceeds {MAX_BODY_SIZE} bytes");
+            return Ok(response(StatusCode::PAYLOAD_TOO_LARGE));
+        }
+        Err(e) => {
+            debug!("Failed to read HTTP POST body: {e}");
+            return Ok(response(StatusCode::BAD_REQUEST));
+        }
+    };
+
+    if !config.secret.is_empty() {
+        match signature {
+            None => {
+                warn!("Rejected HTTP POST event: missing X-Signature");
+                return Ok(response(StatusCode::UNAUTHORIZED));
+            }
+            Some(sig) if !verify_signature(&config.secret, &body, &sig) => {
+                warn!("Rejected HTTP POST event: invalid X-Signature");
+                return Ok(response(StatusCode::FORBIDDEN));
+            }
+            Some(_) => {}
+        }
+    }
+
+    let value: Value = match serde_json::from_slice(&body) {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("Ignored non-JSON HTTP POST payload: {e}");
+            return Ok(response(StatusCode::BAD_REQUEST));
+        }
+    };
+    if value.get("post_type").is_none() {
+        warn!("Ignored HTTP POST payload without post_type: {value}");
+        return Ok(response(StatusCode::BAD_REQUEST));
+    }
+
+    send_driver_event(&event_tx, DriverEvent::Normal(value)).await;
+
+    Ok(response(StatusCode::NO_CONTENT))
+}
+
+/// `X-Signature` 形如 `sha1=<hex>`，为以 secret 为密钥对请求体做的 HMAC-SHA1
+fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let Some(expected) = signature.strip_prefix("sha1=").and_then(decode_hex) else {
+        return false;
+    };
+
+    let mut mac =
+        Hmac::<Sha1>::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn response(status: StatusCode) -> Response<Full<Bytes>> {
+    let mut res = Response::new(Full::default());
+    *res.status_mut() = status;
+    res
+}
@@ -0,0 +1,77 @@
+use crate::driver::EventTx;
+use crate::driver::config::ReconnectConfig;
+use crate::driver::connect::send_driver_event;
+use crate::event::connection_event::{ConnectionChannel, ConnectionEvent, ConnectionState};
+use ahash::RandomState;
+use kovi::driver::{AnyError, DriverEvent};
+use log::{info, warn};
+use std::future::Future;
+use std::time::Duration;
+
+/// 第 `attempt` 次重试（从 1 开始）前的等待时间，已包含随机浮动
+pub(crate) fn backoff_delay(config: &ReconnectConfig, attempt: u32) -> Duration {
+    let base = config.base_delay(attempt).as_millis() as f64;
+    let jitter = config.jitter.clamp(0.0, 1.0);
+    if jitter == 0.0 {
+        return Duration::from_millis(base as u64);
+    }
+
+    // [-1.0, 1.0)
+    let rand = RandomState::new().hash_one(chrono::Utc::now().timestamp_nanos_opt()) % 20_000;
+    let factor = rand as f64 / 10_000.0 - 1.0;
+
+    Duration::from_millis((base * (1.0 + jitter * factor)).max(0.0) as u64)
+}
+
+/// 通知插件连接状态变化
+pub(crate) async fn send_connection_event(
+    event_tx: &EventTx,
+    channel: ConnectionChannel,
+    state: ConnectionState,
+    attempt: u32,
+) {
+    let value = ConnectionEvent::to_value(channel, state, attempt);
+    send_driver_event(event_tx, DriverEvent::Normal(value)).await;
+}
+
+/// 按退避策略不断调用 `connect` 直到成功，重试耗尽时返回 `None`
+pub(crate) async fn reconnect_with_backoff<T, F, Fut>(
+    config: &ReconnectConfig,
+    channel: ConnectionChannel,
+    event_tx: &EventTx,
+    mut connect: F,
+) -> Option<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, AnyError>>,
+{
+    send_connection_event(event_tx, channel, ConnectionState::Disconnected, 0).await;
+
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        if let Some(max) = config.max_retries
+            && attempt > max
+        {
+            warn!("{channel:?} WS reconnect gave up after {max} attempts");
+            return None;
+        }
+
+        let delay = backoff_delay(config, attempt);
+        info!("{channel:?} WS disconnected, reconnecting in {delay:?} (attempt {attempt})");
+        send_connection_event(event_tx, channel, ConnectionState::Reconnecting, attempt).await;
+        tokio::time::sleep(delay).await;
+
+        match connect().await {
+            Ok(v) => {
+                info!("{channel:?} WS reconnected after {attempt} attempt(s)");
+                send_connection_event(event_tx, channel, ConnectionState::Reconnected, attempt)
+                    .await;
+                return Some(v);
+            }
+            Err(e) => {
+                warn!("{channel:?} WS reconnect attempt {attempt} failed: {e}");
+            }
+        }
+    }
+}
@@ -0,0 +1,346 @@
+use crate::driver::config::{Host, Server};
+use crate::driver::connect::api_cnt::{OneBotApiTx, fail_pending_api, ws_read_task, ws_write_task};
+use crate::driver::connect::reconnect::send_connection_event;
+use crate::driver::{self, AbortOnDrop, EventTx, OneshotTxMap};
+use crate::event::{ConnectionChannel, ConnectionState};
+use futures_util::{StreamExt, stream};
+use http::StatusCode;
+use kovi::driver::{AnyError, DriverEvent};
+use kovi::futures_util;
+use log::{debug, error, info, warn};
+use std::borrow::Cow;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tokio_tungstenite::accept_hdr_async;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+
+/// 反向 WS 连接的角色
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ClientRole {
+    /// 同一条连接上收发 API 与事件
+    Universal,
+    Api,
+    Event,
+}
+
+impl ClientRole {
+    fn from_header(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "universal" => Some(ClientRole::Universal),
+            "api" => Some(ClientRole::Api),
+            "event" => Some(ClientRole::Event),
+            _ => None,
+        }
+    }
+
+    fn from_path(path: &str) -> Option<Self> {
+        match path {
+            "" => Some(ClientRole::Universal),
+            "api" => Some(ClientRole::Api),
+            "event" => Some(ClientRole::Event),
+            _ => None,
+        }
+    }
+
+    fn has_api(self) -> bool {
+        matches!(self, ClientRole::Universal | ClientRole::Api)
+    }
+
+    fn has_event(self) -> bool {
+        matches!(self, ClientRole::Universal | ClientRole::Event)
+    }
+
+    fn channels(self) -> &'static [ConnectionChannel] {
+        match self {
+            ClientRole::Universal => &[ConnectionChannel::Event, ConnectionChannel::Api],
+            ClientRole::Api => &[ConnectionChannel::Api],
+            ClientRole::Event => &[ConnectionChannel::Event],
+        }
+    }
+}
+
+/// 反向 WS 服务端的共享状态
+#[derive(Default)]
+pub(crate) struct ReverseWsState {
+    /// 当前可以发送 API 的连接
+    pub(crate) api_tx: parking_lot::Mutex<Option<OneBotApiTx>>,
+    /// 当前连接的账号，所有连接断开后清空
+    connected: parking_lot::Mutex<ConnectedBot>,
+    /// 是否曾经有过事件 / API 连接，用于区分首次连接与重连
+    event_seen: AtomicBool,
+    api_seen: AtomicBool,
+}
+
+/// 当前连接的账号与连接数
+#[derive(Default)]
+struct ConnectedBot {
+    /// 第一条连接的 X-Self-ID，之后的连接必须与之一致
+    self_id: Option<i64>,
+    connections: usize,
+}
+
+/// 通过握手校验的连接，被丢弃时减少连接数
+struct ConnectionGuard(Arc<ReverseWsState>);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        let mut connected = self.0.connected.lock();
+        connected.connections = connected.connections.saturating_sub(1);
+        if connected.connections == 0 {
+            connected.self_id = None;
+        }
+    }
+}
+
+impl ReverseWsState {
+    fn seen(&self, channel: ConnectionChannel) -> &AtomicBool {
+        match channel {
+            ConnectionChannel::Event => &self.event_seen,
+            ConnectionChannel::Api => &self.api_seen,
+        }
+    }
+}
+
+impl driver::OneBotDriver {
+    /// 在 `host:port` 上监听反向 WS 连接，事件统一经注入通道转发
+    pub(crate) async fn ws_reverse_listen(
+        &self,
+        event_rx: mpsc::Receiver<Result<DriverEvent, AnyError>>,
+    ) -> Result<
+        std::pin::Pin<
+            Box<
+                dyn futures_util::Stream<Item = Result<DriverEvent, kovi::driver::AnyError>> + Send,
+            >,
+        >,
+        kovi::driver::AnyError,
+    > {
+        let listener = match &self.server.host {
+            Host::IpAddr(ip) => TcpListener::bind((*ip, self.server.port)).await?,
+            Host::Domain(domain) => TcpListener::bind((domain.as_str(), self.server.port)).await?,
+        };
+
+        if self.server.secure {
+            warn!("Reverse WebSocket server does not support TLS, `secure` is ignored");
+        }
+        info!(
+            "OneBot reverse WebSocket server listening on ws://{}{}",
+            listener.local_addr()?,
+            self.server.path
+        );
+
+        let task = tokio::spawn(accept_loop(
+            listener,
+            Arc::clone(&self.server),
+            Arc::clone(&self.reverse),
+            Arc::clone(&self.event_tx),
+        ));
+        *self.event_task.lock() = Some(AbortOnDrop(task));
+
+        let injected_stream = stream::unfold(event_rx, |mut rx| async move {
+            rx.recv().await.map(|item| (item, rx))
+        });
+
+        Ok(Box::pin(injected_stream))
+    }
+}
+
+/// 接受连接的任务。连接任务放在 JoinSet 中，本任务被 abort 时一并结束
+async fn accept_loop(
+    listener: TcpListener,
+    server: Arc<Server>,
+    state: Arc<ReverseWsState>,
+    event_tx: EventTx,
+) {
+    let mut conns = JoinSet::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, addr) = match accepted {
+                    Ok(v) => v,
+                    Err(e) => {
+                        error!("Reverse WS accept error: {e}");
+                        continue;
+                    }
+                };
+                conns.spawn(serve_conn(
+                    stream,
+                    addr,
+                    Arc::clone(&server),
+                    Arc::clone(&state),
+                    Arc::clone(&event_tx),
+                ));
+            }
+            Some(_) = conns.join_next() => {}
+        }
+    }
+}
+
+/// 单条反向 WS 连接：握手校验后按角色收发 API 与事件
+#[allow(clippy::result_large_err)]
+async fn serve_conn(
+    stream: TcpStream,
+    addr: SocketAddr,
+    server: Arc<Server>,
+    state: Arc<ReverseWsState>,
+    event_tx: EventTx,
+) {
+    let mut handshake = None;
+    let ws = accept_hdr_async(
+        stream,
+        |req: &Request, res: Response| match check_handshake(&server, &state, req) {
+            Ok(v) => {
+                handshake = Some(v);
+                Ok(res)
+            }
+            Err((status, reason)) => {
+                warn!("Rejected reverse WS connection from {addr}: {reason}");
+                Err(error_response(status, reason))
+            }
+        },
+    )
+    .await;
+    let Some((role, self_id)) = handshake else {
+        if let Err(e) = ws {
+            debug!("Reverse WS handshake with {addr} failed: {e}");
+        }
+        return;
+    };
+    // 校验时已经计入连接数，握手失败时也需要减去
+    let connection = ConnectionGuard(Arc::clone(&state));
+    let ws = match ws {
+        Ok(ws) => ws,
+        Err(e) => {
+            debug!("Reverse WS handshake with {addr} failed: {e}");
+            return;
+        }
+    };
+
+    info!("OneBot client connected from {addr}, role: {role:?}, ID: {self_id}");
+
+    let (write, read) = ws.split();
+    let (ctrl_tx, ctrl_rx) = mpsc::unbounded_channel::<Message>();
+    let tx_map: OneshotTxMap = Arc::new(parking_lot::Mutex::new(ahash::HashMap::default()));
+    let (api_tx, mut api_rx) = mpsc::channel(64);
+
+    if role.has_api() {
+        *state.api_tx.lock() = Some(api_tx.clone());
+    }
+    for &channel in role.channels() {
+        if state.seen(channel).swap(true, Ordering::SeqCst) {
+            send_connection_event(&event_tx, channel, ConnectionState::Reconnected, 0).await;
+        }
+    }
+
+    let read_task = AbortOnDrop(tokio::spawn(ws_read_task(
+        read,
+        ctrl_tx,
+        Arc::clone(&tx_map),
+        role.has_event().then(|| Arc::clone(&event_tx)),
+    )));
+    ws_write_task(write, &mut api_rx, ctrl_rx, Arc::clone(&tx_map)).await;
+    drop(read_task);
+
+    if role.has_api() {
+        let mut current = state.api_tx.lock();
+        if current.as_ref().is_some_and(|tx| tx.same_channel(&api_tx)) {
+            *current = None;
+        }
+    }
+    fail_pending_api(&tx_map);
+    drop(connection);
+
+    info!("OneBot client {addr} disconnected, role: {role:?}");
+    for &channel in role.channels() {
+        send_connection_event(&event_tx, channel, ConnectionState::Disconnected, 0).await;
+    }
+}
+
+/// 校验路径、access_token 与 X-Self-ID，返回连接角色与 Self ID
+///
+/// 校验通过时计入连接数，调用方需要创建 [`ConnectionGuard`]。
+fn check_handshake(
+    server: &Server,
+    state: &ReverseWsState,
+    req: &Request,
+) -> Result<(ClientRole, i64), (StatusCode, &'static str)> {
+    let prefix = server.path.trim_end_matches('/');
+    let route = req
+        .uri()
+        .path()
+        .strip_prefix(prefix)
+        .filter(|p| p.is_empty() || p.starts_with('/'))
+        .map(|p| p.trim_matches('/'))
+        .and_then(ClientRole::from_path)
+        .ok_or((StatusCode::NOT_FOUND, "unknown path"))?;
+
+    if !server.access_token.is_empty() {
+        match request_token(req) {
+            None => return Err((StatusCode::UNAUTHORIZED, "missing access token")),
+            Some(token) if *token != server.access_token => {
+                return Err((StatusCode::FORBIDDEN, "invalid access token"));
+            }
+            Some(_) => {}
+        }
+    }
+
+    let role = match req.headers().get("X-Client-Role") {
+        Some(v) => v
+            .to_str()
+            .ok()
+            .and_then(ClientRole::from_header)
+            .ok_or((StatusCode::BAD_REQUEST, "invalid X-Client-Role"))?,
+        None => route,
+    };
+
+    let self_id = req
+        .headers()
+        .get("X-Self-ID")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .ok_or((StatusCode::BAD_REQUEST, "missing or invalid X-Self-ID"))?;
+
+    let mut connected = state.connected.lock();
+    match connected.self_id {
+        Some(id) if id != self_id && connected.connections > 0 => {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "X-Self-ID does not match the connected bot",
+            ));
+        }
+        _ => connected.self_id = Some(self_id),
+    }
+    connected.connections += 1;
+
+    Ok((role, self_id))
+}
+
+/// 从 `Authorization` 请求头（`Bearer` / `Token`）或 `access_token` 查询参数中取出 token
+///
+/// 查询参数中的值会先进行百分号解码。
+fn request_token(req: &Request) -> Option<Cow<'_, str>> {
+    if let Some(value) = req
+        .headers()
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+    {
+        let token = value
+            .strip_prefix("Bearer ")
+            .or_else(|| value.strip_prefix("Token "))
+            .unwrap_or(value);
+        return Some(Cow::Borrowed(token.trim()));
+    }
+
+    form_urlencoded::parse(req.uri().query()?.as_bytes())
+        .find_map(|(k, v)| (k == "access_token").then_some(v))
+}
+
+fn error_response(status: StatusCode, reason: &str) -> ErrorResponse {
+    let mut res = ErrorResponse::new(Some(reason.to_string()));
+    *res.status_mut() = status;
+    res
+}
@@ -2,28 +2,65 @@ use kovi::bot::SendApi;
 use serde::{Deserialize, Serialize};
 
 pub use admin_msg_event::AdminMsgEvent;
+pub use connection_event::{ConnectionChannel, ConnectionEvent, ConnectionState};
+pub use friend_add_event::FriendAddEvent;
+pub use friend_recall_event::FriendRecallEvent;
+pub use friend_request_event::FriendRequestEvent;
+pub use group_admin_event::{GroupAdminEvent, GroupAdminType};
+pub use group_ban_event::{GroupBanEvent, GroupBanType};
+pub use group_decrease_event::{GroupDecreaseEvent, GroupDecreaseType};
+pub use group_increase_event::{GroupIncreaseEvent, GroupIncreaseType};
 pub use group_msg_event::GroupMsgEvent;
+pub use group_recall_event::GroupRecallEvent;
+pub use group_request_event::{GroupRequestEvent, GroupRequestType};
+pub use group_upload_event::{GroupUploadEvent, GroupUploadFile};
+pub use heartbeat_event::{HeartbeatEvent, HeartbeatStatus};
+pub use honor_event::{HonorEvent, HonorType};
+use kovi::event::InternalEvent;
 use kovi::message::Message as KoviMessage;
+use kovi::types::ApiAndOptOneshot;
+pub use lucky_king_event::LuckyKingEvent;
 pub use msg_event::MsgEvent;
 pub use msg_send_from_kovi_event::{MsgSendFromKoviEvent, MsgSendFromKoviType};
 pub use msg_send_from_server_event::MsgSendFromServerEvent;
 pub use notice_event::NoticeEvent;
+pub use poke_event::PokeEvent;
 pub use private_msg_event::PrivateMsgEvent;
 pub use request_event::RequestEvent;
+use serde_json::Value;
+use tokio::sync::mpsc;
 
 #[cfg(not(feature = "cqstring"))]
 use crate::onebot_message::OneBotMessage;
 
 pub mod admin_msg_event;
+pub mod connection_event;
+pub mod friend_add_event;
+pub mod friend_recall_event;
+pub mod friend_request_event;
+pub mod group_admin_event;
+pub mod group_ban_event;
+pub mod group_decrease_event;
+pub mod group_increase_event;
 pub mod group_msg_event;
+pub mod group_recall_event;
+pub mod group_request_event;
+pub mod group_upload_event;
+pub mod heartbeat_event;
+pub mod honor_event;
 pub mod lifecycle_event;
+pub mod lucky_king_event;
 pub mod msg_event;
 pub mod msg_send_from_kovi_event;
 pub mod msg_send_from_server_event;
 pub mod notice_event;
+pub mod poke_event;
 pub mod private_msg_event;
 pub mod request_event;
 
+#[cfg(test)]
+mod test;
+
 #[derive(Debug, Copy, Clone)]
 pub enum Sex {
     Male,
@@ -78,6 +115,25 @@ impl<'de> Deserialize<'de> for PostType {
     }
 }
 
+/// 驱动事件中 `fields` 里的每个字段都等于给定值时返回其 json，用于区分各类通知、请求与元事件
+pub(crate) fn typed_event_json<'a>(
+    event: &'a InternalEvent,
+    fields: &[(&str, &str)],
+) -> Option<&'a Value> {
+    let InternalEvent::DriverEvent(json) = event else {
+        return None;
+    };
+    fields
+        .iter()
+        .all(|(key, value)| json.get(key).and_then(Value::as_str) == Some(value))
+        .then_some(json)
+}
+
+/// 反序列化事件时 `api_tx` 的占位，之后换为收到事件的账号的 sender
+pub(crate) fn detached_api_tx() -> mpsc::Sender<ApiAndOptOneshot> {
+    mpsc::channel(1).0
+}
+
 /// 满足此 trait 即可判断消息来源
 pub trait UniversalMessage {
     fn is_group(&self) -> bool;
@@ -0,0 +1,86 @@
+use kovi::bot::BotInformation;
+use kovi::event::{Event, InternalEvent};
+use kovi::types::ApiAndOptOneshot;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// 驱动注入的连接状态事件使用的 `meta_event_type`
+pub(crate) const CONNECTION_META_EVENT_TYPE: &str = "kovi_connection";
+
+/// 连接状态事件
+///
+/// 由 `OneBotDriver` 产生，并非来自 OneBot 服务端。
+///
+/// 启用断线重连（`server.reconnect.enable`）时，断线产生一次 `Disconnected`，
+/// 每次尝试重连前产生一次 `Reconnecting`，重连成功后产生一次 `Reconnected`。
+///
+/// 反向 WS 模式下，客户端断开时产生 `Disconnected`，客户端再次连接时产生 `Reconnected`。
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConnectionEvent {
+    /// 事件发生的时间戳
+    pub time: i64,
+    /// 发生状态变化的连接
+    pub channel: ConnectionChannel,
+    /// 连接状态
+    pub state: ConnectionState,
+    /// 当前是第几次重连尝试，`Disconnected` 时为 0
+    pub attempt: u32,
+}
+
+/// 驱动维护的连接
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionChannel {
+    /// 事件 WS
+    Event,
+    /// API WS
+    Api,
+}
+
+/// 连接状态
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionState {
+    /// 连接已断开
+    Disconnected,
+    /// 正在重连
+    Reconnecting,
+    /// 重连成功
+    Reconnected,
+}
+
+impl ConnectionEvent {
+    pub(crate) fn to_value(
+        channel: ConnectionChannel,
+        state: ConnectionState,
+        attempt: u32,
+    ) -> Value {
+        json!({
+            "time": chrono::Utc::now().timestamp(),
+            "post_type": "meta_event",
+            "meta_event_type": CONNECTION_META_EVENT_TYPE,
+            "channel": channel,
+            "state": state,
+            "attempt": attempt,
+        })
+    }
+}
+
+impl Event for ConnectionEvent {
+    fn de(
+        event: &InternalEvent,
+        _: &BotInformation,
+        _: &tokio::sync::mpsc::Sender<ApiAndOptOneshot>,
+    ) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        let InternalEvent::DriverEvent(json) = event else {
+            return None;
+        };
+        if json.get("meta_event_type").and_then(Value::as_str) != Some(CONNECTION_META_EVENT_TYPE) {
+            return None;
+        }
+        Self::deserialize(&**json).ok()
+    }
+}
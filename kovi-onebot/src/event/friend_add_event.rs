@@ -0,0 +1,45 @@
+use crate::event::typed_event_json;
+use kovi::bot::BotInformation;
+use kovi::error::EventBuildError;
+use kovi::event::{Event, InternalEvent};
+use kovi::types::ApiAndOptOneshot;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+/// 好友添加事件
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FriendAddEvent {
+    /// 事件发生的时间戳
+    pub time: i64,
+    /// 收到事件的机器人 登陆号
+    pub self_id: i64,
+    /// 新添加好友 QQ 号
+    pub user_id: i64,
+
+    /// 原始的onebot消息，已处理成json格式
+    #[serde(skip)]
+    pub original_json: Value,
+}
+
+impl Event for FriendAddEvent {
+    fn de(
+        event: &InternalEvent,
+        _: &BotInformation,
+        _: &mpsc::Sender<ApiAndOptOneshot>,
+    ) -> Option<Self> {
+        let json = typed_event_json(event, &[("notice_type", "friend_add")])?;
+
+        Self::new(json).ok()
+    }
+}
+
+impl FriendAddEvent {
+    pub(crate) fn new(temp: &Value) -> Result<FriendAddEvent, EventBuildError> {
+        let mut event = FriendAddEvent::deserialize(temp)
+            .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
+        event.original_json = temp.clone();
+
+        Ok(event)
+    }
+}
@@ -0,0 +1,97 @@
+use crate::event::{detached_api_tx, typed_event_json};
+use kovi::bot::runtimebot::{CanSendApi, send_api_request_with_forget};
+use kovi::bot::{BotInformation, SendApi};
+use kovi::error::EventBuildError;
+use kovi::event::{Event, InternalEvent};
+use kovi::types::ApiAndOptOneshot;
+use log::info;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::sync::mpsc;
+
+/// 加好友请求事件
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FriendRequestEvent {
+    /// 事件发生的时间戳
+    pub time: i64,
+    /// 收到事件的机器人 登陆号
+    pub self_id: i64,
+    /// 发送请求的 QQ 号
+    pub user_id: i64,
+    /// 验证信息
+    #[serde(default)]
+    pub comment: String,
+    /// 请求 flag，在调用处理请求的 API 时需要传入
+    pub flag: String,
+
+    /// 原始的onebot消息，已处理成json格式
+    #[serde(skip)]
+    pub original_json: Value,
+
+    #[serde(skip, default = "detached_api_tx")]
+    api_tx: mpsc::Sender<ApiAndOptOneshot>,
+}
+
+impl Event for FriendRequestEvent {
+    fn de(
+        event: &InternalEvent,
+        _: &BotInformation,
+        api_tx: &mpsc::Sender<ApiAndOptOneshot>,
+    ) -> Option<Self> {
+        let json = typed_event_json(event, &[("request_type", "friend")])?;
+
+        Self::new(api_tx.clone(), json).ok()
+    }
+}
+
+impl FriendRequestEvent {
+    pub(crate) fn new(
+        api_tx: mpsc::Sender<ApiAndOptOneshot>,
+        temp: &Value,
+    ) -> Result<FriendRequestEvent, EventBuildError> {
+        let mut event = FriendRequestEvent::deserialize(temp)
+            .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
+        event.original_json = temp.clone();
+        event.api_tx = api_tx;
+
+        Ok(event)
+    }
+
+    /// 同意加好友请求
+    pub fn approve(&self) {
+        self.set_request(true, "");
+    }
+
+    /// 同意加好友请求，并设置好友备注
+    pub fn approve_with_remark(&self, remark: &str) {
+        self.set_request(true, remark);
+    }
+
+    /// 拒绝加好友请求
+    ///
+    /// OneBot v11 的 `set_friend_add_request` 没有拒绝理由，`reason` 只会写入日志
+    pub fn reject(&self, reason: &str) {
+        if !reason.is_empty() {
+            info!("Reject friend request from {}: {reason}", self.user_id);
+        }
+        self.set_request(false, "");
+    }
+
+    fn set_request(&self, approve: bool, remark: &str) {
+        let send_api = SendApi::new(
+            "set_friend_add_request",
+            json!({
+                "flag": self.flag,
+                "approve": approve,
+                "remark": remark,
+            }),
+        );
+        send_api_request_with_forget(&self.api_tx, send_api);
+    }
+}
+
+impl CanSendApi for FriendRequestEvent {
+    fn __get_api_tx(&self) -> &mpsc::Sender<ApiAndOptOneshot> {
+        &self.api_tx
+    }
+}
@@ -0,0 +1,59 @@
+use crate::event::typed_event_json;
+use kovi::bot::BotInformation;
+use kovi::error::EventBuildError;
+use kovi::event::{Event, InternalEvent};
+use kovi::types::ApiAndOptOneshot;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+/// 群管理员变动事件
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GroupAdminEvent {
+    /// 事件发生的时间戳
+    pub time: i64,
+    /// 收到事件的机器人 登陆号
+    pub self_id: i64,
+    /// 事件子类型，分别表示设置和取消管理员
+    pub sub_type: GroupAdminType,
+    /// 群号
+    pub group_id: i64,
+    /// 管理员 QQ 号
+    pub user_id: i64,
+
+    /// 原始的onebot消息，已处理成json格式
+    #[serde(skip)]
+    pub original_json: Value,
+}
+
+/// 群管理员变动类型
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupAdminType {
+    /// 设置管理员
+    Set,
+    /// 取消管理员
+    Unset,
+}
+
+impl Event for GroupAdminEvent {
+    fn de(
+        event: &InternalEvent,
+        _: &BotInformation,
+        _: &mpsc::Sender<ApiAndOptOneshot>,
+    ) -> Option<Self> {
+        let json = typed_event_json(event, &[("notice_type", "group_admin")])?;
+
+        Self::new(json).ok()
+    }
+}
+
+impl GroupAdminEvent {
+    pub(crate) fn new(temp: &Value) -> Result<GroupAdminEvent, EventBuildError> {
+        let mut event = GroupAdminEvent::deserialize(temp)
+            .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
+        event.original_json = temp.clone();
+
+        Ok(event)
+    }
+}
@@ -0,0 +1,63 @@
+use crate::event::typed_event_json;
+use kovi::bot::BotInformation;
+use kovi::error::EventBuildError;
+use kovi::event::{Event, InternalEvent};
+use kovi::types::ApiAndOptOneshot;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+/// 群禁言事件
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GroupBanEvent {
+    /// 事件发生的时间戳
+    pub time: i64,
+    /// 收到事件的机器人 登陆号
+    pub self_id: i64,
+    /// 事件子类型，分别表示禁言、解除禁言
+    pub sub_type: GroupBanType,
+    /// 群号
+    pub group_id: i64,
+    /// 操作者 QQ 号
+    pub operator_id: i64,
+    /// 被禁言 QQ 号，全员禁言时为 0
+    pub user_id: i64,
+    /// 禁言时长，单位秒
+    pub duration: i64,
+
+    /// 原始的onebot消息，已处理成json格式
+    #[serde(skip)]
+    pub original_json: Value,
+}
+
+/// 群禁言类型
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBanType {
+    /// 禁言
+    Ban,
+    /// 解除禁言
+    LiftBan,
+}
+
+impl Event for GroupBanEvent {
+    fn de(
+        event: &InternalEvent,
+        _: &BotInformation,
+        _: &mpsc::Sender<ApiAndOptOneshot>,
+    ) -> Option<Self> {
+        let json = typed_event_json(event, &[("notice_type", "group_ban")])?;
+
+        Self::new(json).ok()
+    }
+}
+
+impl GroupBanEvent {
+    pub(crate) fn new(temp: &Value) -> Result<GroupBanEvent, EventBuildError> {
+        let mut event = GroupBanEvent::deserialize(temp)
+            .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
+        event.original_json = temp.clone();
+
+        Ok(event)
+    }
+}
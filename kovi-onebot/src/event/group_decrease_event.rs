@@ -0,0 +1,63 @@
+use crate::event::typed_event_json;
+use kovi::bot::BotInformation;
+use kovi::error::EventBuildError;
+use kovi::event::{Event, InternalEvent};
+use kovi::types::ApiAndOptOneshot;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+/// 群成员减少事件
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GroupDecreaseEvent {
+    /// 事件发生的时间戳
+    pub time: i64,
+    /// 收到事件的机器人 登陆号
+    pub self_id: i64,
+    /// 事件子类型，分别表示主动退群、成员被踢、登录号被踢
+    pub sub_type: GroupDecreaseType,
+    /// 群号
+    pub group_id: i64,
+    /// 操作者 QQ 号（如果是主动退群，则和 `user_id` 相同）
+    pub operator_id: i64,
+    /// 离开者 QQ 号
+    pub user_id: i64,
+
+    /// 原始的onebot消息，已处理成json格式
+    #[serde(skip)]
+    pub original_json: Value,
+}
+
+/// 群成员减少类型
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupDecreaseType {
+    /// 主动退群
+    Leave,
+    /// 成员被踢
+    Kick,
+    /// 登录号被踢
+    KickMe,
+}
+
+impl Event for GroupDecreaseEvent {
+    fn de(
+        event: &InternalEvent,
+        _: &BotInformation,
+        _: &mpsc::Sender<ApiAndOptOneshot>,
+    ) -> Option<Self> {
+        let json = typed_event_json(event, &[("notice_type", "group_decrease")])?;
+
+        Self::new(json).ok()
+    }
+}
+
+impl GroupDecreaseEvent {
+    pub(crate) fn new(temp: &Value) -> Result<GroupDecreaseEvent, EventBuildError> {
+        let mut event = GroupDecreaseEvent::deserialize(temp)
+            .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
+        event.original_json = temp.clone();
+
+        Ok(event)
+    }
+}
@@ -0,0 +1,61 @@
+use crate::event::typed_event_json;
+use kovi::bot::BotInformation;
+use kovi::error::EventBuildError;
+use kovi::event::{Event, InternalEvent};
+use kovi::types::ApiAndOptOneshot;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+/// 群成员增加事件
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GroupIncreaseEvent {
+    /// 事件发生的时间戳
+    pub time: i64,
+    /// 收到事件的机器人 登陆号
+    pub self_id: i64,
+    /// 事件子类型，分别表示管理员已同意入群、管理员邀请入群
+    pub sub_type: GroupIncreaseType,
+    /// 群号
+    pub group_id: i64,
+    /// 操作者 QQ 号
+    pub operator_id: i64,
+    /// 加入者 QQ 号
+    pub user_id: i64,
+
+    /// 原始的onebot消息，已处理成json格式
+    #[serde(skip)]
+    pub original_json: Value,
+}
+
+/// 群成员增加类型
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupIncreaseType {
+    /// 管理员已同意入群
+    Approve,
+    /// 管理员邀请入群
+    Invite,
+}
+
+impl Event for GroupIncreaseEvent {
+    fn de(
+        event: &InternalEvent,
+        _: &BotInformation,
+        _: &mpsc::Sender<ApiAndOptOneshot>,
+    ) -> Option<Self> {
+        let json = typed_event_json(event, &[("notice_type", "group_increase")])?;
+
+        Self::new(json).ok()
+    }
+}
+
+impl GroupIncreaseEvent {
+    pub(crate) fn new(temp: &Value) -> Result<GroupIncreaseEvent, EventBuildError> {
+        let mut event = GroupIncreaseEvent::deserialize(temp)
+            .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
+        event.original_json = temp.clone();
+
+        Ok(event)
+    }
+}
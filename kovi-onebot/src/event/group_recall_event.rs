@@ -0,0 +1,51 @@
+use crate::event::typed_event_json;
+use kovi::bot::BotInformation;
+use kovi::error::EventBuildError;
+use kovi::event::{Event, InternalEvent};
+use kovi::types::ApiAndOptOneshot;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+/// 群消息撤回事件
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GroupRecallEvent {
+    /// 事件发生的时间戳
+    pub time: i64,
+    /// 收到事件的机器人 登陆号
+    pub self_id: i64,
+    /// 群号
+    pub group_id: i64,
+    /// 消息发送者 QQ 号
+    pub user_id: i64,
+    /// 操作者 QQ 号
+    pub operator_id: i64,
+    /// 被撤回的消息 ID
+    pub message_id: i32,
+
+    /// 原始的onebot消息，已处理成json格式
+    #[serde(skip)]
+    pub original_json: Value,
+}
+
+impl Event for GroupRecallEvent {
+    fn de(
+        event: &InternalEvent,
+        _: &BotInformation,
+        _: &mpsc::Sender<ApiAndOptOneshot>,
+    ) -> Option<Self> {
+        let json = typed_event_json(event, &[("notice_type", "group_recall")])?;
+
+        Self::new(json).ok()
+    }
+}
+
+impl GroupRecallEvent {
+    pub(crate) fn new(temp: &Value) -> Result<GroupRecallEvent, EventBuildError> {
+        let mut event = GroupRecallEvent::deserialize(temp)
+            .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
+        event.original_json = temp.clone();
+
+        Ok(event)
+    }
+}
@@ -0,0 +1,112 @@
+use crate::event::{detached_api_tx, typed_event_json};
+use kovi::bot::runtimebot::{CanSendApi, send_api_request_with_forget};
+use kovi::bot::{BotInformation, SendApi};
+use kovi::error::EventBuildError;
+use kovi::event::{Event, InternalEvent};
+use kovi::types::ApiAndOptOneshot;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::sync::mpsc;
+
+/// 加群请求／邀请事件
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GroupRequestEvent {
+    /// 事件发生的时间戳
+    pub time: i64,
+    /// 收到事件的机器人 登陆号
+    pub self_id: i64,
+    /// 请求子类型，分别表示加群请求、邀请登录号入群
+    pub sub_type: GroupRequestType,
+    /// 群号
+    pub group_id: i64,
+    /// 发送请求的 QQ 号
+    pub user_id: i64,
+    /// 验证信息
+    #[serde(default)]
+    pub comment: String,
+    /// 请求 flag，在调用处理请求的 API 时需要传入
+    pub flag: String,
+
+    /// 原始的onebot消息，已处理成json格式
+    #[serde(skip)]
+    pub original_json: Value,
+
+    #[serde(skip, default = "detached_api_tx")]
+    api_tx: mpsc::Sender<ApiAndOptOneshot>,
+}
+
+/// 加群请求类型
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupRequestType {
+    /// 加群请求
+    Add,
+    /// 邀请登录号入群
+    Invite,
+}
+
+impl GroupRequestType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GroupRequestType::Add => "add",
+            GroupRequestType::Invite => "invite",
+        }
+    }
+}
+
+impl Event for GroupRequestEvent {
+    fn de(
+        event: &InternalEvent,
+        _: &BotInformation,
+        api_tx: &mpsc::Sender<ApiAndOptOneshot>,
+    ) -> Option<Self> {
+        let json = typed_event_json(event, &[("request_type", "group")])?;
+
+        Self::new(api_tx.clone(), json).ok()
+    }
+}
+
+impl GroupRequestEvent {
+    pub(crate) fn new(
+        api_tx: mpsc::Sender<ApiAndOptOneshot>,
+        temp: &Value,
+    ) -> Result<GroupRequestEvent, EventBuildError> {
+        let mut event = GroupRequestEvent::deserialize(temp)
+            .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
+        event.original_json = temp.clone();
+        event.api_tx = api_tx;
+
+        Ok(event)
+    }
+
+    /// 同意加群请求／邀请
+    pub fn approve(&self) {
+        self.set_request(true, "");
+    }
+
+    /// 拒绝加群请求／邀请
+    ///
+    /// `reason`: 拒绝理由，可为空
+    pub fn reject(&self, reason: &str) {
+        self.set_request(false, reason);
+    }
+
+    fn set_request(&self, approve: bool, reason: &str) {
+        let send_api = SendApi::new(
+            "set_group_add_request",
+            json!({
+                "flag": self.flag,
+                "sub_type": self.sub_type.as_str(),
+                "approve": approve,
+                "reason": reason,
+            }),
+        );
+        send_api_request_with_forget(&self.api_tx, send_api);
+    }
+}
+
+impl CanSendApi for GroupRequestEvent {
+    fn __get_api_tx(&self) -> &mpsc::Sender<ApiAndOptOneshot> {
+        &self.api_tx
+    }
+}
@@ -0,0 +1,62 @@
+use crate::event::typed_event_json;
+use kovi::bot::BotInformation;
+use kovi::error::EventBuildError;
+use kovi::event::{Event, InternalEvent};
+use kovi::types::ApiAndOptOneshot;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+/// 群文件上传事件
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GroupUploadEvent {
+    /// 事件发生的时间戳
+    pub time: i64,
+    /// 收到事件的机器人 登陆号
+    pub self_id: i64,
+    /// 群号
+    pub group_id: i64,
+    /// 发送者 QQ 号
+    pub user_id: i64,
+    /// 文件信息
+    pub file: GroupUploadFile,
+
+    /// 原始的onebot消息，已处理成json格式
+    #[serde(skip)]
+    pub original_json: Value,
+}
+
+/// 上传的群文件
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GroupUploadFile {
+    /// 文件 ID
+    pub id: String,
+    /// 文件名
+    pub name: String,
+    /// 文件大小（字节数）
+    pub size: i64,
+    /// busid（目前不清楚有什么作用）
+    pub busid: i64,
+}
+
+impl Event for GroupUploadEvent {
+    fn de(
+        event: &InternalEvent,
+        _: &BotInformation,
+        _: &mpsc::Sender<ApiAndOptOneshot>,
+    ) -> Option<Self> {
+        let json = typed_event_json(event, &[("notice_type", "group_upload")])?;
+
+        Self::new(json).ok()
+    }
+}
+
+impl GroupUploadEvent {
+    pub(crate) fn new(temp: &Value) -> Result<GroupUploadEvent, EventBuildError> {
+        let mut event = GroupUploadEvent::deserialize(temp)
+            .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
+        event.original_json = temp.clone();
+
+        Ok(event)
+    }
+}
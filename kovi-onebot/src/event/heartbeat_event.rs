@@ -0,0 +1,58 @@
+use crate::event::typed_event_json;
+use kovi::bot::BotInformation;
+use kovi::error::EventBuildError;
+use kovi::event::{Event, InternalEvent};
+use kovi::types::ApiAndOptOneshot;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+/// 心跳事件
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HeartbeatEvent {
+    /// 事件发生的时间戳
+    pub time: i64,
+    /// 收到事件的机器人 登陆号
+    pub self_id: i64,
+    /// 状态信息，与 `get_status` API 的返回相同
+    pub status: HeartbeatStatus,
+    /// 到下次心跳的间隔，单位毫秒
+    pub interval: i64,
+
+    /// 原始的onebot消息，已处理成json格式
+    #[serde(skip)]
+    pub original_json: Value,
+}
+
+/// 心跳中的状态信息
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HeartbeatStatus {
+    /// 当前 QQ 在线，`None` 表示无法查询到在线状态
+    #[serde(default)]
+    pub online: Option<bool>,
+    /// 状态符合预期，意味着各模块正常运行、功能正常，且 QQ 在线
+    #[serde(default)]
+    pub good: bool,
+}
+
+impl Event for HeartbeatEvent {
+    fn de(
+        event: &InternalEvent,
+        _: &BotInformation,
+        _: &mpsc::Sender<ApiAndOptOneshot>,
+    ) -> Option<Self> {
+        let json = typed_event_json(event, &[("meta_event_type", "heartbeat")])?;
+
+        Self::new(json).ok()
+    }
+}
+
+impl HeartbeatEvent {
+    pub(crate) fn new(temp: &Value) -> Result<HeartbeatEvent, EventBuildError> {
+        let mut event = HeartbeatEvent::deserialize(temp)
+            .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
+        event.original_json = temp.clone();
+
+        Ok(event)
+    }
+}
@@ -0,0 +1,64 @@
+use crate::event::typed_event_json;
+use kovi::bot::BotInformation;
+use kovi::error::EventBuildError;
+use kovi::event::{Event, InternalEvent};
+use kovi::types::ApiAndOptOneshot;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+/// 群成员荣誉变更事件
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HonorEvent {
+    /// 事件发生的时间戳
+    pub time: i64,
+    /// 收到事件的机器人 登陆号
+    pub self_id: i64,
+    /// 群号
+    pub group_id: i64,
+    /// 荣誉类型，分别表示龙王、群聊之火、快乐源泉
+    pub honor_type: HonorType,
+    /// 成员 QQ 号
+    pub user_id: i64,
+
+    /// 原始的onebot消息，已处理成json格式
+    #[serde(skip)]
+    pub original_json: Value,
+}
+
+/// 群荣誉类型
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HonorType {
+    /// 龙王
+    Talkative,
+    /// 群聊之火
+    Performer,
+    /// 快乐源泉
+    Emotion,
+    /// 实现扩展的其他荣誉
+    #[serde(untagged)]
+    Other(String),
+}
+
+impl Event for HonorEvent {
+    fn de(
+        event: &InternalEvent,
+        _: &BotInformation,
+        _: &mpsc::Sender<ApiAndOptOneshot>,
+    ) -> Option<Self> {
+        let json = typed_event_json(event, &[("notice_type", "notify"), ("sub_type", "honor")])?;
+
+        Self::new(json).ok()
+    }
+}
+
+impl HonorEvent {
+    pub(crate) fn new(temp: &Value) -> Result<HonorEvent, EventBuildError> {
+        let mut event = HonorEvent::deserialize(temp)
+            .map_err(|e| EventBuildError::ParseError(e.to_string()))?;
+        event.original_json = temp.clone();
+
+        Ok(event)
+    }
+}
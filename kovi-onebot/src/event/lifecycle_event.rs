@@ -33,7 +33,7 @@ impl Event for LifecycleEvent {
         let InternalEvent::DriverEvent(json_str) = event else {
             return None;
         };
-        let event: LifecycleEvent = serde_json::from_value(json_str.clone()).ok()?;
+        let event = LifecycleEvent::deserialize(&**json_str).ok()?;
         if event.meta_event_type == "lifecycle" {
             Some(event)
         } else {
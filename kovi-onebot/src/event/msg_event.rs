@@ -13,6 +13,7 @@ use log::{debug, info};
 use serde::Serialize;
 use serde_json::value::Index;
 use serde_json::{self, Value, json};
+use std::sync::Arc;
 use tokio::sync::mpsc;
 
 #[derive(Debug, Clone)]
@@ -49,7 +50,7 @@ pub struct MsgEvent {
     /// 处理过的文本，会解析成人类易读形式，里面会包含\[image\]\[face\]等解析后字符串
     pub human_text: String,
     /// 原始的onebot消息，已处理成json格式
-    pub original_json: Value,
+    pub original_json: Arc<Value>,
 
     /// 不推荐的消息发送方式
     pub api_tx: mpsc::Sender<ApiAndOptOneshot>,
@@ -94,7 +95,7 @@ impl Event for MsgEvent {
 impl MsgEvent {
     pub(crate) fn new(
         api_tx: mpsc::Sender<ApiAndOptOneshot>,
-        temp: Value,
+        temp: Arc<Value>,
     ) -> Result<MsgEvent, EventBuildError> {
         let temp_object = temp.as_object().ok_or(EventBuildError::ParseError(
             "Invalid JSON object".to_string(),
@@ -595,3 +596,23 @@ impl CanSendApi for MsgEvent {
         &self.api_tx
     }
 }
+
+/// 供 `kovi::command` 等只依赖 kovi 的功能回复此事件
+#[cfg(not(feature = "cqstring"))]
+impl kovi::event::RepliableEvent for MsgEvent {
+    fn reply<T>(&self, msg: T)
+    where
+        KoviMessage: From<T>,
+        T: Serialize,
+    {
+        RepliableEvent::reply(self, msg)
+    }
+
+    fn reply_and_quote<T>(&self, msg: T)
+    where
+        KoviMessage: From<T>,
+        T: Serialize,
+    {
+        RepliableEvent::reply_and_quote(self, msg)
+    }
+}
@@ -3,12 +3,13 @@ use crate::message_trait::MessageRegistrar as _;
 use kovi::bot::runtimebot::{CanSendApi, send_api_request_with_forget};
 use kovi::bot::{BotInformation, SendApi};
 use kovi::error::EventBuildError;
-use kovi::event::{Event, InternalEvent};
+use kovi::event::{Event, InternalEvent, parse_cached};
 use kovi::types::ApiAndOptOneshot;
 use log::info;
 use serde::Serialize;
 use serde_json::value::Index;
 use serde_json::{self, Value, json};
+use std::sync::Arc;
 use tokio::sync::mpsc;
 
 #[cfg(feature = "cqstring")]
@@ -51,7 +52,7 @@ pub struct MsgSendFromServerEvent {
     /// 处理过的文本，会解析成人类易读形式，里面会包含\[image\]\[face\]等解析后字符串
     pub human_text: String,
     /// 原始的onebot消息，已处理成json格式
-    pub original_json: Value,
+    pub original_json: Arc<Value>,
 
     /// 不推荐的消息发送方式
     pub api_tx: mpsc::Sender<ApiAndOptOneshot>,
@@ -60,26 +61,19 @@ pub struct MsgSendFromServerEvent {
 impl Event for MsgSendFromServerEvent {
     fn de(
         event: &InternalEvent,
-        _: &BotInformation,
+        bot_info: &BotInformation,
         api_tx: &mpsc::Sender<ApiAndOptOneshot>,
     ) -> Option<Self> {
-        let InternalEvent::DriverEvent(json) = event else {
-            return None;
-        };
-
-        let event = Self::new(api_tx.clone(), json.clone()).ok()?;
+        let msg_event = parse_cached::<MsgEvent>(event, bot_info, api_tx)?;
+        let event = Self::from_msg_event(&msg_event).ok()?;
 
         Some(event)
     }
 }
 
 impl MsgSendFromServerEvent {
-    fn new(
-        api_tx: mpsc::Sender<ApiAndOptOneshot>,
-        json: Value,
-    ) -> Result<MsgSendFromServerEvent, EventBuildError> {
-        let msg_event = MsgEvent::new(api_tx, json)?;
-
+    /// 从已经解析好的 [`MsgEvent`] 构造
+    fn from_msg_event(msg_event: &MsgEvent) -> Result<MsgSendFromServerEvent, EventBuildError> {
         if msg_event.post_type != PostType::MessageSent {
             return Err(EventBuildError::ParseError(
                 "MsgSendFromServerEvent Not message_sent".to_string(),
@@ -89,21 +83,21 @@ impl MsgSendFromServerEvent {
         Ok(MsgSendFromServerEvent {
             time: msg_event.time,
             self_id: msg_event.self_id,
-            post_type: msg_event.post_type,
-            message_type: msg_event.message_type,
-            sub_type: msg_event.sub_type,
-            message: msg_event.message,
+            post_type: msg_event.post_type.clone(),
+            message_type: msg_event.message_type.clone(),
+            sub_type: msg_event.sub_type.clone(),
+            message: msg_event.message.clone(),
             message_id: msg_event.message_id,
             group_id: msg_event.group_id,
             user_id: msg_event.user_id,
-            anonymous: msg_event.anonymous,
-            raw_message: msg_event.raw_message,
+            anonymous: msg_event.anonymous.clone(),
+            raw_message: msg_event.raw_message.clone(),
             font: msg_event.font,
-            sender: msg_event.sender,
-            text: msg_event.text,
-            human_text: msg_event.human_text,
-            original_json: msg_event.original_json,
-            api_tx: msg_event.api_tx,
+            sender: msg_event.sender.clone(),
+            text: msg_event.text.clone(),
+            human_text: msg_event.human_text.clone(),
+            original_json: msg_event.original_json.clone(),
+            api_tx: msg_event.api_tx.clone(),
         })
     }
 }
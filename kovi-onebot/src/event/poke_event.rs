@@ -0,0 +1,55 @@
+use crate::event::typed_event_json;
+use kovi::bot::BotInformation;
+use kovi::error::EventBuildError;
+use kovi::event::{Event, InternalEvent};
+use kovi::types::ApiAndOptOneshot;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+/// 戳一戳事件
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PokeEvent {
+    /// 事件发生的时间戳
+    pub time: i64,
+    /// 收到事件的机器人 登陆号
+    pub self_id: i64,
+    /// 群号，部分实现中好友间的戳一戳没有此字段
+    #[serde(default)]
+    pub group_id: Option<i64>,
+    /// 发送者 QQ 号
+    pub user_id: i64,
+    /// 被戳者 QQ 号
+    pub target_id: i64,
+
+    /// 原始的onebot消息，已处理成json格式
+    #[serde(skip)]
+    pub original_json: Value,
+}
+
+impl Event for PokeEvent {
+    fn de(
+        event: &InternalEvent,
+        _: &BotInformation,
+        _: &mpsc::Sender<ApiAndOptOneshot>,
+    ) -> Option<Self> {
+        let json = typed_event_json(event, &[("notice_type", "notify"), ("sub_type", "poke")])?;
+
+        Self::new(json).ok()
+    }
+}
+
+impl PokeEvent {
+    pub(crate) fn new(temp: &Value) -> Result<PokeEvent, EventBuildError> {
+        let mut event =
+            PokeEvent::deserialize(temp).map_err(|e| EventBuildError::ParseError(e.to_string()))?;
+        event.original_json = temp.clone();
+
+        Ok(event)
+    }
+
+    /// 是否为群内的戳一戳
+    pub fn is_group(&self) -> bool {
+        self.group_id.is_some()
+    }
+}
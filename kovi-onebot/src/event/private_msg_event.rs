@@ -8,13 +8,14 @@ use kovi::bot::runtimebot::{CanSendApi, send_api_request_with_forget};
 use kovi::bot::{BotInformation, SendApi};
 use kovi::error::EventBuildError;
 use kovi::event::id::ref_id::RefID;
-use kovi::event::{Event, InternalEvent, MessageEventTrait};
+use kovi::event::{Event, InternalEvent, MessageEventTrait, parse_cached};
 use kovi::message::Message as KoviMessage;
 use kovi::types::ApiAndOptOneshot;
 use log::info;
 use serde::Serialize;
 use serde_json::value::Index;
 use serde_json::{self, Value, json};
+use std::sync::Arc;
 use tokio::sync::mpsc;
 
 #[cfg(feature = "cqstring")]
@@ -52,7 +53,7 @@ pub struct PrivateMsgEvent {
     /// 处理过的文本，会解析成人类易读形式，里面会包含\[image\]\[face\]等解析后字符串
     pub human_text: String,
     /// 原始的onebot消息，已处理成json格式
-    pub original_json: Value,
+    pub original_json: Arc<Value>,
 
     /// 不推荐的消息发送方式
     pub api_tx: mpsc::Sender<ApiAndOptOneshot>,
@@ -61,26 +62,19 @@ pub struct PrivateMsgEvent {
 impl Event for PrivateMsgEvent {
     fn de(
         event: &InternalEvent,
-        _: &BotInformation,
+        bot_info: &BotInformation,
         api_tx: &mpsc::Sender<ApiAndOptOneshot>,
     ) -> Option<Self> {
-        let InternalEvent::DriverEvent(json) = event else {
-            return None;
-        };
-
-        let event = Self::new(api_tx.clone(), json.clone()).ok()?;
+        let msg_event = parse_cached::<MsgEvent>(event, bot_info, api_tx)?;
+        let event = Self::from_msg_event(&msg_event).ok()?;
 
         Some(event)
     }
 }
 
 impl PrivateMsgEvent {
-    fn new(
-        api_tx: mpsc::Sender<ApiAndOptOneshot>,
-        json: Value,
-    ) -> Result<PrivateMsgEvent, EventBuildError> {
-        let msg_event = MsgEvent::new(api_tx, json)?;
-
+    /// 从已经解析好的 [`MsgEvent`] 构造
+    fn from_msg_event(msg_event: &MsgEvent) -> Result<PrivateMsgEvent, EventBuildError> {
         if msg_event.is_group() {
             return Err(EventBuildError::ParseError(
                 "PrivateMsgEvent Not Group".to_string(),
@@ -90,20 +84,20 @@ impl PrivateMsgEvent {
         Ok(PrivateMsgEvent {
             time: msg_event.time,
             self_id: msg_event.self_id,
-            post_type: msg_event.post_type,
-            message_type: msg_event.message_type,
-            sub_type: msg_event.sub_type,
-            message: msg_event.message,
+            post_type: msg_event.post_type.clone(),
+            message_type: msg_event.message_type.clone(),
+            sub_type: msg_event.sub_type.clone(),
+            message: msg_event.message.clone(),
             message_id: msg_event.message_id,
             user_id: msg_event.user_id,
-            anonymous: msg_event.anonymous,
-            raw_message: msg_event.raw_message,
+            anonymous: msg_event.anonymous.clone(),
+            raw_message: msg_event.raw_message.clone(),
             font: msg_event.font,
-            sender: msg_event.sender,
-            text: msg_event.text,
-            human_text: msg_event.human_text,
-            original_json: msg_event.original_json,
-            api_tx: msg_event.api_tx,
+            sender: msg_event.sender.clone(),
+            text: msg_event.text.clone(),
+            human_text: msg_event.human_text.clone(),
+            original_json: msg_event.original_json.clone(),
+            api_tx: msg_event.api_tx.clone(),
         })
     }
 }
@@ -358,3 +352,23 @@ impl MessageEventTrait for PrivateMsgEvent {
         None
     }
 }
+
+/// 供 `kovi::command` 等只依赖 kovi 的功能回复此事件
+#[cfg(not(feature = "cqstring"))]
+impl kovi::event::RepliableEvent for PrivateMsgEvent {
+    fn reply<T>(&self, msg: T)
+    where
+        KoviMessage: From<T>,
+        T: Serialize,
+    {
+        RepliableEvent::reply(self, msg)
+    }
+
+    fn reply_and_quote<T>(&self, msg: T)
+    where
+        KoviMessage: From<T>,
+        T: Serialize,
+    {
+        RepliableEvent::reply_and_quote(self, msg)
+    }
+}
@@ -0,0 +1,232 @@
+use super::*;
+use ahash::HashSet;
+use kovi::bot::BotInformation;
+use kovi::event::Event;
+use kovi::event::id::ID;
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+fn de<T: Event>(json: Value) -> Option<T> {
+    let bot_info = BotInformation::build(ID::new(0), HashSet::default());
+    let (api_tx, _api_rx) = mpsc::channel(1);
+    T::de(
+        &InternalEvent::DriverEvent(Arc::new(json)),
+        &bot_info,
+        &api_tx,
+    )
+}
+
+fn notice(notice_type: &str, fields: Value) -> Value {
+    let mut json = json!({
+        "time": 1700000000,
+        "self_id": 10000,
+        "post_type": "notice",
+        "notice_type": notice_type,
+    });
+    json.as_object_mut()
+        .unwrap()
+        .extend(fields.as_object().unwrap().clone());
+    json
+}
+
+#[test]
+fn group_member_change() {
+    let increase = notice(
+        "group_increase",
+        json!({ "sub_type": "invite", "group_id": 1, "operator_id": 2, "user_id": 3 }),
+    );
+    let event = de::<GroupIncreaseEvent>(increase.clone()).unwrap();
+    assert_eq!(event.sub_type, GroupIncreaseType::Invite);
+    assert_eq!(
+        (event.group_id, event.operator_id, event.user_id),
+        (1, 2, 3)
+    );
+    assert_eq!(event.original_json, increase);
+    // 字段相同的减少事件不会被当作增加事件
+    assert!(de::<GroupDecreaseEvent>(increase).is_none());
+
+    let decrease = notice(
+        "group_decrease",
+        json!({ "sub_type": "kick_me", "group_id": 1, "operator_id": 2, "user_id": 10000 }),
+    );
+    let event = de::<GroupDecreaseEvent>(decrease).unwrap();
+    assert_eq!(event.sub_type, GroupDecreaseType::KickMe);
+
+    let admin = notice(
+        "group_admin",
+        json!({ "sub_type": "unset", "group_id": 1, "user_id": 3 }),
+    );
+    assert_eq!(
+        de::<GroupAdminEvent>(admin).unwrap().sub_type,
+        GroupAdminType::Unset
+    );
+
+    let ban = notice(
+        "group_ban",
+        json!({ "sub_type": "lift_ban", "group_id": 1, "operator_id": 2, "user_id": 3, "duration": 0 }),
+    );
+    assert_eq!(
+        de::<GroupBanEvent>(ban).unwrap().sub_type,
+        GroupBanType::LiftBan
+    );
+}
+
+#[test]
+fn group_upload_and_recall() {
+    let upload = notice(
+        "group_upload",
+        json!({
+            "group_id": 1,
+            "user_id": 2,
+            "file": { "id": "abc", "name": "a.txt", "size": 12, "busid": 102 },
+        }),
+    );
+    let event = de::<GroupUploadEvent>(upload).unwrap();
+    assert_eq!(event.file.name, "a.txt");
+    assert_eq!(event.file.size, 12);
+
+    let group_recall = notice(
+        "group_recall",
+        json!({ "group_id": 1, "user_id": 2, "operator_id": 3, "message_id": 4 }),
+    );
+    assert_eq!(de::<GroupRecallEvent>(group_recall).unwrap().message_id, 4);
+
+    let friend_recall = notice("friend_recall", json!({ "user_id": 2, "message_id": 5 }));
+    assert_eq!(
+        de::<FriendRecallEvent>(friend_recall).unwrap().message_id,
+        5
+    );
+
+    let friend_add = notice("friend_add", json!({ "user_id": 2 }));
+    assert_eq!(de::<FriendAddEvent>(friend_add).unwrap().user_id, 2);
+}
+
+#[test]
+fn notify_sub_types() {
+    let poke = notice(
+        "notify",
+        json!({ "sub_type": "poke", "group_id": 1, "user_id": 2, "target_id": 10000 }),
+    );
+    let event = de::<PokeEvent>(poke.clone()).unwrap();
+    assert!(event.is_group());
+    assert_eq!(event.target_id, 10000);
+    assert!(de::<LuckyKingEvent>(poke.clone()).is_none());
+    assert!(de::<HonorEvent>(poke).is_none());
+
+    let friend_poke = notice(
+        "notify",
+        json!({ "sub_type": "poke", "user_id": 2, "target_id": 10000 }),
+    );
+    assert!(!de::<PokeEvent>(friend_poke).unwrap().is_group());
+
+    let lucky_king = notice(
+        "notify",
+        json!({ "sub_type": "lucky_king", "group_id": 1, "user_id": 2, "target_id": 3 }),
+    );
+    assert_eq!(de::<LuckyKingEvent>(lucky_king).unwrap().target_id, 3);
+
+    let honor = notice(
+        "notify",
+        json!({ "sub_type": "honor", "group_id": 1, "honor_type": "talkative", "user_id": 2 }),
+    );
+    assert_eq!(
+        de::<HonorEvent>(honor).unwrap().honor_type,
+        HonorType::Talkative
+    );
+
+    let unknown_honor = notice(
+        "notify",
+        json!({ "sub_type": "honor", "group_id": 1, "honor_type": "legend", "user_id": 2 }),
+    );
+    assert_eq!(
+        de::<HonorEvent>(unknown_honor).unwrap().honor_type,
+        HonorType::Other("legend".to_string())
+    );
+}
+
+#[test]
+fn request_events() {
+    let friend = json!({
+        "time": 1700000000,
+        "self_id": 10000,
+        "post_type": "request",
+        "request_type": "friend",
+        "user_id": 2,
+        "comment": "hi",
+        "flag": "flag-1",
+    });
+    let event = de::<FriendRequestEvent>(friend.clone()).unwrap();
+    assert_eq!(
+        (event.comment.as_str(), event.flag.as_str()),
+        ("hi", "flag-1")
+    );
+    assert!(de::<GroupRequestEvent>(friend).is_none());
+
+    let group = json!({
+        "time": 1700000000,
+        "self_id": 10000,
+        "post_type": "request",
+        "request_type": "group",
+        "sub_type": "invite",
+        "group_id": 1,
+        "user_id": 2,
+        "comment": "",
+        "flag": "flag-2",
+    });
+    let event = de::<GroupRequestEvent>(group).unwrap();
+    assert_eq!(event.sub_type, GroupRequestType::Invite);
+    assert_eq!(event.flag, "flag-2");
+}
+
+#[test]
+fn heartbeat() {
+    let heartbeat = json!({
+        "time": 1700000000,
+        "self_id": 10000,
+        "post_type": "meta_event",
+        "meta_event_type": "heartbeat",
+        "status": { "online": true, "good": true },
+        "interval": 5000,
+    });
+    let event = de::<HeartbeatEvent>(heartbeat).unwrap();
+    assert_eq!(event.status.online, Some(true));
+    assert_eq!(event.interval, 5000);
+
+    let lifecycle = json!({
+        "time": 1700000000,
+        "self_id": 10000,
+        "post_type": "meta_event",
+        "meta_event_type": "lifecycle",
+        "sub_type": "connect",
+    });
+    assert!(de::<HeartbeatEvent>(lifecycle).is_none());
+}
+
+#[test]
+fn derived_msg_events_share_json() {
+    let json = Arc::new(json!({
+        "time": 1700000000,
+        "self_id": 10000,
+        "post_type": "message",
+        "message_type": "group",
+        "sub_type": "normal",
+        "message_id": 1,
+        "group_id": 20000,
+        "user_id": 30000,
+        "anonymous": null,
+        "message": [{ "type": "text", "data": { "text": "hi" } }],
+        "raw_message": "hi",
+        "font": 0,
+        "sender": { "user_id": 30000, "nickname": "kovi" },
+    }));
+    let bot_info = BotInformation::build(ID::new(0), HashSet::default());
+    let (api_tx, _api_rx) = mpsc::channel(1);
+    let event = InternalEvent::DriverEvent(json.clone());
+
+    let msg = MsgEvent::de(&event, &bot_info, &api_tx).unwrap();
+    assert!(Arc::ptr_eq(&msg.original_json, &json));
+    let group = GroupMsgEvent::de(&event, &bot_info, &api_tx).unwrap();
+    assert!(Arc::ptr_eq(&group.original_json, &json));
+    assert_eq!(group["group_id"], 20000);
+}
@@ -1,13 +1,17 @@
 use crate::event::{
-    AdminMsgEvent, GroupMsgEvent, MsgEvent, MsgSendFromServerEvent, NoticeEvent, PrivateMsgEvent,
-    RequestEvent,
+    AdminMsgEvent, FriendAddEvent, FriendRecallEvent, FriendRequestEvent, GroupAdminEvent,
+    GroupBanEvent, GroupDecreaseEvent, GroupIncreaseEvent, GroupMsgEvent, GroupRecallEvent,
+    GroupRequestEvent, GroupUploadEvent, HeartbeatEvent, HonorEvent, LuckyKingEvent, MsgEvent,
+    MsgSendFromServerEvent, NoticeEvent, PokeEvent, PrivateMsgEvent, RequestEvent,
 };
+use crate::request_policy::{AddRequestEvent, RequestPolicy};
 use kovi::PluginBuilder;
+use kovi::plugin::listener::ListenerHandle;
 use std::sync::Arc;
 
 pub trait EventRegistrar {
     /// 注册事件处理函数。
-    fn on_msg<F, Fut>(handler: F)
+    fn on_msg<F, Fut>(handler: F) -> ListenerHandle
     where
         F: Fn(Arc<MsgEvent>) -> Fut + Send + Sync + 'static,
         Fut: Future + Send,
@@ -17,7 +21,7 @@ pub trait EventRegistrar {
     }
 
     /// 注册事件处理函数。
-    fn on_admin_msg<F, Fut>(handler: F)
+    fn on_admin_msg<F, Fut>(handler: F) -> ListenerHandle
     where
         F: Fn(Arc<AdminMsgEvent>) -> Fut + Send + Sync + 'static,
         Fut: Future + Send,
@@ -27,7 +31,7 @@ pub trait EventRegistrar {
     }
 
     /// 注册事件处理函数。
-    fn on_private_msg<F, Fut>(handler: F)
+    fn on_private_msg<F, Fut>(handler: F) -> ListenerHandle
     where
         F: Fn(Arc<PrivateMsgEvent>) -> Fut + Send + Sync + 'static,
         Fut: Future + Send,
@@ -37,7 +41,7 @@ pub trait EventRegistrar {
     }
 
     /// 注册事件处理函数。
-    fn on_group_msg<F, Fut>(handler: F)
+    fn on_group_msg<F, Fut>(handler: F) -> ListenerHandle
     where
         F: Fn(Arc<GroupMsgEvent>) -> Fut + Send + Sync + 'static,
         Fut: Future + Send,
@@ -50,7 +54,7 @@ pub trait EventRegistrar {
         note = "请使用 `PluginBuilder::on::(|event: Arc<MsgSendFromServerEvent>| fn())` 代替"
     )]
     /// 注册事件处理函数。
-    fn on_msg_send<F, Fut>(handler: F)
+    fn on_msg_send<F, Fut>(handler: F) -> ListenerHandle
     where
         F: Fn(Arc<MsgSendFromServerEvent>) -> Fut + Send + Sync + 'static,
         Fut: Future + Send,
@@ -60,7 +64,7 @@ pub trait EventRegistrar {
     }
 
     /// 注册事件处理函数。
-    fn on_notice<F, Fut>(handler: F)
+    fn on_notice<F, Fut>(handler: F) -> ListenerHandle
     where
         F: Fn(Arc<NoticeEvent>) -> Fut + Send + Sync + 'static,
         Fut: Future + Send,
@@ -70,7 +74,7 @@ pub trait EventRegistrar {
     }
 
     /// 注册事件处理函数。
-    fn on_request<F, Fut>(handler: F)
+    fn on_request<F, Fut>(handler: F) -> ListenerHandle
     where
         F: Fn(Arc<RequestEvent>) -> Fut + Send + Sync + 'static,
         Fut: Future + Send,
@@ -78,7 +82,162 @@ pub trait EventRegistrar {
     {
         PluginBuilder::on::<RequestEvent, _>(handler)
     }
-}
 
-impl EventRegistrar for PluginBuilder {
+    /// 注册事件处理函数。
+    fn on_group_upload<F, Fut>(handler: F) -> ListenerHandle
+    where
+        F: Fn(Arc<GroupUploadEvent>) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send,
+        Fut::Output: Send,
+    {
+        PluginBuilder::on::<GroupUploadEvent, _>(handler)
+    }
+
+    /// 注册事件处理函数。
+    fn on_group_admin<F, Fut>(handler: F) -> ListenerHandle
+    where
+        F: Fn(Arc<GroupAdminEvent>) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send,
+        Fut::Output: Send,
+    {
+        PluginBuilder::on::<GroupAdminEvent, _>(handler)
+    }
+
+    /// 注册事件处理函数。
+    fn on_group_decrease<F, Fut>(handler: F) -> ListenerHandle
+    where
+        F: Fn(Arc<GroupDecreaseEvent>) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send,
+        Fut::Output: Send,
+    {
+        PluginBuilder::on::<GroupDecreaseEvent, _>(handler)
+    }
+
+    /// 注册事件处理函数。
+    fn on_group_increase<F, Fut>(handler: F) -> ListenerHandle
+    where
+        F: Fn(Arc<GroupIncreaseEvent>) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send,
+        Fut::Output: Send,
+    {
+        PluginBuilder::on::<GroupIncreaseEvent, _>(handler)
+    }
+
+    /// 注册事件处理函数。
+    fn on_group_ban<F, Fut>(handler: F) -> ListenerHandle
+    where
+        F: Fn(Arc<GroupBanEvent>) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send,
+        Fut::Output: Send,
+    {
+        PluginBuilder::on::<GroupBanEvent, _>(handler)
+    }
+
+    /// 注册事件处理函数。
+    fn on_friend_add<F, Fut>(handler: F) -> ListenerHandle
+    where
+        F: Fn(Arc<FriendAddEvent>) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send,
+        Fut::Output: Send,
+    {
+        PluginBuilder::on::<FriendAddEvent, _>(handler)
+    }
+
+    /// 注册事件处理函数。
+    fn on_group_recall<F, Fut>(handler: F) -> ListenerHandle
+    where
+        F: Fn(Arc<GroupRecallEvent>) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send,
+        Fut::Output: Send,
+    {
+        PluginBuilder::on::<GroupRecallEvent, _>(handler)
+    }
+
+    /// 注册事件处理函数。
+    fn on_friend_recall<F, Fut>(handler: F) -> ListenerHandle
+    where
+        F: Fn(Arc<FriendRecallEvent>) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send,
+        Fut::Output: Send,
+    {
+        PluginBuilder::on::<FriendRecallEvent, _>(handler)
+    }
+
+    /// 注册事件处理函数。
+    fn on_poke<F, Fut>(handler: F) -> ListenerHandle
+    where
+        F: Fn(Arc<PokeEvent>) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send,
+        Fut::Output: Send,
+    {
+        PluginBuilder::on::<PokeEvent, _>(handler)
+    }
+
+    /// 注册事件处理函数。
+    fn on_lucky_king<F, Fut>(handler: F) -> ListenerHandle
+    where
+        F: Fn(Arc<LuckyKingEvent>) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send,
+        Fut::Output: Send,
+    {
+        PluginBuilder::on::<LuckyKingEvent, _>(handler)
+    }
+
+    /// 注册事件处理函数。
+    fn on_honor<F, Fut>(handler: F) -> ListenerHandle
+    where
+        F: Fn(Arc<HonorEvent>) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send,
+        Fut::Output: Send,
+    {
+        PluginBuilder::on::<HonorEvent, _>(handler)
+    }
+
+    /// 注册事件处理函数。
+    fn on_friend_request<F, Fut>(handler: F) -> ListenerHandle
+    where
+        F: Fn(Arc<FriendRequestEvent>) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send,
+        Fut::Output: Send,
+    {
+        PluginBuilder::on::<FriendRequestEvent, _>(handler)
+    }
+
+    /// 注册事件处理函数。
+    fn on_group_request<F, Fut>(handler: F) -> ListenerHandle
+    where
+        F: Fn(Arc<GroupRequestEvent>) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send,
+        Fut::Output: Send,
+    {
+        PluginBuilder::on::<GroupRequestEvent, _>(handler)
+    }
+
+    /// 注册事件处理函数。
+    fn on_heartbeat<F, Fut>(handler: F) -> ListenerHandle
+    where
+        F: Fn(Arc<HeartbeatEvent>) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send,
+        Fut::Output: Send,
+    {
+        PluginBuilder::on::<HeartbeatEvent, _>(handler)
+    }
+
+    /// 注册加好友、加群请求的处理策略。
+    fn request_policy<E: AddRequestEvent>(policy: RequestPolicy<E>) -> ListenerHandle {
+        let bot = PluginBuilder::get_runtime_bot();
+        let policy = Arc::new(policy);
+        PluginBuilder::on(move |event: Arc<E>| {
+            let bot = bot.clone();
+            let policy = policy.clone();
+            async move {
+                let admins = bot.get_all_admin().unwrap_or_default();
+                if let Some(decision) = policy.decide(&event, &admins) {
+                    event.respond(&decision);
+                }
+            }
+        })
+    }
 }
+
+impl EventRegistrar for PluginBuilder {}
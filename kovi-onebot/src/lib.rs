@@ -4,18 +4,27 @@ pub mod event_registrar;
 pub mod message_trait;
 pub mod onebot_api;
 pub mod onebot_message;
+pub mod request_policy;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 // ── Driver ──
 pub use driver::OneBotDriver;
-pub use driver::config::{Host, OneBotDriverConfig, Server, load_local_conf};
+pub use driver::config::{
+    Host, HttpPostConfig, OneBotDriverConfig, ReconnectConfig, Server, ServerMode, load_local_conf,
+};
 
 // ── Events ──
 pub use event::{
-    AdminMsgEvent, GroupMsgEvent, MsgEvent, MsgSendFromKoviEvent, MsgSendFromServerEvent,
-    NoticeEvent, PrivateMsgEvent, RepliableEvent, RequestEvent,
+    AdminMsgEvent, ConnectionEvent, FriendAddEvent, FriendRecallEvent, FriendRequestEvent,
+    GroupAdminEvent, GroupBanEvent, GroupDecreaseEvent, GroupIncreaseEvent, GroupMsgEvent,
+    GroupRecallEvent, GroupRequestEvent, GroupUploadEvent, HeartbeatEvent, HonorEvent,
+    LuckyKingEvent, MsgEvent, MsgSendFromKoviEvent, MsgSendFromServerEvent, NoticeEvent, PokeEvent,
+    PrivateMsgEvent, RepliableEvent, RequestEvent,
 };
 pub use event_registrar::EventRegistrar;
 pub use onebot_message::OneBotMessage;
+pub use request_policy::{RequestDecision, RequestPolicy};
 
 // ── Message builder ──
 pub use message_trait::MessageRegistrar;
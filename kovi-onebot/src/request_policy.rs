@@ -0,0 +1,193 @@
+use crate::event::{FriendRequestEvent, GroupRequestEvent, GroupRequestType};
+use kovi::event::Event;
+use kovi::event::id::ID;
+
+/// 可以被同意或拒绝的请求事件
+pub trait AddRequestEvent: Event {
+    /// 发送请求的 QQ 号
+    fn requester_id(&self) -> i64;
+
+    /// 验证信息
+    fn comment(&self) -> &str;
+
+    /// 按 `decision` 处理此请求
+    fn respond(&self, decision: &RequestDecision);
+}
+
+impl AddRequestEvent for FriendRequestEvent {
+    fn requester_id(&self) -> i64 {
+        self.user_id
+    }
+
+    fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    fn respond(&self, decision: &RequestDecision) {
+        match decision {
+            RequestDecision::Approve => self.approve(),
+            RequestDecision::ApproveWithRemark(remark) => self.approve_with_remark(remark),
+            RequestDecision::Reject(reason) => self.reject(reason),
+        }
+    }
+}
+
+impl AddRequestEvent for GroupRequestEvent {
+    fn requester_id(&self) -> i64 {
+        self.user_id
+    }
+
+    fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    fn respond(&self, decision: &RequestDecision) {
+        match decision {
+            RequestDecision::Approve => self.approve(),
+            RequestDecision::ApproveWithRemark(_) => {
+                log::warn!(
+                    "Group requests cannot carry a remark, approving the request from {} without it",
+                    self.user_id
+                );
+                self.approve()
+            }
+            RequestDecision::Reject(reason) => self.reject(reason),
+        }
+    }
+}
+
+/// 对请求的处理
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestDecision {
+    /// 同意
+    Approve,
+    /// 同意并设置好友备注，对加群请求等同于 `Approve`，并记录警告
+    ApproveWithRemark(String),
+    /// 拒绝，附带拒绝理由
+    Reject(String),
+}
+
+type Matcher<E> = Box<dyn Fn(&E, &[ID]) -> bool + Send + Sync>;
+
+/// 声明式的请求处理策略，使用 `EventRegistrar::request_policy()` 注册
+///
+/// 规则按添加顺序匹配，第一条满足的规则生效；都不满足时使用 `otherwise()` 的处理，未设置则不处理此请求。
+///
+/// # Examples
+/// ```ignore
+/// use kovi_onebot::{EventRegistrar, GroupRequestEvent, FriendRequestEvent};
+/// use kovi_onebot::request_policy::{RequestDecision, RequestPolicy};
+///
+/// PluginBuilder::request_policy(RequestPolicy::<GroupRequestEvent>::new().approve_invites_from_admins());
+///
+/// PluginBuilder::request_policy(
+///     RequestPolicy::<FriendRequestEvent>::new()
+///         .approve_from_admins()
+///         .reject_comment_unless(|comment| comment.contains("kovi"), "答案错误")
+///         .otherwise(RequestDecision::Approve),
+/// );
+/// ```
+pub struct RequestPolicy<E> {
+    rules: Vec<(Matcher<E>, RequestDecision)>,
+    fallback: Option<RequestDecision>,
+}
+
+impl<E: AddRequestEvent> Default for RequestPolicy<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: AddRequestEvent> RequestPolicy<E> {
+    pub fn new() -> Self {
+        RequestPolicy {
+            rules: Vec::new(),
+            fallback: None,
+        }
+    }
+
+    /// 满足 `predicate` 时按 `decision` 处理
+    pub fn when<F>(mut self, predicate: F, decision: RequestDecision) -> Self
+    where
+        F: Fn(&E) -> bool + Send + Sync + 'static,
+    {
+        self.rules
+            .push((Box::new(move |event, _| predicate(event)), decision));
+        self
+    }
+
+    /// 满足 `predicate` 时同意
+    pub fn approve_if<F>(self, predicate: F) -> Self
+    where
+        F: Fn(&E) -> bool + Send + Sync + 'static,
+    {
+        self.when(predicate, RequestDecision::Approve)
+    }
+
+    /// 满足 `predicate` 时拒绝
+    pub fn reject_if<F>(self, predicate: F, reason: &str) -> Self
+    where
+        F: Fn(&E) -> bool + Send + Sync + 'static,
+    {
+        self.when(predicate, RequestDecision::Reject(reason.to_string()))
+    }
+
+    /// 不满足 `predicate` 时拒绝
+    pub fn reject_unless<F>(self, predicate: F, reason: &str) -> Self
+    where
+        F: Fn(&E) -> bool + Send + Sync + 'static,
+    {
+        self.reject_if(move |event| !predicate(event), reason)
+    }
+
+    /// 验证信息不满足 `predicate` 时拒绝
+    pub fn reject_comment_unless<F>(self, predicate: F, reason: &str) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.reject_unless(move |event| predicate(event.comment()), reason)
+    }
+
+    /// 请求来自 Bot 管理员时同意
+    pub fn approve_from_admins(mut self) -> Self {
+        self.rules.push((
+            Box::new(|event, admins| is_admin(admins, event.requester_id())),
+            RequestDecision::Approve,
+        ));
+        self
+    }
+
+    /// 没有规则满足时的处理
+    pub fn otherwise(mut self, decision: RequestDecision) -> Self {
+        self.fallback = Some(decision);
+        self
+    }
+
+    /// 按规则得出对 `event` 的处理，`admins` 为 Bot 的所有管理员
+    pub fn decide(&self, event: &E, admins: &[ID]) -> Option<RequestDecision> {
+        self.rules
+            .iter()
+            .find(|(matcher, _)| matcher(event, admins))
+            .map(|(_, decision)| decision)
+            .or(self.fallback.as_ref())
+            .cloned()
+    }
+}
+
+impl RequestPolicy<GroupRequestEvent> {
+    /// Bot 管理员邀请登录号入群时同意
+    pub fn approve_invites_from_admins(mut self) -> Self {
+        self.rules.push((
+            Box::new(|event, admins| {
+                event.sub_type == GroupRequestType::Invite && is_admin(admins, event.user_id)
+            }),
+            RequestDecision::Approve,
+        ));
+        self
+    }
+}
+
+fn is_admin(admins: &[ID], user_id: i64) -> bool {
+    let user_id = user_id.to_string();
+    admins.iter().any(|admin| admin.to_string() == user_id)
+}
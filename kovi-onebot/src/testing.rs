@@ -0,0 +1,66 @@
+//! 用 `kovi::testing` 测试 OneBot 插件
+//!
+//! 需要启用 `testing` feature。
+
+use crate::MsgEvent;
+use kovi::testing::{MockDriver, MockHandle};
+use serde_json::{Value, json};
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// 假事件中机器人自己的账号
+pub const SELF_ID: i64 = 10000;
+
+static MESSAGE_ID: AtomicI32 = AtomicI32::new(1);
+
+/// 以 OneBot 的 [`MsgEvent`] 作为消息事件的 [`MockDriver`]
+pub fn mock_driver() -> (MockDriver, MockHandle) {
+    MockDriver::new::<MsgEvent>()
+}
+
+/// 好友发来的一条纯文本消息
+pub fn private_msg(user_id: i64, text: &str) -> Value {
+    msg(
+        json!({
+            "message_type": "private",
+            "sub_type": "friend",
+            "user_id": user_id,
+            "sender": { "user_id": user_id, "nickname": format!("user{user_id}") },
+        }),
+        text,
+    )
+}
+
+/// 群成员发来的一条纯文本消息
+pub fn group_msg(group_id: i64, user_id: i64, text: &str) -> Value {
+    msg(
+        json!({
+            "message_type": "group",
+            "sub_type": "normal",
+            "group_id": group_id,
+            "user_id": user_id,
+            "sender": {
+                "user_id": user_id,
+                "nickname": format!("user{user_id}"),
+                "card": "",
+                "role": "member",
+            },
+        }),
+        text,
+    )
+}
+
+fn msg(fields: Value, text: &str) -> Value {
+    let mut json = json!({
+        "time": chrono::Utc::now().timestamp(),
+        "self_id": SELF_ID,
+        "post_type": "message",
+        "message_id": MESSAGE_ID.fetch_add(1, Ordering::Relaxed),
+        "message": [{ "type": "text", "data": { "text": text } }],
+        "raw_message": text,
+        "font": 0,
+    });
+    if let (Some(json), Value::Object(fields)) = (json.as_object_mut(), fields) {
+        json.extend(fields);
+    }
+    json
+}
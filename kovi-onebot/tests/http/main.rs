@@ -0,0 +1,305 @@
+//! 模拟 OneBot HTTP API 服务端与 HTTP POST 上报，实测 HTTP 模式下的 API 调用与事件接收。
+
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use hmac::{Hmac, Mac};
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use kovi::bot::SendApi;
+use kovi::driver::{Driver, DriverEvent};
+use kovi_onebot::OneBotDriver;
+use kovi_onebot::driver::config::{Host, HttpPostConfig, OneBotDriverConfig, Server, ServerMode};
+use serde_json::{Value, json};
+use sha1::Sha1;
+use tokio::net::TcpListener;
+use tokio::time::timeout;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 一次 API 请求的记录：路径、Authorization 头、请求体
+type Recorded = (String, Option<String>, Value);
+
+/// 模拟 OneBot HTTP API：`/get_login_info` 与 `/send_msg` 成功，`/fail` 返回 failed，其余 404
+struct MockHttpApi {
+    port: u16,
+    requests: Arc<parking_lot::Mutex<Vec<Recorded>>>,
+}
+
+impl MockHttpApi {
+    async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock onebot");
+        let port = listener.local_addr().expect("local addr").port();
+        let requests = Arc::new(parking_lot::Mutex::new(Vec::new()));
+
+        let recorded = Arc::clone(&requests);
+        tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let recorded = Arc::clone(&recorded);
+                tokio::spawn(async move {
+                    let service = service_fn(move |req| answer_api(req, Arc::clone(&recorded)));
+                    let _ = http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await;
+                });
+            }
+        });
+
+        Self { port, requests }
+    }
+
+    fn requests(&self) -> Vec<Recorded> {
+        self.requests.lock().clone()
+    }
+}
+
+async fn answer_api(
+    req: Request<Incoming>,
+    recorded: Arc<parking_lot::Mutex<Vec<Recorded>>>,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let path = req.uri().path().to_string();
+    let auth = req
+        .headers()
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let body = req.into_body().collect().await.expect("body").to_bytes();
+    let params: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+    recorded.lock().push((path.clone(), auth, params));
+
+    let (status, body) = match path.as_str() {
+        "/get_login_info" => (
+            StatusCode::OK,
+            json!({ "status": "ok", "retcode": 0, "data": { "user_id": 10000, "nickname": "mock-bot" } }),
+        ),
+        "/send_msg" => (
+            StatusCode::OK,
+            json!({ "status": "ok", "retcode": 0, "data": { "message_id": 1 } }),
+        ),
+        "/fail" => (
+            StatusCode::OK,
+            json!({ "status": "failed", "retcode": 100, "data": null }),
+        ),
+        _ => return Ok(status_only(StatusCode::NOT_FOUND)),
+    };
+
+    let mut res = Response::new(Full::new(Bytes::from(body.to_string())));
+    *res.status_mut() = status;
+    Ok(res)
+}
+
+fn status_only(status: StatusCode) -> Response<Full<Bytes>> {
+    let mut res = Response::new(Full::default());
+    *res.status_mut() = status;
+    res
+}
+
+async fn unused_port() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    listener.local_addr().expect("local addr").port()
+}
+
+fn http_driver(api_port: u16, token: &str, post_port: u16, secret: &str) -> OneBotDriver {
+    OneBotDriver::new(OneBotDriverConfig {
+        server: Server::new(
+            Host::IpAddr("127.0.0.1".parse().expect("ip")),
+            api_port,
+            token.to_string(),
+            false,
+            "/".into(),
+            false,
+        )
+        .with_mode(ServerMode::Http)
+        .with_http_post(HttpPostConfig {
+            port: post_port,
+            secret: secret.to_string(),
+            ..Default::default()
+        }),
+    })
+}
+
+/// 模拟 OneBot 实现上报事件，返回 HTTP 状态码
+async fn post_event(port: u16, body: &str, signature: Option<&str>) -> StatusCode {
+    let mut request = reqwest::Client::new()
+        .post(format!("http://127.0.0.1:{port}/"))
+        .header("Content-Type", "application/json")
+        .header("X-Self-ID", "10000")
+        .body(body.to_string());
+    if let Some(signature) = signature {
+        request = request.header("X-Signature", signature);
+    }
+    let status = request.send().await.expect("post event").status();
+    StatusCode::from_u16(status.as_u16()).expect("status")
+}
+
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret.as_bytes()).expect("hmac");
+    mac.update(body.as_bytes());
+    let hex: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    format!("sha1={hex}")
+}
+
+/// API 以 `POST /<action>` 发出，参数为请求体，带上 access_token。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn api_posts_to_action_path() {
+    let api = MockHttpApi::start().await;
+    let driver = http_driver(api.port, "secret-token", unused_port().await, "");
+
+    let result = timeout(
+        CONNECT_TIMEOUT,
+        driver.api_handler(SendApi::new(
+            "send_msg",
+            json!({ "message_type": "private", "user_id": 1, "message": "hi" }),
+        )),
+    )
+    .await
+    .expect("api hung")
+    .expect("transport");
+    assert_eq!(result.expect("api ok").data["message_id"], 1);
+
+    let (path, auth, params) = api.requests().pop().expect("request recorded");
+    assert_eq!(path, "/send_msg");
+    assert_eq!(auth.as_deref(), Some("Bearer secret-token"));
+    assert_eq!(params["message"], "hi");
+}
+
+/// access_token 不能作为请求头时，连接与调用 API 返回错误而不是 panic。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn invalid_access_token_is_an_error() {
+    let driver = http_driver(unused_port().await, "bad\ntoken", unused_port().await, "");
+
+    let error = driver
+        .api_handler(SendApi::new("send_msg", json!({})))
+        .await
+        .expect_err("invalid token");
+    assert!(error.to_string().contains("access_token"), "{error}");
+    assert!(driver.event_channel().await.is_err());
+}
+
+/// status 不为 ok 或 HTTP 404 时返回 Err(ApiReturn)。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn api_failure_is_returned_as_err() {
+    let api = MockHttpApi::start().await;
+    let driver = http_driver(api.port, "", unused_port().await, "");
+
+    let failed = driver
+        .api_handler(SendApi::new("fail", json!({})))
+        .await
+        .expect("transport");
+    assert_eq!(failed.expect_err("failed status").retcode, 100);
+
+    let unknown = driver
+        .api_handler(SendApi::new("no_such_action", json!({})))
+        .await
+        .expect("transport");
+    assert_eq!(unknown.expect_err("404").retcode, 404);
+}
+
+/// 上报的事件以 DriverEvent::Normal 送入事件流。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn posted_events_feed_event_stream() {
+    let api = MockHttpApi::start().await;
+    let post_port = unused_port().await;
+    let driver = http_driver(api.port, "", post_port, "");
+    let mut stream = driver.event_channel().await.expect("event_channel");
+
+    let body = json!({ "post_type": "notice", "notice_type": "poke", "time": 1, "self_id": 10000 })
+        .to_string();
+    assert_eq!(
+        post_event(post_port, &body, None).await,
+        StatusCode::NO_CONTENT
+    );
+
+    let event = timeout(CONNECT_TIMEOUT, stream.next())
+        .await
+        .expect("event did not arrive")
+        .expect("stream ended")
+        .expect("stream error");
+    let DriverEvent::Normal(value) = event else {
+        panic!("unexpected Exit");
+    };
+    assert_eq!(value["notice_type"], "poke");
+
+    assert_eq!(
+        post_event(post_port, "not json", None).await,
+        StatusCode::BAD_REQUEST
+    );
+}
+
+/// 设置 secret 时，缺失或错误的 X-Signature 都会被拒绝。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn signature_is_checked() {
+    let api = MockHttpApi::start().await;
+    let post_port = unused_port().await;
+    let driver = http_driver(api.port, "", post_port, "post-secret");
+    let mut stream = driver.event_channel().await.expect("event_channel");
+
+    let body =
+        json!({ "post_type": "meta_event", "meta_event_type": "heartbeat", "time": 1 }).to_string();
+    assert_eq!(
+        post_event(post_port, &body, None).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        post_event(post_port, &body, Some(&sign("wrong", &body))).await,
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        post_event(post_port, &body, Some(&sign("post-secret", &body))).await,
+        StatusCode::NO_CONTENT
+    );
+
+    let event = timeout(CONNECT_TIMEOUT, stream.next())
+        .await
+        .expect("event did not arrive")
+        .expect("stream ended")
+        .expect("stream error");
+    let DriverEvent::Normal(value) = event else {
+        panic!("unexpected Exit");
+    };
+    assert_eq!(value["meta_event_type"], "heartbeat");
+}
+
+/// 超过大小上限的请求体在校验签名前被拒绝。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn oversized_body_is_rejected() {
+    let api = MockHttpApi::start().await;
+    let post_port = unused_port().await;
+    let driver = http_driver(api.port, "", post_port, "post-secret");
+    let _stream = driver.event_channel().await.expect("event_channel");
+
+    let body = " ".repeat(16 * 1024 * 1024 + 1);
+    assert_eq!(
+        post_event(post_port, &body, None).await,
+        StatusCode::PAYLOAD_TOO_LARGE
+    );
+}
+
+/// API 不可用时 event_channel 失败，且不再占用上报端口。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn event_channel_fails_when_api_unreachable() {
+    let post_port = unused_port().await;
+    let driver = http_driver(unused_port().await, "", post_port, "");
+
+    assert!(driver.event_channel().await.is_err());
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    TcpListener::bind(("127.0.0.1", post_port))
+        .await
+        .expect("listener should be released");
+}
@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use kovi::PluginBuilder;
+use kovi::testing::{PluginTest, failed_return};
+use kovi_onebot::testing::{group_msg, mock_driver, private_msg};
+use kovi_onebot::{GroupMsgEvent, PrivateMsgEvent};
+
+/// 被测试的插件：群里收到 `ping` 回复 `pong`，私聊收到 `name` 时查询登录信息
+async fn main() {
+    PluginBuilder::on(|event: Arc<GroupMsgEvent>| async move {
+        if event.borrow_text() == Some("ping") {
+            event.reply("pong");
+        }
+    });
+    let bot = PluginBuilder::get_runtime_bot();
+    PluginBuilder::on(move |event: Arc<PrivateMsgEvent>| {
+        let bot = bot.clone();
+        async move {
+            if event.borrow_text() != Some("name") {
+                return;
+            }
+            match bot
+                .send_api_return("get_login_info", Default::default())
+                .await
+            {
+                Ok(ret) => event.reply(ret.data["nickname"].as_str().unwrap_or_default()),
+                Err(ret) => event.reply(format!("failed: {}", ret.retcode)),
+            }
+        }
+    });
+}
+
+fn text(api: &kovi::bot::SendApi) -> &str {
+    api.params["message"][0]["data"]["text"]
+        .as_str()
+        .unwrap_or_default()
+}
+
+/// 插件的 main 在测试中运行，收到假事件后的回复被记录下来。
+#[kovi::test(driver = mock_driver())]
+async fn plugin_replies_to_fake_events(test: PluginTest) {
+    main().await;
+
+    test.driver().send(group_msg(100, 2, "hello")).await;
+    test.driver().send(group_msg(100, 2, "ping")).await;
+    let sent = test.api().wait("send_msg", 1).await;
+
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].params["group_id"], 100);
+    assert_eq!(text(&sent[0]), "pong");
+}
+
+/// API 按预设返回。
+#[kovi::test(driver = mock_driver(), plugin = "login")]
+async fn api_returns_canned_responses(test: PluginTest) {
+    main().await;
+
+    test.api()
+        .respond("get_login_info", Err(failed_return(100, "offline")));
+    test.driver().send(private_msg(2, "name")).await;
+    let sent = test.api().wait("send_msg", 1).await;
+    assert_eq!(text(&sent[0]), "failed: 100");
+
+    test.api().respond_with("get_login_info", |_| {
+        Ok(kovi::testing::ok_return(
+            kovi::serde_json::json!({ "nickname": "kovi" }),
+        ))
+    });
+    test.driver().send(private_msg(2, "name")).await;
+    let sent = test.api().wait("send_msg", 2).await;
+    assert_eq!(text(&sent[1]), "kovi");
+    assert_eq!(test.api().requests_of("get_login_info").len(), 2);
+}
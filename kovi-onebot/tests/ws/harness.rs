@@ -1,6 +1,6 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::Duration;
 
 use futures_util::{SinkExt, StreamExt};
@@ -10,7 +10,7 @@ use kovi::config::kovi_conf::KoviConf;
 use kovi::driver::{Driver, DriverEvent};
 use kovi::event::id::ID;
 use kovi_onebot::OneBotDriver;
-use kovi_onebot::driver::config::{Host, OneBotDriverConfig, Server};
+use kovi_onebot::driver::config::{Host, OneBotDriverConfig, ReconnectConfig, Server};
 use serde_json::{Value, json};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::{Notify, mpsc};
@@ -57,6 +57,8 @@ pub(crate) struct MockOneBot {
     api_connect_count: Arc<AtomicUsize>,
     event_connect_count: Arc<AtomicUsize>,
     api_force_fail: Arc<AtomicUsize>,
+    api_silent: Arc<AtomicBool>,
+    api_requests: Arc<parking_lot::Mutex<Vec<Value>>>,
     shutdown: Arc<Notify>,
 }
 
@@ -74,6 +76,8 @@ impl MockOneBot {
             api_connect_count: Arc::new(AtomicUsize::new(0)),
             event_connect_count: Arc::new(AtomicUsize::new(0)),
             api_force_fail: Arc::new(AtomicUsize::new(0)),
+            api_silent: Arc::new(AtomicBool::new(false)),
+            api_requests: Arc::new(parking_lot::Mutex::new(Vec::new())),
             shutdown: Arc::new(Notify::new()),
         });
 
@@ -135,6 +139,29 @@ impl MockOneBot {
         })
     }
 
+    /// 启用快速重连（50ms 起步、无随机浮动）的驱动
+    pub(crate) fn driver_with_reconnect(&self, max_retries: Option<u32>) -> OneBotDriver {
+        OneBotDriver::new(OneBotDriverConfig {
+            server: Server::new(
+                Host::IpAddr("127.0.0.1".parse().expect("ip")),
+                self.port,
+                String::new(),
+                false,
+                "/".into(),
+                false,
+            )
+            .with_reconnect(ReconnectConfig {
+                enable: true,
+                initial_delay_ms: 50,
+                max_delay_ms: 200,
+                multiplier: 2.0,
+                jitter: 0.0,
+                max_retries,
+            }),
+        })
+    }
+
+    #[allow(clippy::result_large_err)]
     async fn handle_conn(&self, stream: TcpStream) {
         let mut path = String::new();
         let ws = match accept_hdr_async(stream, |req: &Request, res: Response| {
@@ -176,7 +203,15 @@ impl MockOneBot {
         *self.api_cmd.lock().await = Some(cmd_tx);
         self.api_connect_count.fetch_add(1, Ordering::SeqCst);
         let fail = Arc::clone(&self.api_force_fail);
+        let silent = Arc::clone(&self.api_silent);
+        let requests = Arc::clone(&self.api_requests);
         let on_text: OnText = Some(Arc::new(move |text: &str| {
+            if let Ok(request) = serde_json::from_str(text) {
+                requests.lock().push(request);
+            }
+            if silent.load(Ordering::SeqCst) {
+                return None;
+            }
             reply_onebot_api(text, fail.load(Ordering::SeqCst) != 0)
         }));
         run_ws_session(ws, cmd_rx, on_text).await;
@@ -228,6 +263,36 @@ impl MockOneBot {
         self.api_force_fail.store(1, Ordering::SeqCst);
     }
 
+    /// API 请求不再回包，用于制造挂起中的 API 调用
+    pub(crate) fn set_api_silent(&self, silent: bool) {
+        self.api_silent.store(silent, Ordering::SeqCst);
+    }
+
+    /// 停止接受新连接，已建立的连接不受影响
+    pub(crate) fn stop_accepting(&self) {
+        self.shutdown.notify_waiters();
+    }
+
+    pub(crate) async fn wait_event_connects(&self, n: usize) {
+        timeout(CONNECT_TIMEOUT, async {
+            while self.event_connects() < n {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("event ws did not reconnect");
+    }
+
+    pub(crate) async fn wait_api_connects(&self, n: usize) {
+        timeout(CONNECT_TIMEOUT, async {
+            while self.api_connects() < n {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("api ws did not reconnect");
+    }
+
     pub(crate) fn api_connects(&self) -> usize {
         self.api_connect_count.load(Ordering::SeqCst)
     }
@@ -235,6 +300,27 @@ impl MockOneBot {
     pub(crate) fn event_connects(&self) -> usize {
         self.event_connect_count.load(Ordering::SeqCst)
     }
+
+    /// 等待 API WS 收到 `n` 个 `action` 请求，返回它们的 params
+    pub(crate) async fn wait_api_requests(&self, action: &str, n: usize) -> Vec<Value> {
+        timeout(CONNECT_TIMEOUT, async {
+            loop {
+                let params: Vec<Value> = self
+                    .api_requests
+                    .lock()
+                    .iter()
+                    .filter(|request| request["action"] == action)
+                    .map(|request| request["params"].clone())
+                    .collect();
+                if params.len() >= n {
+                    return params;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap_or_else(|_| panic!("{n} {action} api requests did not arrive"))
+    }
 }
 
 impl Drop for MockOneBot {
@@ -327,7 +413,13 @@ async fn send_disconnect(tx: Option<&mpsc::Sender<ConnCmd>>, how: Disconnect) {
     tokio::time::sleep(Duration::from_millis(50)).await;
 }
 
+/// 测试中的配置与数据都在当前目录中
+fn use_current_dir() {
+    kovi::config::paths::KoviPaths::in_dir(".").set_current();
+}
+
 pub(crate) fn conf() -> KoviConf {
+    use_current_dir();
     KoviConf::new(ID::new(1i64), None, false)
 }
 
@@ -363,6 +455,7 @@ pub(crate) async fn drain_until_break(
 }
 
 pub(crate) fn status_file_guard() -> StatusFileGuard {
+    use_current_dir();
     StatusFileGuard {
         plugin_existed: std::path::Path::new("kovi.plugin.toml").exists(),
         conf_existed: std::path::Path::new("kovi.conf.toml").exists(),
@@ -1,6 +1,9 @@
-//! 模拟 OneBot 反向 WS 服务端，实测事件通道 / API 通道断连行为。
+//! 模拟 OneBot 服务端 / 客户端，实测事件通道 / API 通道断连行为、反向 WS 模式与请求事件处理。
 
 mod api;
 mod connect;
 mod event;
 mod harness;
+mod reconnect;
+mod request;
+mod reverse;
@@ -0,0 +1,164 @@
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use kovi::Bot;
+use kovi::bot::SendApi;
+use kovi::driver::{AnyError, Driver, DriverEvent};
+use serde_json::json;
+use tokio::time::timeout;
+
+use crate::harness::{
+    CONNECT_TIMEOUT, Disconnect, MockOneBot, RunOutcome, conf, observe_run, status_file_guard,
+};
+
+/// 从事件流中收集驱动注入的连接状态，直到 `channel` 出现 `until` 状态。
+async fn collect_connection_states(
+    stream: &mut (impl StreamExt<Item = Result<DriverEvent, AnyError>> + Unpin),
+    channel: &str,
+    until: &str,
+) -> Vec<String> {
+    let mut states = Vec::new();
+    timeout(CONNECT_TIMEOUT, async {
+        while let Some(Ok(event)) = stream.next().await {
+            let DriverEvent::Normal(value) = event else {
+                panic!("event stream yielded Exit while reconnecting");
+            };
+            if value["meta_event_type"] != "kovi_connection" || value["channel"] != channel {
+                continue;
+            }
+            let state = value["state"].as_str().expect("state").to_string();
+            let done = state == until;
+            states.push(state);
+            if done {
+                return;
+            }
+        }
+        panic!("event stream ended while reconnecting");
+    })
+    .await
+    .expect("connection state did not arrive");
+    states
+}
+
+/// 启用重连时，事件 WS Close 后重新建连，并依次产生连接状态事件。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn event_ws_reconnects_after_close() {
+    let server = MockOneBot::start().await;
+    let driver = server.driver_with_reconnect(None);
+    let mut stream = driver.event_channel().await.expect("event_channel");
+    server.wait_event().await;
+
+    server.disconnect_event(Disconnect::Close).await;
+
+    let states = collect_connection_states(&mut stream, "event", "reconnected").await;
+    assert_eq!(states, ["disconnected", "reconnecting", "reconnected"]);
+    server.wait_event_connects(2).await;
+
+    // 重连后的事件照常送达
+    server
+        .send_event(tokio_tungstenite::tungstenite::Message::text(
+            json!({ "post_type": "meta_event", "meta_event_type": "heartbeat" }).to_string(),
+        ))
+        .await;
+    let next = timeout(CONNECT_TIMEOUT, stream.next())
+        .await
+        .expect("event after reconnect")
+        .expect("stream ended")
+        .expect("stream error");
+    let DriverEvent::Normal(value) = next else {
+        panic!("unexpected Exit after reconnect");
+    };
+    assert_eq!(value["meta_event_type"], "heartbeat");
+}
+
+/// 启用重连时，事件 WS 异常断开不会让 Bot 退出。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn bot_keeps_running_after_event_drop() {
+    let _guard = status_file_guard();
+    let server = MockOneBot::start().await;
+    let handle = tokio::spawn(Bot::build(conf(), server.driver_with_reconnect(None)).run());
+    server.wait_api().await;
+    server.wait_event().await;
+
+    server.disconnect_event(Disconnect::Drop).await;
+    server.wait_event_connects(2).await;
+
+    assert_eq!(observe_run(handle).await, RunOutcome::Hung);
+}
+
+/// 启用重连时，API WS 断开后重新建连，之后的 API 调用正常返回。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn api_ws_reconnects_after_close() {
+    let server = MockOneBot::start().await;
+    let driver = server.driver_with_reconnect(None);
+    driver
+        .api_handler(SendApi::new("get_login_info", json!({})))
+        .await
+        .expect("first api connect")
+        .expect("first get_login_info");
+    server.wait_api().await;
+
+    server.disconnect_api(Disconnect::Close).await;
+    server.wait_api_connects(2).await;
+
+    let second = timeout(
+        CONNECT_TIMEOUT,
+        driver.api_handler(SendApi::new("get_login_info", json!({}))),
+    )
+    .await
+    .expect("api call after reconnect hung")
+    .expect("api transport after reconnect");
+    assert!(
+        second.is_ok(),
+        "api call after reconnect failed: {second:?}"
+    );
+}
+
+/// API WS 断开时，等待回包中的 API 调用立即失败而不是挂起。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn pending_api_fails_on_disconnect() {
+    let server = MockOneBot::start().await;
+    let driver = std::sync::Arc::new(server.driver());
+    driver
+        .api_handler(SendApi::new("get_login_info", json!({})))
+        .await
+        .expect("first api connect")
+        .expect("first get_login_info");
+    server.wait_api().await;
+    server.set_api_silent(true);
+
+    let pending = tokio::spawn({
+        let driver = driver.clone();
+        async move {
+            driver
+                .api_handler(SendApi::new("get_status", json!({})))
+                .await
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    server.disconnect_api(Disconnect::Drop).await;
+
+    let result = timeout(Duration::from_secs(2), pending)
+        .await
+        .expect("pending api call hung after disconnect")
+        .expect("join")
+        .expect("api transport");
+    let err = result.expect_err("pending api call should fail");
+    assert_eq!(err.status, "failed");
+}
+
+/// 重试次数耗尽后 Bot 以 FromDrive 退出。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn bot_exits_when_reconnect_retries_exhausted() {
+    let _guard = status_file_guard();
+    let server = MockOneBot::start().await;
+    let handle = tokio::spawn(Bot::build(conf(), server.driver_with_reconnect(Some(2))).run());
+    server.wait_api().await;
+    server.wait_event().await;
+
+    server.stop_accepting();
+    server.disconnect_event(Disconnect::Drop).await;
+
+    assert_eq!(observe_run(handle).await, RunOutcome::ExitedFromDrive);
+}
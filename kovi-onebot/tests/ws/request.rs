@@ -0,0 +1,155 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use kovi::{Bot, PluginBuilder};
+use kovi_onebot::{
+    EventRegistrar, FriendRequestEvent, GroupRequestEvent, RequestDecision, RequestPolicy,
+};
+use serde_json::{Value, json};
+use tokio::time::timeout;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::harness::{CONNECT_TIMEOUT, MockOneBot, conf, status_file_guard};
+
+fn friend_request(user_id: i64, comment: &str, flag: &str) -> Message {
+    Message::text(
+        json!({
+            "time": 1700000000,
+            "self_id": 10000,
+            "post_type": "request",
+            "request_type": "friend",
+            "user_id": user_id,
+            "comment": comment,
+            "flag": flag,
+        })
+        .to_string(),
+    )
+}
+
+fn group_invite(user_id: i64, flag: &str) -> Message {
+    Message::text(
+        json!({
+            "time": 1700000000,
+            "self_id": 10000,
+            "post_type": "request",
+            "request_type": "group",
+            "sub_type": "invite",
+            "group_id": 100,
+            "user_id": user_id,
+            "comment": "",
+            "flag": flag,
+        })
+        .to_string(),
+    )
+}
+
+fn by_flag<'a>(params: &'a [Value], flag: &str) -> Option<&'a Value> {
+    params.iter().find(|params| params["flag"] == flag)
+}
+
+/// 启动挂载了 `register` 插件的 Bot，等待插件注册完成
+async fn start_bot(
+    server: &MockOneBot,
+    register: impl Fn() + Send + Sync + 'static,
+) -> tokio::task::JoinHandle<kovi::ExitEvent> {
+    let register = Arc::new(register);
+    let (ready_tx, mut ready_rx) = tokio::sync::watch::channel(false);
+    let mut bot = Bot::build(conf(), server.driver());
+    bot.mount_plugin(kovi::plugin::Plugin::new(
+        "request-probe",
+        "0.0.0",
+        Arc::new(move || {
+            let register = register.clone();
+            let ready_tx = ready_tx.clone();
+            Box::pin(async move {
+                register();
+                let _ = ready_tx.send(true);
+            })
+        }),
+    ));
+
+    let handle = tokio::spawn(bot.run());
+    server.wait_api().await;
+    server.wait_event().await;
+    timeout(CONNECT_TIMEOUT, ready_rx.wait_for(|ready| *ready))
+        .await
+        .expect("plugin ready")
+        .expect("ready watch closed");
+    handle
+}
+
+/// approve / reject 带上事件中的 flag 调用对应的 API。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn request_event_helpers_call_api() {
+    let _guard = status_file_guard();
+    let server = MockOneBot::start().await;
+    let handle = start_bot(&server, || {
+        PluginBuilder::on_friend_request(|event| async move {
+            event.approve_with_remark("from test");
+        });
+        PluginBuilder::on_group_request(|event| async move {
+            event.reject("no invites");
+        });
+    })
+    .await;
+
+    server.send_event(friend_request(2, "hi", "f-1")).await;
+    server.send_event(group_invite(3, "g-1")).await;
+
+    let friend = server.wait_api_requests("set_friend_add_request", 1).await;
+    assert_eq!(
+        friend[0],
+        json!({ "flag": "f-1", "approve": true, "remark": "from test" })
+    );
+    let group = server.wait_api_requests("set_group_add_request", 1).await;
+    assert_eq!(
+        group[0],
+        json!({ "flag": "g-1", "sub_type": "invite", "approve": false, "reason": "no invites" })
+    );
+
+    handle.abort();
+}
+
+/// 策略按规则处理请求：管理员的邀请被同意，验证信息不符的好友请求被拒绝，其余按 otherwise 处理。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn request_policy_decides_requests() {
+    let _guard = status_file_guard();
+    let server = MockOneBot::start().await;
+    let handle = start_bot(&server, || {
+        PluginBuilder::request_policy(
+            RequestPolicy::<GroupRequestEvent>::new().approve_invites_from_admins(),
+        );
+        PluginBuilder::request_policy(
+            RequestPolicy::<FriendRequestEvent>::new()
+                .reject_comment_unless(|comment| comment.contains("kovi"), "wrong answer")
+                .otherwise(RequestDecision::ApproveWithRemark("kovi user".to_string())),
+        );
+    })
+    .await;
+
+    // 用户 3 不是管理员，不会被处理
+    server.send_event(group_invite(3, "g-not-admin")).await;
+    // conf() 中的主管理员为 1
+    server.send_event(group_invite(1, "g-admin")).await;
+    server
+        .send_event(friend_request(2, "hello", "f-wrong"))
+        .await;
+    server
+        .send_event(friend_request(2, "I use kovi", "f-right"))
+        .await;
+
+    let group = server.wait_api_requests("set_group_add_request", 1).await;
+    assert_eq!(by_flag(&group, "g-admin").unwrap()["approve"], true);
+
+    let friend = server.wait_api_requests("set_friend_add_request", 2).await;
+    assert_eq!(by_flag(&friend, "f-wrong").unwrap()["approve"], false);
+    let right = by_flag(&friend, "f-right").unwrap();
+    assert_eq!(right["approve"], true);
+    assert_eq!(right["remark"], "kovi user");
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let group = server.wait_api_requests("set_group_add_request", 1).await;
+    assert!(by_flag(&group, "g-not-admin").is_none());
+
+    handle.abort();
+}
@@ -0,0 +1,328 @@
+use futures_util::{SinkExt, StreamExt};
+use kovi::bot::SendApi;
+use kovi::driver::{Driver, DriverEvent};
+use kovi_onebot::OneBotDriver;
+use kovi_onebot::driver::config::{Host, OneBotDriverConfig, Server, ServerMode};
+use serde_json::{Value, json};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
+
+use crate::harness::{CONNECT_TIMEOUT, unused_port};
+
+type Client = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+fn reverse_driver(port: u16, token: &str) -> OneBotDriver {
+    reverse_driver_at(port, token, "/")
+}
+
+fn reverse_driver_at(port: u16, token: &str, path: &str) -> OneBotDriver {
+    OneBotDriver::new(OneBotDriverConfig {
+        server: Server::new(
+            Host::IpAddr("127.0.0.1".parse().expect("ip")),
+            port,
+            token.to_string(),
+            false,
+            path.into(),
+            false,
+        )
+        .with_mode(ServerMode::WsReverse),
+    })
+}
+
+/// 模拟 OneBot 实现连接 Kovi 的反向 WS 服务端
+async fn connect_client(
+    port: u16,
+    path: &str,
+    role: Option<&str>,
+    self_id: Option<&str>,
+    token: Option<&str>,
+) -> Result<Client, tokio_tungstenite::tungstenite::Error> {
+    let mut request = format!("ws://127.0.0.1:{port}{path}")
+        .into_client_request()
+        .expect("request");
+    let headers = request.headers_mut();
+    if let Some(role) = role {
+        headers.insert("X-Client-Role", role.parse().expect("header"));
+    }
+    if let Some(self_id) = self_id {
+        headers.insert("X-Self-ID", self_id.parse().expect("header"));
+    }
+    if let Some(token) = token {
+        headers.insert(
+            "Authorization",
+            format!("Bearer {token}").parse().expect("header"),
+        );
+    }
+    connect_async(request).await.map(|(ws, _)| ws)
+}
+
+/// 客户端收到一条 API 请求后按 echo 回包
+async fn answer_one_api(client: &mut Client) -> Value {
+    let request = loop {
+        match timeout(CONNECT_TIMEOUT, client.next())
+            .await
+            .expect("api request did not arrive")
+        {
+            Some(Ok(Message::Text(text))) => {
+                break serde_json::from_str::<Value>(&text).expect("api json");
+            }
+            Some(Ok(_)) => continue,
+            other => panic!("client closed before api request: {other:?}"),
+        }
+    };
+    client
+        .send(Message::text(
+            json!({
+                "status": "ok",
+                "retcode": 0,
+                "data": { "user_id": 10000, "nickname": "mock-bot" },
+                "echo": request["echo"],
+            })
+            .to_string(),
+        ))
+        .await
+        .expect("send api return");
+    request
+}
+
+/// Universal 连接：同一条连接上收发事件与 API。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn universal_connection_carries_events_and_api() {
+    let port = unused_port().await;
+    let driver = reverse_driver(port, "");
+    let mut stream = driver.event_channel().await.expect("listen");
+
+    let mut client = connect_client(port, "/", Some("Universal"), Some("10000"), None)
+        .await
+        .expect("client connect");
+
+    client
+        .send(Message::text(
+            json!({ "post_type": "meta_event", "meta_event_type": "heartbeat", "time": 1, "self_id": 10000 })
+                .to_string(),
+        ))
+        .await
+        .expect("send event");
+    let event = timeout(CONNECT_TIMEOUT, stream.next())
+        .await
+        .expect("event did not arrive")
+        .expect("stream ended")
+        .expect("stream error");
+    let DriverEvent::Normal(value) = event else {
+        panic!("unexpected Exit");
+    };
+    assert_eq!(value["meta_event_type"], "heartbeat");
+
+    let api = tokio::spawn(async move {
+        driver
+            .api_handler(SendApi::new("get_login_info", json!({})))
+            .await
+    });
+    let request = answer_one_api(&mut client).await;
+    assert_eq!(request["action"], "get_login_info");
+    let result = timeout(CONNECT_TIMEOUT, api)
+        .await
+        .expect("api hung")
+        .expect("join")
+        .expect("transport");
+    assert_eq!(result.expect("api ok").data["user_id"], 10000);
+}
+
+/// 分开的 `/api` 与 `/event` 连接按路径判断角色。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn separate_api_and_event_connections() {
+    let port = unused_port().await;
+    let driver = reverse_driver(port, "");
+    let mut stream = driver.event_channel().await.expect("listen");
+
+    let mut event_client = connect_client(port, "/event", None, Some("10000"), None)
+        .await
+        .expect("event connect");
+    let mut api_client = connect_client(port, "/api", None, Some("10000"), None)
+        .await
+        .expect("api connect");
+
+    event_client
+        .send(Message::text(
+            json!({ "post_type": "notice", "notice_type": "poke", "time": 1, "self_id": 10000 })
+                .to_string(),
+        ))
+        .await
+        .expect("send event");
+    let event = timeout(CONNECT_TIMEOUT, stream.next())
+        .await
+        .expect("event did not arrive")
+        .expect("stream ended")
+        .expect("stream error");
+    let DriverEvent::Normal(value) = event else {
+        panic!("unexpected Exit");
+    };
+    assert_eq!(value["notice_type"], "poke");
+
+    // 等待 API 连接注册完成
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    let api = tokio::spawn(async move {
+        driver
+            .api_handler(SendApi::new("get_status", json!({})))
+            .await
+    });
+    answer_one_api(&mut api_client).await;
+    let result = timeout(CONNECT_TIMEOUT, api)
+        .await
+        .expect("api hung")
+        .expect("join")
+        .expect("transport");
+    assert!(result.is_ok());
+}
+
+/// 没有客户端连接时 API 立即失败。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn api_fails_without_client() {
+    let port = unused_port().await;
+    let driver = reverse_driver(port, "");
+    let _stream = driver.event_channel().await.expect("listen");
+
+    let result = timeout(
+        CONNECT_TIMEOUT,
+        driver.api_handler(SendApi::new("get_status", json!({}))),
+    )
+    .await
+    .expect("api hung");
+    assert!(result.is_err());
+}
+
+/// 设置 access_token 时，错误或缺失的 token 都会被拒绝。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn access_token_is_checked() {
+    let port = unused_port().await;
+    let driver = reverse_driver(port, "secret");
+    let _stream = driver.event_channel().await.expect("listen");
+
+    assert!(
+        connect_client(port, "/", None, Some("10000"), None)
+            .await
+            .is_err(),
+        "missing token must be rejected"
+    );
+    assert!(
+        connect_client(port, "/", None, Some("10000"), Some("wrong"))
+            .await
+            .is_err(),
+        "wrong token must be rejected"
+    );
+    connect_client(port, "/", None, Some("10000"), Some("secret"))
+        .await
+        .expect("correct token must be accepted");
+    connect_client(port, "/?access_token=se%63ret", None, Some("10000"), None)
+        .await
+        .expect("percent-encoded query token must be accepted");
+}
+
+/// 路径必须在 `/` 处与配置的前缀分隔。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn path_prefix_needs_boundary() {
+    let port = unused_port().await;
+    let driver = reverse_driver_at(port, "", "/onebot");
+    let _stream = driver.event_channel().await.expect("listen");
+
+    assert!(
+        connect_client(port, "/onebotapi", None, Some("10000"), None)
+            .await
+            .is_err(),
+        "path without boundary must be rejected"
+    );
+    connect_client(port, "/onebot/api", None, Some("10000"), None)
+        .await
+        .expect("api path");
+    connect_client(port, "/onebot", None, Some("10000"), None)
+        .await
+        .expect("universal path");
+}
+
+/// 缺失 X-Self-ID 或与已连接的账号不一致时拒绝连接。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn self_id_is_checked() {
+    let port = unused_port().await;
+    let driver = reverse_driver(port, "");
+    let _stream = driver.event_channel().await.expect("listen");
+
+    assert!(
+        connect_client(port, "/", None, None, None).await.is_err(),
+        "missing X-Self-ID must be rejected"
+    );
+    let _first = connect_client(port, "/event", None, Some("10000"), None)
+        .await
+        .expect("first connect");
+    assert!(
+        connect_client(port, "/api", None, Some("20000"), None)
+            .await
+            .is_err(),
+        "different X-Self-ID must be rejected"
+    );
+    assert!(
+        connect_client(port, "/unknown", None, Some("10000"), None)
+            .await
+            .is_err(),
+        "unknown path must be rejected"
+    );
+}
+
+/// 所有连接断开后，可以换成另一个账号连接。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn self_id_is_reset_after_all_connections_close() {
+    let port = unused_port().await;
+    let driver = reverse_driver(port, "");
+    let mut stream = driver.event_channel().await.expect("listen");
+
+    let mut client = connect_client(port, "/event", None, Some("10000"), None)
+        .await
+        .expect("first connect");
+    client.close(None).await.expect("close");
+    drop(client);
+    assert_eq!(next_connection_state(&mut stream).await, "disconnected");
+
+    connect_client(port, "/event", None, Some("20000"), None)
+        .await
+        .expect("another account must be accepted after disconnect");
+}
+
+/// 客户端断开后重新连接，插件会收到连接状态事件。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn client_reconnect_emits_connection_events() {
+    let port = unused_port().await;
+    let driver = reverse_driver(port, "");
+    let mut stream = driver.event_channel().await.expect("listen");
+
+    let mut client = connect_client(port, "/event", None, Some("10000"), None)
+        .await
+        .expect("first connect");
+    client.close(None).await.expect("close");
+    drop(client);
+    assert_eq!(next_connection_state(&mut stream).await, "disconnected");
+
+    let _client = connect_client(port, "/event", None, Some("10000"), None)
+        .await
+        .expect("second connect");
+    assert_eq!(next_connection_state(&mut stream).await, "reconnected");
+}
+
+/// 从事件流中取出下一条驱动注入的连接状态。
+async fn next_connection_state(
+    stream: &mut (impl StreamExt<Item = Result<DriverEvent, kovi::driver::AnyError>> + Unpin),
+) -> String {
+    timeout(CONNECT_TIMEOUT, async {
+        loop {
+            let Some(Ok(DriverEvent::Normal(value))) = stream.next().await else {
+                panic!("stream ended");
+            };
+            if value["meta_event_type"] == "kovi_connection" {
+                return value["state"].as_str().expect("state").to_string();
+            }
+        }
+    })
+    .await
+    .expect("connection event did not arrive")
+}
@@ -1,4 +1,5 @@
 use ahash::{HashMap, HashMapExt as _, HashSet};
+use arc_swap::ArcSwap;
 use ouroboros::self_referencing;
 use parking_lot::RwLock;
 // #[cfg(feature = "plugin-access-control")]
@@ -7,10 +8,21 @@ use serde::{Deserialize, Serialize};
 use serde_json::{self, Value};
 use std::fmt::Debug;
 use std::fs;
-use std::io::Write as _;
-use std::sync::Arc;
-
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Weak};
+
+use crate::bot::account::{Account, AccountDriver, DEFAULT_ACCOUNT};
+use crate::bot::dispatch::DispatchMode;
+use crate::bot::dispatch_table::DispatchTable;
+use crate::bot::dry_run::DryRun;
+use crate::bot::failure::Failures;
+use crate::bot::middleware::{ApiMiddleware, EventMiddleware, Middlewares};
 use crate::config::kovi_conf::KoviConf;
+use crate::config::paths::KoviPaths;
+#[cfg(feature = "save_plugin_status")]
+use crate::config::paths::create_parent_dir;
+#[cfg(feature = "save_bot_admin")]
+use crate::config::paths::{lock_conf_file, write_conf_file};
 use crate::driver::Driver;
 use crate::error::BotError;
 
@@ -21,16 +33,37 @@ use crate::event::id::ref_id::RefID;
 use crate::plugin::plugin_set::PluginSet;
 use crate::plugin::{Plugin, PluginStatus};
 
+pub mod dispatch;
+pub(crate) mod dispatch_table;
+pub mod dry_run;
+pub mod failure;
 pub(crate) mod handler;
 pub(crate) mod run;
 
+pub mod account;
+pub mod middleware;
 pub mod runtimebot;
 
 /// bot结构体
 pub struct Bot {
     pub information: Arc<RwLock<BotInformation>>,
+    /// 默认账号的驱动
     pub drive: Arc<dyn Driver>,
+    /// 所有账号，第一个为默认账号
+    pub(crate) accounts: Vec<AccountDriver>,
     pub(crate) plugins: HashMap<String, Plugin>,
+    pub(crate) middlewares: Middlewares,
+    pub(crate) dispatch_mode: DispatchMode,
+    /// 插件监听的失败记录
+    pub(crate) failures: Arc<Failures>,
+    /// 试运行，见 [`dry_run`]
+    pub(crate) dry_run: Arc<DryRun>,
+    /// 收到事件时使用的分发表，见 [`DispatchTable`]
+    pub(crate) dispatch_table: Arc<ArcSwap<DispatchTable>>,
+    /// 分发表需要重新生成，见 [`Bot::mark_dispatch_table_dirty`]
+    pub(crate) dispatch_table_dirty: Arc<AtomicBool>,
+    /// Bot 运行后指向自己
+    pub(crate) this: Weak<RwLock<Bot>>,
     pub(crate) run_abort: Vec<tokio::task::AbortHandle>,
 }
 impl Drop for Bot {
@@ -75,14 +108,103 @@ impl Bot {
         }
         .build();
 
+        let drive: Arc<dyn Driver> = Arc::new(drive);
+        let information = Arc::new(RwLock::new(bot_info));
+        let failures: Arc<Failures> = Default::default();
+
         Bot {
-            information: Arc::new(RwLock::new(bot_info)),
-            drive: Arc::new(drive),
+            dispatch_table: Arc::new(ArcSwap::from_pointee(DispatchTable::empty(
+                information.clone(),
+                failures.clone(),
+            ))),
+            dispatch_table_dirty: Default::default(),
+            information,
+            drive: drive.clone(),
+            accounts: vec![AccountDriver::new(Account::new(DEFAULT_ACCOUNT), drive)],
             plugins: HashMap::<_, _>::new(),
+            middlewares: Middlewares::default(),
+            dispatch_mode: DispatchMode::default(),
+            failures,
+            dry_run: Default::default(),
+            this: Weak::new(),
             run_abort: Vec::new(),
         }
     }
 
+    /// 添加一个账号，让一个 Bot 同时驱动多个账号。
+    ///
+    /// `Bot::build()` 传入的驱动为默认账号 `"default"`，同名账号会被替换。
+    ///
+    /// 所有账号共享插件与管理员，每个事件都会记录收到它的账号，
+    /// 见 [`Account::current()`] 与 [`AccountEvent`](account::AccountEvent)。
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let bot = Bot::build(conf, OneBotDriver::new(conf_a))
+    ///     .add_account("second", OneBotDriver::new(conf_b))
+    ///     .add_account("milky", MilkyDriver::new(conf_c));
+    /// ```
+    pub fn add_account<T, D>(mut self, name: T, drive: D) -> Self
+    where
+        T: AsRef<str>,
+        D: Driver + 'static,
+    {
+        self.add_account_ref(name, drive);
+        self
+    }
+
+    /// 添加一个账号，让一个 Bot 同时驱动多个账号。
+    ///
+    /// `Bot::build()` 传入的驱动为默认账号 `"default"`，同名账号会被替换。
+    pub fn add_account_ref<T, D>(&mut self, name: T, drive: D)
+    where
+        T: AsRef<str>,
+        D: Driver + 'static,
+    {
+        let account = Account::new(name.as_ref());
+        let drive: Arc<dyn Driver> = Arc::new(drive);
+
+        if account.is_default() {
+            self.drive = drive.clone();
+        }
+
+        match self.accounts.iter_mut().find(|v| v.account == account) {
+            Some(v) => v.drive = drive,
+            None => self.accounts.push(AccountDriver::new(account, drive)),
+        }
+    }
+
+    /// 获取所有账号
+    pub fn get_accounts(&self) -> Vec<Account> {
+        self.accounts.iter().map(|v| v.account.clone()).collect()
+    }
+
+    /// 添加事件中间件，在事件交给插件之前按添加顺序执行，见 [`EventMiddleware`]。
+    pub fn middleware<M: EventMiddleware>(mut self, middleware: M) -> Self {
+        self.middleware_ref(middleware);
+        self
+    }
+
+    /// 添加事件中间件，在事件交给插件之前按添加顺序执行，见 [`EventMiddleware`]。
+    pub fn middleware_ref<M: EventMiddleware>(&mut self, middleware: M) {
+        Arc::make_mut(&mut self.middlewares.event).push(Arc::new(middleware));
+    }
+
+    /// 添加 API 中间件，在 API 交给驱动之前按添加顺序执行，见 [`ApiMiddleware`]。
+    ///
+    /// 需要在 `Bot::run()` 之前添加。
+    pub fn api_middleware<M: ApiMiddleware>(mut self, middleware: M) -> Self {
+        self.api_middleware_ref(middleware);
+        self
+    }
+
+    /// 添加 API 中间件，在 API 交给驱动之前按添加顺序执行，见 [`ApiMiddleware`]。
+    ///
+    /// 需要在 `Bot::run()` 之前添加。
+    pub fn api_middleware_ref<M: ApiMiddleware>(&mut self, middleware: M) {
+        Arc::make_mut(&mut self.middlewares.api).push(Arc::new(middleware));
+    }
+
     /// 挂载插件。
     pub fn mount_plugin(&mut self, plugin: Plugin) {
         self.plugins.insert(plugin.name.clone(), plugin);
@@ -103,7 +225,7 @@ impl Bot {
     ///
     /// 如果配置文件读取失败或者解析toml失败，将会保留插件默认状态
     pub fn set_plugin_startup_use_file(mut self) -> Self {
-        let file_path = "kovi.plugin.toml";
+        let file_path = KoviPaths::current().plugin_status_file();
         let content = match fs::read_to_string(file_path) {
             Ok(v) => {
                 log::debug!("Set plugin startup use file successfully");
@@ -146,7 +268,7 @@ impl Bot {
     ///
     /// 如果配置文件读取失败或者解析toml失败，将会保留插件默认状态
     pub fn set_plugin_startup_use_file_ref(&mut self) {
-        let file_path = "kovi.plugin.toml";
+        let file_path = KoviPaths::current().plugin_status_file();
         let content = match fs::read_to_string(file_path) {
             Ok(v) => {
                 log::debug!("Set plugin startup use file successfully");
@@ -242,7 +364,7 @@ impl Bot {
     pub(crate) fn save_bot_status(&self) {
         #[cfg(feature = "save_plugin_status")]
         {
-            let _file_path = "kovi.plugin.toml";
+            let _file_path = KoviPaths::current().plugin_status_file();
 
             let mut plugin_status = HashMap::new();
             for (name, plugin) in self.plugins.iter() {
@@ -264,15 +386,18 @@ impl Bot {
                     return;
                 }
             };
-            if let Err(e) = fs::write(_file_path, serialized) {
+            if let Err(e) =
+                create_parent_dir(&_file_path).and_then(|_| fs::write(&_file_path, serialized))
+            {
                 log::error!("Failed to write plugin status to file: {e}");
             }
         }
 
         #[cfg(feature = "save_bot_admin")]
         {
-            let file_path = "kovi.conf.toml";
-            let existing_content = fs::read_to_string(file_path).unwrap_or_default();
+            let file_path = KoviPaths::current().conf_file();
+            let _lock = lock_conf_file();
+            let existing_content = fs::read_to_string(&file_path).unwrap_or_default();
 
             let mut doc = existing_content
                 .parse::<toml_edit::DocumentMut>()
@@ -300,16 +425,8 @@ impl Bot {
                     .collect(),
             ));
 
-            match fs::File::create(file_path) {
-                Ok(file) => {
-                    let mut writer = std::io::BufWriter::new(file);
-                    if let Err(e) = writer.write_all(doc.to_string().as_bytes()) {
-                        log::error!("Failed to write to file: {e}");
-                    }
-                }
-                Err(e) => {
-                    log::error!("Failed to create file: {e}");
-                }
+            if let Err(e) = write_conf_file(&file_path, &doc.to_string()) {
+                log::error!("Failed to write to file: {e}");
             }
         }
     }
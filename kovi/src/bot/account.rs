@@ -0,0 +1,148 @@
+use crate::bot::BotInformation;
+use crate::bot::runtimebot::CanSendApi;
+use crate::driver::Driver;
+use crate::event::{Event, InternalEvent, parse_cached};
+use crate::types::ApiAndOptOneshot;
+use std::fmt::Display;
+use std::ops::Deref;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// `Bot::build()` 传入的驱动所对应的账号名
+pub const DEFAULT_ACCOUNT: &str = "default";
+
+tokio::task_local! {
+    pub(crate) static ACCOUNT: Account;
+}
+
+/// Bot 中注册的一个账号，一个账号对应一个驱动，以名称区分。
+///
+/// 一个 Bot 可以同时驱动多个账号，插件、插件状态与定时任务在所有账号间共享，只存在一份。
+///
+/// 需要在事件中保留账号时，监听 [`AccountEvent`]。
+///
+/// # Examples
+/// ```ignore
+/// use kovi::bot::account::Account;
+///
+/// PluginBuilder::on(|event: Arc<MsgEvent>| async move {
+///     let account = Account::current().unwrap();
+///     event.reply(format!("Received by {account}"));
+/// });
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Account {
+    name: Arc<str>,
+}
+
+impl Account {
+    pub fn new<T: Into<Arc<str>>>(name: T) -> Self {
+        Account { name: name.into() }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 是否为 `Bot::build()` 传入的默认账号
+    pub fn is_default(&self) -> bool {
+        &*self.name == DEFAULT_ACCOUNT
+    }
+
+    /// 获取当前正在处理的事件所属的账号
+    ///
+    /// 在插件的监听闭包、解析事件的 [`Event::de`]，以及由监听闭包中 `kovi::spawn()` 创建的新线程中可用，
+    /// 其他地方会返回 `None`。
+    ///
+    /// `tokio::spawn()` 创建的线程与稍后处理的事件中无法取得，这时请使用 [`AccountEvent`]。
+    pub fn current() -> Option<Account> {
+        ACCOUNT.try_with(|account| account.clone()).ok()
+    }
+}
+
+impl Display for Account {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// 带有收到它的账号的事件
+///
+/// 账号保存在事件中，事件被移到 `tokio::spawn()` 创建的线程中或者留到之后处理时，依然可以取得。
+/// 可以通过 `Deref` 直接使用原本的事件。
+///
+/// # Examples
+/// ```ignore
+/// use kovi::bot::account::AccountEvent;
+///
+/// PluginBuilder::on(|event: Arc<AccountEvent<MsgEvent>>| async move {
+///     tokio::spawn(async move {
+///         event.reply(format!("Received by {}", event.account()));
+///     });
+/// });
+/// ```
+pub struct AccountEvent<T> {
+    account: Account,
+    event: Arc<T>,
+}
+
+impl<T> AccountEvent<T> {
+    pub fn new(account: Account, event: Arc<T>) -> Self {
+        AccountEvent { account, event }
+    }
+
+    /// 收到此事件的账号
+    pub fn account(&self) -> &Account {
+        &self.account
+    }
+
+    /// 原本的事件
+    pub fn event(&self) -> &Arc<T> {
+        &self.event
+    }
+}
+
+impl<T> Deref for AccountEvent<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.event
+    }
+}
+
+impl<T: Event> Event for AccountEvent<T> {
+    /// 不在 Kovi 分发事件的过程中解析时，账号为默认账号
+    fn de(
+        event: &InternalEvent,
+        bot_info: &BotInformation,
+        api_tx: &mpsc::Sender<ApiAndOptOneshot>,
+    ) -> Option<Self> {
+        let account = Account::current().unwrap_or_else(|| Account::new(DEFAULT_ACCOUNT));
+        let event = parse_cached::<T>(event, bot_info, api_tx)?;
+        Some(AccountEvent { account, event })
+    }
+}
+
+impl<T: CanSendApi> CanSendApi for AccountEvent<T> {
+    fn __get_api_tx(&self) -> &mpsc::Sender<ApiAndOptOneshot> {
+        self.event.__get_api_tx()
+    }
+}
+
+/// 账号与它的驱动
+pub(crate) struct AccountDriver {
+    pub(crate) account: Account,
+    pub(crate) drive: Arc<dyn Driver>,
+    /// 发往此账号驱动的 api 通道，Bot 运行后才会存在
+    pub(crate) api_tx: Option<mpsc::Sender<ApiAndOptOneshot>>,
+}
+
+impl AccountDriver {
+    pub(crate) fn new(account: Account, drive: Arc<dyn Driver>) -> Self {
+        AccountDriver {
+            account,
+            drive,
+            api_tx: None,
+        }
+    }
+}
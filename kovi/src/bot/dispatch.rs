@@ -0,0 +1,123 @@
+use crate::bot::Bot;
+use crate::bot::account::Account;
+use crate::event::MessageEventTrait;
+use crate::event::id::ID;
+use ahash::HashMap;
+use tokio::sync::oneshot;
+
+/// 事件的分发方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DispatchMode {
+    /// 每个事件都并发处理，后收到的消息可能先被处理
+    #[default]
+    Concurrent,
+    /// 同一会话（同一个群，或同一个私聊对象）中的消息按收到的顺序处理，上一条消息的监听全部结束后才处理下一条，
+    /// 不同会话之间仍然并发处理。不是消息的事件不受影响。
+    Ordered,
+}
+
+/// 会话，群消息以群区分，私聊消息以发送者区分
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct ConversationKey {
+    account: Account,
+    group: Option<ID>,
+    sender: Option<ID>,
+}
+
+impl ConversationKey {
+    pub(crate) fn of<T: MessageEventTrait + ?Sized>(account: Account, event: &T) -> Self {
+        match event.get_group_id() {
+            Some(group) => ConversationKey {
+                account,
+                group: Some(ID::new(group)),
+                sender: None,
+            },
+            None => ConversationKey {
+                account,
+                group: None,
+                sender: Some(ID::new(event.get_sender_id())),
+            },
+        }
+    }
+}
+
+/// 一个事件在会话中的位置
+///
+/// 按顺序处理的插件在执行监听之前等待上一个事件处理完。ticket 被 drop 时表示此事件处理完，
+/// 但仍会先等待上一个事件，保证会话中的顺序可以传递。
+pub(crate) struct OrderTicket {
+    prev: Option<oneshot::Receiver<()>>,
+    done: Option<oneshot::Sender<()>>,
+}
+
+impl OrderTicket {
+    /// 等待会话中的上一个事件处理完，只有第一次调用会等待
+    pub(crate) async fn wait_prev(&mut self) {
+        if let Some(prev) = self.prev.take() {
+            let _ = prev.await;
+        }
+    }
+}
+
+impl Drop for OrderTicket {
+    fn drop(&mut self) {
+        let done = self.done.take();
+        if let Some(prev) = self.prev.take() {
+            tokio::spawn(async move {
+                let _ = prev.await;
+                drop(done);
+            });
+        }
+    }
+}
+
+/// 每个会话中最后一个事件的完成信号
+pub(crate) struct ConversationQueue {
+    tails: HashMap<ConversationKey, oneshot::Receiver<()>>,
+    prune_at: usize,
+}
+
+impl Default for ConversationQueue {
+    fn default() -> Self {
+        ConversationQueue {
+            tails: HashMap::default(),
+            prune_at: Self::MIN_PRUNE_AT,
+        }
+    }
+}
+
+impl ConversationQueue {
+    const MIN_PRUNE_AT: usize = 1024;
+
+    /// 把事件排到会话的末尾
+    pub(crate) fn ticket(&mut self, key: ConversationKey) -> OrderTicket {
+        let (done, tail) = oneshot::channel();
+        let prev = self.tails.insert(key, tail);
+
+        // 清理已经处理完的会话
+        if self.tails.len() > self.prune_at {
+            self.tails.retain(|_, tail| {
+                matches!(tail.try_recv(), Err(oneshot::error::TryRecvError::Empty))
+            });
+            self.prune_at = (self.tails.len() * 2).max(Self::MIN_PRUNE_AT);
+        }
+
+        OrderTicket {
+            prev,
+            done: Some(done),
+        }
+    }
+}
+
+impl Bot {
+    /// 设置事件的分发方式，插件自己设置的分发方式优先，默认为 [`DispatchMode::Concurrent`]
+    pub fn set_dispatch_mode(mut self, mode: DispatchMode) -> Self {
+        self.set_dispatch_mode_ref(mode);
+        self
+    }
+
+    /// 设置事件的分发方式，插件自己设置的分发方式优先，默认为 [`DispatchMode::Concurrent`]
+    pub fn set_dispatch_mode_ref(&mut self, mode: DispatchMode) {
+        self.dispatch_mode = mode;
+    }
+}
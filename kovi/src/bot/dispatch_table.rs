@@ -0,0 +1,236 @@
+use crate::bot::account::Account;
+use crate::bot::dispatch::{ConversationKey, DispatchMode};
+use crate::bot::failure::Failures;
+use crate::bot::middleware::EventMiddleware;
+use crate::bot::runtimebot::RuntimeBot;
+#[cfg(feature = "plugin-access-control")]
+use crate::bot::runtimebot::kovi_api::{AccessControlMode, AccessList};
+use crate::bot::{Bot, BotInformation};
+use crate::driver::Driver;
+use crate::event::InternalEvent;
+use crate::plugin::plugin_builder::ListenInner;
+use crate::types::ApiAndOptOneshot;
+use arc_swap::ArcSwap;
+use parking_lot::RwLock;
+use std::any::TypeId;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::{mpsc, watch};
+
+/// 事件的分发表
+///
+/// 收到事件时需要的所有信息都预先算好，插件挂载、启用、停用、注册监听或修改名单时重新生成，
+/// 收到事件时只需原子地取得当前的分发表，不需要锁住 Bot。
+pub(crate) struct DispatchTable {
+    pub(crate) accounts: Vec<AccountEntry>,
+    pub(crate) information: Arc<RwLock<BotInformation>>,
+    pub(crate) middlewares: Arc<Vec<Arc<dyn EventMiddleware>>>,
+    pub(crate) plugins: ahash::HashMap<Arc<String>, Arc<PluginEntry>>,
+    /// 按优先级从高到低分组的监听
+    pub(crate) groups: Vec<ListenerGroup>,
+    /// 是否有插件按会话顺序处理
+    pub(crate) ordered: bool,
+    pub(crate) failures: Arc<Failures>,
+}
+
+/// 已经运行的账号
+pub(crate) struct AccountEntry {
+    pub(crate) account: Account,
+    pub(crate) drive: Arc<dyn Driver>,
+    pub(crate) api_tx: mpsc::Sender<ApiAndOptOneshot>,
+}
+
+pub(crate) struct PluginEntry {
+    pub(crate) name: Arc<String>,
+    #[cfg(feature = "plugin-access-control")]
+    pub(crate) acc: AccCache,
+    pub(crate) enabled: watch::Receiver<bool>,
+    /// 是否按会话顺序处理
+    pub(crate) ordered: bool,
+    /// 监听中注册监听时使用，Bot 运行后才会存在
+    pub(crate) runtime_bot: Option<Arc<RuntimeBot>>,
+}
+
+#[cfg(feature = "plugin-access-control")]
+pub(crate) struct AccCache {
+    pub(crate) access_control: bool,
+    pub(crate) list_mode: AccessControlMode,
+    pub(crate) access_list: AccessList,
+}
+
+/// 同一优先级的监听，以事件类型为索引，每种类型只需解析一次
+pub(crate) struct ListenerGroup {
+    pub(crate) types: TypeMap<Vec<(Arc<PluginEntry>, Arc<ListenInner>)>>,
+}
+
+impl DispatchTable {
+    fn build(bot: &Bot) -> Self {
+        let accounts = bot
+            .accounts
+            .iter()
+            .filter_map(|v| {
+                Some(AccountEntry {
+                    account: v.account.clone(),
+                    drive: v.drive.clone(),
+                    api_tx: v.api_tx.clone()?,
+                })
+            })
+            .collect();
+
+        let api_tx = bot.accounts.first().and_then(|v| v.api_tx.clone());
+        let plugins: ahash::HashMap<Arc<String>, Arc<PluginEntry>> = bot
+            .plugins
+            .iter()
+            .map(|(name, plugin)| {
+                let name = Arc::new(name.clone());
+                let entry = PluginEntry {
+                    name: name.clone(),
+                    #[cfg(feature = "plugin-access-control")]
+                    acc: AccCache {
+                        access_control: plugin.access_control,
+                        list_mode: plugin.list_mode,
+                        access_list: plugin.access_list.clone(),
+                    },
+                    enabled: plugin.enabled.subscribe(),
+                    ordered: plugin.dispatch_mode.unwrap_or(bot.dispatch_mode)
+                        == DispatchMode::Ordered,
+                    runtime_bot: api_tx.clone().map(|api_tx| {
+                        Arc::new(RuntimeBot {
+                            bot: bot.this.clone(),
+                            plugin_name: name.to_string(),
+                            api_tx,
+                        })
+                    }),
+                };
+                (name, Arc::new(entry))
+            })
+            .collect();
+
+        let mut listeners: Vec<(Arc<PluginEntry>, Arc<ListenInner>)> = bot
+            .plugins
+            .iter()
+            .flat_map(|(name, plugin)| {
+                let entry = &plugins[name];
+                plugin
+                    .listen
+                    .list
+                    .iter()
+                    .map(|listen| (entry.clone(), listen.clone()))
+            })
+            .collect();
+        listeners.sort_by_key(|(_, listen)| std::cmp::Reverse(listen.priority));
+
+        let groups = listeners
+            .chunk_by(|(_, a), (_, b)| a.priority == b.priority)
+            .map(|group| {
+                let mut types: TypeMap<Vec<_>> = Default::default();
+                for (entry, listen) in group {
+                    types
+                        .entry(listen.type_id)
+                        .or_default()
+                        .push((entry.clone(), listen.clone()));
+                }
+                ListenerGroup { types }
+            })
+            .collect();
+
+        DispatchTable {
+            accounts,
+            information: bot.information.clone(),
+            middlewares: bot.middlewares.event.clone(),
+            ordered: plugins.values().any(|entry| entry.ordered),
+            plugins,
+            groups,
+            failures: bot.failures.clone(),
+        }
+    }
+
+    pub(crate) fn empty(information: Arc<RwLock<BotInformation>>, failures: Arc<Failures>) -> Self {
+        DispatchTable {
+            accounts: Vec::new(),
+            information,
+            middlewares: Default::default(),
+            plugins: Default::default(),
+            groups: Vec::new(),
+            ordered: false,
+            failures,
+        }
+    }
+
+    pub(crate) fn account(&self, account: &Account) -> Option<&AccountEntry> {
+        self.accounts.iter().find(|v| &v.account == account)
+    }
+
+    /// 有插件按顺序处理时，返回消息事件所属的会话
+    pub(crate) fn conversation_key(
+        &self,
+        account: &Account,
+        event: &InternalEvent,
+    ) -> Option<ConversationKey> {
+        if !self.ordered {
+            return None;
+        }
+
+        let entry = self.account(account)?;
+        let info = self.information.read();
+        let msg_event =
+            (entry.drive.message_event_register().type_de)(event, &info, &entry.api_tx)?;
+        Some(ConversationKey::of(account.clone(), &*msg_event))
+    }
+}
+
+impl Bot {
+    /// 插件或 Bot 的设置变化后重新生成分发表
+    pub(crate) fn refresh_dispatch_table(&self) {
+        self.dispatch_table
+            .store(Arc::new(DispatchTable::build(self)));
+    }
+
+    /// 标记分发表需要重新生成，插件的 `main` 返回后或收到下一个事件前统一生成
+    ///
+    /// `main` 中通常会连续注册许多监听，每次都重新生成分发表的开销与监听数的平方成正比。
+    pub(crate) fn mark_dispatch_table_dirty(&self) {
+        self.dispatch_table_dirty.store(true, Ordering::Release);
+    }
+
+    /// 分发表被标记过时重新生成
+    pub(crate) fn refresh_dirty_dispatch_table(&self) {
+        if self.dispatch_table_dirty.swap(false, Ordering::AcqRel) {
+            self.refresh_dispatch_table();
+        }
+    }
+
+    pub(crate) fn dispatch_table(&self) -> Arc<ArcSwap<DispatchTable>> {
+        self.dispatch_table.clone()
+    }
+
+    pub(crate) fn dispatch_table_dirty(&self) -> Arc<AtomicBool> {
+        self.dispatch_table_dirty.clone()
+    }
+}
+
+/// 以 TypeId 为键的表
+pub(crate) type TypeMap<V> =
+    std::collections::HashMap<TypeId, V, std::hash::BuildHasherDefault<IdHasher>>;
+
+/// With TypeIds as keys, there's no need to hash them. They are already hashes
+/// themselves, coming from the compiler. The IdHasher holds the u64 of
+/// the TypeId, and then returns it, instead of doing any bit fiddling.
+#[derive(Default, Debug)]
+pub(crate) struct IdHasher(u64);
+
+impl std::hash::Hasher for IdHasher {
+    fn write(&mut self, _: &[u8]) {
+        unreachable!("TypeId calls write_u64");
+    }
+
+    #[inline]
+    fn write_u64(&mut self, id: u64) {
+        self.0 = id;
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
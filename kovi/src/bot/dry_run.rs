@@ -0,0 +1,254 @@
+//! 试运行
+//!
+//! 开启后，会改变状态的 API 不会交给驱动，只记录到日志，并得到一个成功的返回，
+//! 其余 API（如 `get_group_member_list`）照常发出，事件也照常接收。
+//!
+//! # Examples
+//! ```ignore
+//! // 启动时开启
+//! let bot = Bot::build(conf, driver).set_dry_run(true);
+//!
+//! // 运行时由主管理员通过 `/dry-run on`、`/dry-run off` 切换
+//! PluginBuilder::commands(CommandSet::<MsgEvent>::new().register(dry_run_command()));
+//! ```
+
+use crate::bot::{ApiReturn, Bot, SendApi};
+use crate::command::args::Arg;
+use crate::command::{Command, CommandDef, Permission};
+use crate::error::BotError;
+use crate::event::{MessageEventTrait, RepliableEvent};
+use crate::{PluginBuilder, RuntimeBot};
+use ahash::HashSet;
+use parking_lot::RwLock;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 默认视为会改变状态的 API，包括 OneBot 与 Milky 中发送、撤回、踢人、禁言、修改资料等
+pub const DEFAULT_MUTATING_ACTIONS: &[&str] = &[
+    // OneBot
+    "send_msg",
+    "send_private_msg",
+    "send_group_msg",
+    "send_forward_msg",
+    "send_private_forward_msg",
+    "send_group_forward_msg",
+    "delete_msg",
+    "send_like",
+    "set_group_kick",
+    "set_group_ban",
+    "set_group_anonymous_ban",
+    "set_group_whole_ban",
+    "set_group_admin",
+    "set_group_anonymous",
+    "set_group_card",
+    "set_group_name",
+    "set_group_leave",
+    "set_group_special_title",
+    "set_friend_add_request",
+    "set_group_add_request",
+    "clean_cache",
+    // Milky
+    "send_private_message",
+    "send_group_message",
+    "recall_private_message",
+    "recall_group_message",
+    "mark_message_as_read",
+    "send_friend_nudge",
+    "send_profile_like",
+    "delete_friend",
+    "set_avatar",
+    "set_nickname",
+    "set_bio",
+    "set_peer_pin",
+    "set_group_avatar",
+    "set_group_member_card",
+    "set_group_member_special_title",
+    "set_group_member_admin",
+    "set_group_member_mute",
+    "set_group_whole_mute",
+    "kick_group_member",
+    "send_group_announcement",
+    "delete_group_announcement",
+    "set_group_essence_message",
+    "quit_group",
+    "send_group_message_reaction",
+    "send_group_nudge",
+    "accept_friend_request",
+    "reject_friend_request",
+    "accept_group_request",
+    "reject_group_request",
+    "accept_group_invitation",
+    "reject_group_invitation",
+    "upload_private_file",
+    "upload_group_file",
+    "move_group_file",
+    "rename_group_file",
+    "delete_group_file",
+    "create_group_folder",
+    "rename_group_folder",
+    "delete_group_folder",
+];
+
+/// 发送消息的 API，试运行时返回一个虚构的消息 ID
+pub const MESSAGE_SENDING_ACTIONS: &[&str] = &[
+    // OneBot
+    "send_msg",
+    "send_private_msg",
+    "send_group_msg",
+    "send_forward_msg",
+    "send_private_forward_msg",
+    "send_group_forward_msg",
+    // Milky
+    "send_private_message",
+    "send_group_message",
+];
+
+/// 试运行的开关与拦截的 API
+pub(crate) struct DryRun {
+    enabled: AtomicBool,
+    /// 视为会改变状态的 API 的 action
+    mutating: RwLock<HashSet<String>>,
+}
+
+impl Default for DryRun {
+    fn default() -> Self {
+        DryRun {
+            enabled: AtomicBool::new(false),
+            mutating: RwLock::new(
+                DEFAULT_MUTATING_ACTIONS
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl DryRun {
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// 需要拦截时，返回代替驱动的返回
+    pub(crate) fn intercept(&self, api: &SendApi) -> Option<ApiReturn> {
+        if !self.is_enabled() || !self.mutating.read().contains(&api.action) {
+            return None;
+        }
+        log::info!("[dry-run] {} {}", api.action, api.params);
+        Some(synthetic_return(&api.action))
+    }
+}
+
+/// 发送消息的 API 需要返回消息 ID，其余返回空数据
+fn synthetic_return(action: &str) -> ApiReturn {
+    let data = if MESSAGE_SENDING_ACTIONS.contains(&action) {
+        json!({
+            "message_id": 0,
+            "message_seq": 0,
+            "time": chrono::Utc::now().timestamp(),
+        })
+    } else {
+        Value::Null
+    };
+    ApiReturn {
+        status: "ok".to_string(),
+        retcode: 0,
+        message: None,
+        data,
+    }
+}
+
+impl Bot {
+    /// 开启或关闭试运行，运行时可以通过 [`dry_run_command()`] 切换
+    ///
+    /// 试运行时，会改变状态的 API（见 [`Bot::set_dry_run_actions()`]）不会交给驱动，
+    /// 只记录到日志，并得到一个成功的返回。
+    pub fn set_dry_run(mut self, enabled: bool) -> Self {
+        self.set_dry_run_ref(enabled);
+        self
+    }
+
+    /// 开启或关闭试运行，见 [`Bot::set_dry_run()`]
+    pub fn set_dry_run_ref(&mut self, enabled: bool) {
+        self.dry_run.set_enabled(enabled);
+    }
+
+    /// 设置试运行时拦截的 API，默认为 [`DEFAULT_MUTATING_ACTIONS`]
+    pub fn set_dry_run_actions<I, T>(mut self, actions: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.set_dry_run_actions_ref(actions);
+        self
+    }
+
+    /// 设置试运行时拦截的 API，默认为 [`DEFAULT_MUTATING_ACTIONS`]
+    pub fn set_dry_run_actions_ref<I, T>(&mut self, actions: I)
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        *self.dry_run.mutating.write() = actions.into_iter().map(Into::into).collect();
+    }
+}
+
+/// 只有主管理员可用的 `dry-run [on|off]` 命令，切换或查看试运行
+///
+/// 需要在插件的 `main` 中调用。
+pub fn dry_run_command<E>() -> CommandDef<E>
+where
+    E: MessageEventTrait + RepliableEvent,
+{
+    let bot = PluginBuilder::get_runtime_bot();
+    let command = Command::new("dry-run")
+        .about("切换试运行")
+        .arg(Arg::string("mode").optional())
+        .permission(Permission::MainAdmin);
+    CommandDef::new(command, move |event: Arc<E>, args| {
+        let bot = bot.clone();
+        async move {
+            let result = match args.str("mode") {
+                Some("on") => bot.set_dry_run(true),
+                Some("off") => bot.set_dry_run(false),
+                Some(_) => {
+                    event.reply("Usage: dry-run [on|off]");
+                    return;
+                }
+                None => Ok(()),
+            };
+            match result.and_then(|_| bot.is_dry_run()) {
+                Ok(true) => event.reply("dry-run: on"),
+                Ok(false) => event.reply("dry-run: off"),
+                Err(e) => log::error!("Failed to set dry-run: {e}"),
+            }
+        }
+    })
+}
+
+impl RuntimeBot {
+    /// 开启或关闭试运行，对所有账号立即生效
+    ///
+    /// 不向插件公开，插件通过只有主管理员可用的 [`dry_run_command()`] 切换。
+    pub(crate) fn set_dry_run(&self, enabled: bool) -> Result<(), BotError> {
+        let bot = self.bot.upgrade().ok_or(BotError::RefExpired)?;
+        bot.read().dry_run.set_enabled(enabled);
+        Ok(())
+    }
+
+    /// 是否正在试运行
+    ///
+    /// # error
+    ///
+    /// 如果此 `RuntimeBot` 实例内部的 `Bot` 中已经不存在，将会返回Err `BotError::RefExpired` 。
+    pub fn is_dry_run(&self) -> Result<bool, BotError> {
+        let bot = self.bot.upgrade().ok_or(BotError::RefExpired)?;
+        let enabled = bot.read().dry_run.is_enabled();
+        Ok(enabled)
+    }
+}
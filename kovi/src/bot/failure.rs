@@ -0,0 +1,239 @@
+use crate::bot::account::Account;
+use crate::bot::dispatch_table::DispatchTable;
+use crate::bot::handler::InternalInternalEvent;
+use crate::bot::runtimebot::kovi_api::disable_plugin;
+use crate::bot::runtimebot::send_api_request_with_forget;
+use crate::bot::{Bot, BotInformation};
+use crate::event::{Event, InternalEvent};
+use crate::types::ApiAndOptOneshot;
+use parking_lot::{Mutex, RwLock};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, OnceLock, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// 插件监听失败后的处理方式
+///
+/// 失败总会记录到日志，并发出 [`PluginFailureEvent`]。
+/// 处理 [`PluginFailureEvent`] 时的失败只记录与计数，不会再发出事件或通知主管理员，以免循环。
+///
+/// # Examples
+/// ```ignore
+/// use kovi::bot::failure::FailurePolicy;
+///
+/// // 一分钟内失败 5 次时关闭插件，并私聊通知主管理员
+/// let bot = Bot::build(conf, driver).set_failure_policy(FailurePolicy {
+///     max_failures: 5,
+///     window: Duration::from_secs(60),
+///     notify_main_admin: true,
+/// });
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailurePolicy {
+    /// 在 `window` 内失败达到此次数时关闭插件，为 0 时不关闭
+    pub max_failures: usize,
+    /// 统计失败次数的时间窗口
+    pub window: Duration,
+    /// 是否私聊通知主管理员，需要驱动支持
+    pub notify_main_admin: bool,
+}
+
+impl Default for FailurePolicy {
+    fn default() -> Self {
+        FailurePolicy {
+            max_failures: 0,
+            window: Duration::from_secs(60),
+            notify_main_admin: false,
+        }
+    }
+}
+
+/// 监听失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FailureKind {
+    /// 监听返回了错误
+    Error(String),
+    /// 监听发生了 panic
+    Panic(String),
+}
+
+impl fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailureKind::Error(e) => write!(f, "returned an error: {e}"),
+            FailureKind::Panic(e) => write!(f, "panicked: {e}"),
+        }
+    }
+}
+
+/// 插件的监听返回了错误或发生了 panic
+///
+/// 由 Kovi 发出，可以像其他事件一样监听。
+#[derive(Debug, Clone)]
+pub struct PluginFailureEvent {
+    /// 失败的插件
+    pub plugin: String,
+    /// 失败时处理的事件所属的账号
+    pub account: Account,
+    pub kind: FailureKind,
+    /// 插件是否因此被关闭
+    pub disabled: bool,
+}
+
+impl Event for PluginFailureEvent {
+    fn de(
+        event: &InternalEvent,
+        _: &BotInformation,
+        _: &mpsc::Sender<ApiAndOptOneshot>,
+    ) -> Option<Self> {
+        let InternalEvent::PluginFailure(event) = event else {
+            return None;
+        };
+        Some((**event).clone())
+    }
+}
+
+/// 记录插件的失败，并按 [`FailurePolicy`] 处理
+#[derive(Default)]
+pub(crate) struct Failures {
+    pub(crate) policy: RwLock<FailurePolicy>,
+    /// 各插件在时间窗口内的失败时间
+    recent: Mutex<ahash::HashMap<String, VecDeque<Instant>>>,
+    /// Bot 运行后才会存在
+    runtime: OnceLock<FailureRuntime>,
+}
+
+struct FailureRuntime {
+    bot: Weak<RwLock<Bot>>,
+    event_tx: mpsc::Sender<InternalInternalEvent>,
+}
+
+impl Failures {
+    pub(crate) fn start(
+        &self,
+        bot: Weak<RwLock<Bot>>,
+        event_tx: mpsc::Sender<InternalInternalEvent>,
+    ) {
+        let _ = self.runtime.set(FailureRuntime { bot, event_tx });
+    }
+
+    /// 插件开启时重新计数
+    pub(crate) fn reset(&self, plugin: &str) {
+        self.recent.lock().remove(plugin);
+    }
+
+    /// `from_failure` 为失败发生在处理 [`PluginFailureEvent`] 时
+    pub(crate) fn report(
+        &self,
+        table: &DispatchTable,
+        plugin: &str,
+        account: Account,
+        kind: FailureKind,
+        from_failure: bool,
+    ) {
+        log::error!("Plugin '{plugin}' {kind}");
+
+        let policy = self.policy.read().clone();
+        let disabled = policy.max_failures > 0 && self.record(plugin, &policy);
+
+        if policy.notify_main_admin && !from_failure {
+            notify_main_admin(table, plugin, &kind, disabled);
+        }
+
+        let Some(runtime) = self.runtime.get() else {
+            return;
+        };
+
+        if disabled {
+            log::warn!(
+                "Plugin '{plugin}' failed {} times within {:?}, disabling it",
+                policy.max_failures,
+                policy.window
+            );
+            if let Some(bot) = runtime.bot.upgrade()
+                && let Err(e) = disable_plugin(bot, plugin)
+            {
+                log::error!("Failed to disable plugin '{plugin}': {e}");
+            }
+        }
+
+        if from_failure {
+            return;
+        }
+        let event = PluginFailureEvent {
+            plugin: plugin.to_string(),
+            account: account.clone(),
+            kind,
+            disabled,
+        };
+        let event_tx = runtime.event_tx.clone();
+        tokio::spawn(async move {
+            let event = InternalEvent::PluginFailure(Arc::new(event));
+            let _ = event_tx
+                .send(InternalInternalEvent::DriverEvent(account, Box::new(event)))
+                .await;
+        });
+    }
+
+    /// 记录一次失败，返回是否达到关闭插件的次数
+    fn record(&self, plugin: &str, policy: &FailurePolicy) -> bool {
+        let now = Instant::now();
+        let mut recent = self.recent.lock();
+        let times = recent.entry(plugin.to_string()).or_default();
+        times.push_back(now);
+        while times
+            .front()
+            .is_some_and(|v| now.duration_since(*v) > policy.window)
+        {
+            times.pop_front();
+        }
+        if times.len() >= policy.max_failures {
+            times.clear();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn notify_main_admin(table: &DispatchTable, plugin: &str, kind: &FailureKind, disabled: bool) {
+    // 通知总是由默认账号发出
+    let Some(entry) = table.accounts.first() else {
+        return;
+    };
+    let main_admin = table.information.read().get_main_admin().clone();
+    let mut text = format!("Plugin '{plugin}' {kind}");
+    if disabled {
+        text.push_str("\nThe plugin has been disabled");
+    }
+    match entry.drive.private_message_api(&main_admin, &text) {
+        Some(api) => send_api_request_with_forget(&entry.api_tx, api),
+        None => log::warn!("The driver does not support notifying the main admin"),
+    }
+}
+
+/// 取得 panic 的信息
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(v) = payload.downcast_ref::<&str>() {
+        v.to_string()
+    } else if let Some(v) = payload.downcast_ref::<String>() {
+        v.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+impl Bot {
+    /// 设置插件监听失败后的处理方式，见 [`FailurePolicy`]
+    pub fn set_failure_policy(mut self, policy: FailurePolicy) -> Self {
+        self.set_failure_policy_ref(policy);
+        self
+    }
+
+    /// 设置插件监听失败后的处理方式，见 [`FailurePolicy`]
+    pub fn set_failure_policy_ref(&mut self, policy: FailurePolicy) {
+        *self.failures.policy.write() = policy;
+    }
+}
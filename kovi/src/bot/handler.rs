@@ -1,35 +1,48 @@
 #[cfg(feature = "plugin-access-control")]
 use crate::bot::AccessControlMode;
-use crate::bot::BotInformation;
+use crate::bot::account::{ACCOUNT, Account};
+use crate::bot::dispatch::OrderTicket;
 #[cfg(feature = "plugin-access-control")]
-use crate::bot::runtimebot::kovi_api::AccessList;
+use crate::bot::dispatch_table::AccCache;
+use crate::bot::dispatch_table::{DispatchTable, TypeMap};
+use crate::bot::failure::{FailureKind, panic_message};
+use crate::bot::middleware::{ANNOTATIONS, Annotations, EventContext, Flow, run_event_middlewares};
 use crate::{Bot, ExitEvent};
 
-use crate::event::{Event, InternalEvent, MessageEventTrait};
-use crate::plugin::PLUGIN_NAME;
+use crate::event::{Event, InternalEvent, MessageEventTrait, PROPAGATION, ParseCache};
 use crate::plugin::plugin_builder::ListenInner;
+use crate::plugin::{PLUGIN_NAME, PLUGIN_RUNTIME_BOT};
+use crate::session::SESSIONS;
 use crate::types::ApiAndOptOneshot;
+use futures_util::FutureExt as _;
+use futures_util::future::Either;
 use parking_lot::RwLock;
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::sync::{mpsc, watch};
 
 /// Kovi内部事件
 #[derive(Clone)]
 pub(crate) enum InternalInternalEvent {
     Exit(ExitEvent),
-    DriverEvent(Box<InternalEvent>),
+    /// 事件与收到它的账号
+    DriverEvent(Account, Box<InternalEvent>),
 }
 
 impl Bot {
+    /// `ticket` 为消息在会话中的位置，只有需要按会话顺序处理时存在
     pub(crate) async fn handler_event(
         bot: Arc<RwLock<Self>>,
+        table: Arc<DispatchTable>,
+        cache: Arc<ParseCache>,
         event: InternalInternalEvent,
-        api_tx: mpsc::Sender<ApiAndOptOneshot>,
+        ticket: Option<OrderTicket>,
     ) {
         match event {
             InternalInternalEvent::Exit(_) => Self::handle_kovi_exit(bot).await,
-            InternalInternalEvent::DriverEvent(msg) => {
-                Self::handler_internal_event(bot, *msg, api_tx).await
+            InternalInternalEvent::DriverEvent(account, msg) => {
+                Self::handler_internal_event(table, cache, account, *msg, ticket).await
             }
         }
     }
@@ -53,129 +66,204 @@ impl Bot {
         }
     }
 
+    /// `cache` 为此事件的解析缓存，此事件的所有解析都经过它
     async fn handler_internal_event(
-        bot: Arc<RwLock<Self>>,
+        table: Arc<DispatchTable>,
+        cache: Arc<ParseCache>,
+        account: Account,
         msg: InternalEvent,
-        api_tx: mpsc::Sender<ApiAndOptOneshot>,
+        ticket: Option<OrderTicket>,
     ) {
-        let bot_read = bot.read();
-        let drive = bot_read.drive.clone();
-        let info = &bot_read.information;
-
-        let plugin_iter = bot_read.plugins.iter();
-
-        let plugin_cache = plugin_iter
-            .clone()
-            .map(|(name, plugin)| {
-                let name = Arc::new(name.to_owned());
-                (
-                    name.clone(),
-                    PluginCache {
-                        name,
-                        #[cfg(feature = "plugin-access-control")]
-                        acc: AccCache::new(
-                            plugin.access_control,
-                            plugin.list_mode,
-                            plugin.access_list.clone(),
-                        ),
-                        bot_info: info.clone(),
-                        enabled: plugin.enabled.subscribe(),
-                    },
+        // 用收到事件的账号的驱动解析消息事件，事件中的 api_tx 也指向这个账号
+        let Some(account_entry) = table.account(&account) else {
+            log::error!("Received an event from unknown account '{account}'");
+            return;
+        };
+        let parse_msg_event = |msg: &InternalEvent| {
+            cache.enter(|| {
+                (account_entry.drive.message_event_register().type_de)(
+                    msg,
+                    &table.information.read(),
+                    &account_entry.api_tx,
                 )
             })
-            .collect::<ahash::HashMap<Arc<String>, PluginCache>>();
-
-        let type_plugin_map = {
-            let mut type_plugin_map: PluginMap = Default::default();
-            for (name, plugin) in plugin_iter {
-                for listen in &plugin.listen.list {
-                    let plugin_map = type_plugin_map.entry(listen.type_id).or_default();
-
-                    let plugin_vec = plugin_map
-                        .plugins
-                        .entry(plugin_cache[name].name.clone())
-                        .or_default();
-
-                    plugin_vec.push(listen.clone());
-                }
-            }
-            type_plugin_map
         };
 
-        let msg_event =
-            (drive.message_event_register().type_de)(&msg, &info.read(), &api_tx).map(|e| {
-                log_msg_event(&*e);
-                e
-            });
+        let msg_event = parse_msg_event(&msg).map(|e| {
+            log_msg_event(&*e);
+            e
+        });
 
-        drop(bot_read);
+        // 事件中间件可以查看、修改、标注或拦截事件
+        let mut ctx = EventContext::new(account, msg, msg_event);
+        if run_event_middlewares(&table.middlewares, &mut ctx) == Flow::Stop {
+            return;
+        }
+        let EventContext {
+            account,
+            event: msg,
+            message: msg_event,
+            modified,
+            annotations,
+            skipped,
+        } = ctx;
+        let msg_event = if modified {
+            cache.clear();
+            parse_msg_event(&msg)
+        } else {
+            msg_event
+        };
+        let api_tx = account_entry.api_tx.clone();
+
+        // 正在等待下一条消息的会话先取走事件，取走事件的插件不再处理它
+        let mut consumed = cache.enter(|| {
+            SESSIONS.deliver(&account, &msg, &table.information.read(), &api_tx, |name| {
+                let Some(plugin) = table.plugins.get(name) else {
+                    return false;
+                };
+                // 与监听一样判断黑白名单
+                #[cfg(feature = "plugin-access-control")]
+                if let Some(msg_event) = &msg_event
+                    && !is_access(&plugin.acc, &**msg_event)
+                {
+                    return false;
+                }
+                *plugin.enabled.borrow()
+            })
+        });
+        // 被中间件跳过的插件同样不处理它
+        consumed.extend(skipped.into_iter().map(Arc::new));
 
         struct SharedData {
             msg: InternalEvent,
+            account: Account,
             api_tx: mpsc::Sender<ApiAndOptOneshot>,
-            plugin_cache: ahash::HashMap<Arc<String>, PluginCache>,
+            cache: Arc<ParseCache>,
+            consumed: ahash::HashSet<Arc<String>>,
+            annotations: Arc<Annotations>,
         }
 
         let shared_data = Arc::new(SharedData {
             msg,
+            account,
             api_tx,
-            plugin_cache,
+            cache,
+            consumed,
+            annotations: Arc::new(annotations),
         });
 
-        for plugin_map in type_plugin_map.into_values() {
-            tokio::spawn(type_handler(
-                plugin_map,
-                msg_event.clone(),
-                shared_data.clone(),
-            ));
-        }
+        tokio::spawn(dispatch(table, msg_event, shared_data, ticket));
 
-        async fn type_handler(
-            plugin_map: EventHandler,
+        /// 按优先级从高到低分组执行监听，同一组并发执行，有监听停止传播时不再执行之后的组
+        #[cfg_attr(not(feature = "plugin-access-control"), allow(unused_variables))]
+        async fn dispatch(
+            table: Arc<DispatchTable>,
             msg_event: Option<Arc<dyn MessageEventTrait>>,
             shared_data: Arc<SharedData>,
+            mut ticket: Option<OrderTicket>,
         ) {
-            let mut event_cache: Option<Arc<dyn Event>> = None;
-            for (name, plugin_vec) in plugin_map.plugins.into_iter() {
-                let plugin_cache = &shared_data.plugin_cache[&name];
-
-                #[cfg(feature = "plugin-access-control")]
-                if let Some(event) = &msg_event {
-                    // 判断是否黑白名单
-                    if !is_access(&plugin_cache.acc, &**event) {
-                        continue;
-                    }
-                }
-
-                for listen in plugin_vec {
-                    let event = match &event_cache {
-                        Some(v) => v.clone(),
-                        None => {
-                            let event_opt = (listen.type_de)(
+            let mut event_cache: TypeMap<Option<Arc<dyn Event>>> = Default::default();
+            let stopped = Arc::new(AtomicBool::new(false));
+            let mut groups = table.groups.iter().peekable();
+            while let Some(group) = groups.next() {
+                let mut listeners = Vec::new();
+                for (type_id, type_listeners) in &group.types {
+                    // 同一种事件只解析一次
+                    let event = event_cache.entry(*type_id).or_insert_with(|| {
+                        let (_, listen) = type_listeners.first()?;
+                        shared_data.cache.enter(|| {
+                            (listen.type_de)(
                                 &shared_data.msg,
-                                &plugin_cache.bot_info.read(),
+                                &table.information.read(),
                                 &shared_data.api_tx,
-                            );
+                            )
+                        })
+                    });
+                    let Some(event) = event else {
+                        continue;
+                    };
+
+                    for (plugin, listen) in type_listeners {
+                        if shared_data.consumed.contains(&plugin.name) {
+                            continue;
+                        }
 
-                            match event_opt {
-                                Some(event) => {
-                                    event_cache = Some(event.clone());
-                                    event
-                                }
-                                None => return,
+                        #[cfg(feature = "plugin-access-control")]
+                        if let Some(msg_event) = &msg_event {
+                            // 判断是否黑白名单
+                            if !is_access(&plugin.acc, &**msg_event) {
+                                continue;
                             }
                         }
-                    };
 
-                    let name = name.clone();
-                    let enabled = plugin_cache.enabled.clone();
+                        listeners.push((plugin, listen, event.clone()));
+                    }
+                }
+                // 先启动并发处理的插件，避免它们跟着按顺序处理的插件一起等待
+                listeners.sort_by_key(|(plugin, _, _)| plugin.ordered);
+
+                let mut handles = Vec::with_capacity(listeners.len());
+                for (plugin, listen, event) in listeners {
+                    // 按顺序处理的插件等待会话中的上一条消息处理完
+                    if plugin.ordered
+                        && let Some(ticket) = &mut ticket
+                    {
+                        ticket.wait_prev().await;
+                    }
 
-                    tokio::spawn(async move {
+                    let name = plugin.name.clone();
+                    let listen = listen.clone();
+                    let account = shared_data.account.clone();
+                    let annotations = shared_data.annotations.clone();
+                    let stopped = stopped.clone();
+                    let enabled = plugin.enabled.clone();
+                    let runtime_bot = plugin.runtime_bot.clone();
+                    let table = table.clone();
+                    let from_failure = matches!(shared_data.msg, InternalEvent::PluginFailure(_));
+
+                    let handle = tokio::spawn(async move {
+                        let listen = ANNOTATIONS.scope(annotations, handle_listen(listen, event));
+                        let listen = PROPAGATION.scope(stopped, listen);
+                        let listen = match runtime_bot {
+                            Some(runtime_bot) => {
+                                Either::Left(PLUGIN_RUNTIME_BOT.scope(runtime_bot, listen))
+                            }
+                            None => Either::Right(listen),
+                        };
+                        let listen =
+                            PLUGIN_NAME.scope(name.clone(), ACCOUNT.scope(account.clone(), listen));
+                        // panic 只影响这一次监听，并记在插件名下
                         tokio::select! {
-                            _ = PLUGIN_NAME.scope(name, handle_listen(listen, event)) => {}
+                            result = AssertUnwindSafe(listen).catch_unwind() => {
+                                let kind = match result {
+                                    Ok(Ok(())) => return,
+                                    Ok(Err(e)) => FailureKind::Error(e),
+                                    Err(payload) => FailureKind::Panic(panic_message(&*payload)),
+                                };
+                                table
+                                    .failures
+                                    .report(&table, &name, account, kind, from_failure);
+                            }
                             _ = monitor_enabled_state(enabled) => {}
                         }
                     });
+                    handles.push((plugin.ordered, handle));
+                }
+
+                // 最后一组不需要等待，除非此消息需要在会话中按顺序处理完
+                if groups.peek().is_none() {
+                    if ticket.is_some() {
+                        for (_, handle) in handles.into_iter().filter(|(ordered, _)| *ordered) {
+                            let _ = handle.await;
+                        }
+                    }
+                    break;
+                }
+                for (_, handle) in handles {
+                    let _ = handle.await;
+                }
+                if stopped.load(Ordering::Relaxed) {
+                    break;
                 }
             }
         }
@@ -208,38 +296,11 @@ impl Bot {
             );
         }
 
-        async fn handle_listen(listen: Arc<ListenInner>, cache_event: Arc<dyn Event + 'static>) {
-            (*listen.handler)(cache_event).await;
-        }
-    }
-}
-
-struct PluginCache {
-    name: Arc<String>,
-    #[cfg(feature = "plugin-access-control")]
-    acc: AccCache,
-    bot_info: Arc<RwLock<BotInformation>>,
-    enabled: watch::Receiver<bool>,
-}
-
-#[cfg(feature = "plugin-access-control")]
-struct AccCache {
-    pub(crate) access_control: bool,
-    pub(crate) list_mode: AccessControlMode,
-    pub(crate) access_list: AccessList,
-}
-
-#[cfg(feature = "plugin-access-control")]
-impl AccCache {
-    pub fn new(
-        access_control: bool,
-        list_mode: AccessControlMode,
-        access_list: AccessList,
-    ) -> Self {
-        Self {
-            access_control,
-            list_mode,
-            access_list,
+        async fn handle_listen(
+            listen: Arc<ListenInner>,
+            cache_event: Arc<dyn Event + 'static>,
+        ) -> Result<(), String> {
+            (*listen.handler)(cache_event).await
         }
     }
 }
@@ -273,45 +334,3 @@ fn is_access<T: MessageEventTrait + ?Sized>(plugin: &AccCache, event: &T) -> boo
         }
     }
 }
-
-#[allow(dead_code)]
-#[derive(Default)]
-struct EventHandler {
-    plugins: ahash::HashMap<Arc<String>, Vec<Arc<ListenInner>>>,
-}
-
-#[allow(warnings)]
-type PluginMap<'a> = std::collections::HashMap<
-    std::any::TypeId,
-    EventHandler,
-    std::hash::BuildHasherDefault<IdHasher>,
->;
-
-#[allow(warnings)]
-type TypeEventCacheMap = std::collections::HashMap<
-    std::any::TypeId,
-    Option<Arc<dyn Event>>,
-    std::hash::BuildHasherDefault<IdHasher>,
->;
-
-/// With TypeIds as keys, there's no need to hash them. They are already hashes
-/// themselves, coming from the compiler. The IdHasher holds the u64 of
-/// the TypeId, and then returns it, instead of doing any bit fiddling.
-#[derive(Default, Debug)]
-struct IdHasher(u64);
-
-impl std::hash::Hasher for IdHasher {
-    fn write(&mut self, _: &[u8]) {
-        unreachable!("TypeId calls write_u64");
-    }
-
-    #[inline]
-    fn write_u64(&mut self, id: u64) {
-        self.0 = id;
-    }
-
-    #[inline]
-    fn finish(&self) -> u64 {
-        self.0
-    }
-}
@@ -0,0 +1,223 @@
+use crate::bot::SendApi;
+use crate::bot::account::Account;
+use crate::event::{InternalEvent, MessageEventTrait};
+use std::any::{Any, TypeId};
+use std::sync::Arc;
+
+tokio::task_local! {
+    pub(crate) static ANNOTATIONS: Arc<Annotations>;
+}
+
+/// 中间件的处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// 交给下一个中间件，最后交给插件或驱动
+    Continue,
+    /// 到此为止，事件不再交给插件，API 不再交给驱动
+    Stop,
+}
+
+/// 事件中间件，在事件交给插件的监听之前按注册顺序执行
+///
+/// 中间件在事件分发的路径上同步执行，应尽快返回。闭包 `Fn(&mut EventContext) -> Flow` 也是中间件。
+///
+/// # Examples
+/// ```ignore
+/// use kovi::bot::middleware::{EventContext, Flow};
+///
+/// let bot = Bot::build(conf, driver).middleware(|ctx: &mut EventContext| {
+///     let blocked = ctx
+///         .message()
+///         .is_some_and(|msg| msg.get_sender_id() == ID::new(10086));
+///     if blocked { Flow::Stop } else { Flow::Continue }
+/// });
+/// ```
+pub trait EventMiddleware: Send + Sync + 'static {
+    fn handle(&self, ctx: &mut EventContext) -> Flow;
+}
+
+impl<F> EventMiddleware for F
+where
+    F: Fn(&mut EventContext) -> Flow + Send + Sync + 'static,
+{
+    fn handle(&self, ctx: &mut EventContext) -> Flow {
+        self(ctx)
+    }
+}
+
+/// API 中间件，在 API 交给驱动之前按注册顺序执行，可以修改或拦截 API
+///
+/// 被拦截的 API 不会发出，等待返回值的调用方会收到 [`VETOED_RETCODE`] 的错误返回。
+/// 闭包 `Fn(&mut ApiContext) -> Flow` 也是 API 中间件。
+pub trait ApiMiddleware: Send + Sync + 'static {
+    fn handle(&self, ctx: &mut ApiContext) -> Flow;
+}
+
+impl<F> ApiMiddleware for F
+where
+    F: Fn(&mut ApiContext) -> Flow + Send + Sync + 'static,
+{
+    fn handle(&self, ctx: &mut ApiContext) -> Flow {
+        self(ctx)
+    }
+}
+
+/// 被 API 中间件拦截的 API 的返回码
+pub const VETOED_RETCODE: i32 = -403;
+
+/// 交给事件中间件的事件
+pub struct EventContext {
+    pub(crate) account: Account,
+    pub(crate) event: InternalEvent,
+    pub(crate) message: Option<Arc<dyn MessageEventTrait>>,
+    pub(crate) modified: bool,
+    pub(crate) annotations: Annotations,
+    pub(crate) skipped: Vec<String>,
+}
+
+impl EventContext {
+    pub(crate) fn new(
+        account: Account,
+        event: InternalEvent,
+        message: Option<Arc<dyn MessageEventTrait>>,
+    ) -> Self {
+        EventContext {
+            account,
+            event,
+            message,
+            modified: false,
+            annotations: Annotations::default(),
+            skipped: Vec::new(),
+        }
+    }
+
+    /// 收到事件的账号
+    pub fn account(&self) -> &Account {
+        &self.account
+    }
+
+    pub fn event(&self) -> &InternalEvent {
+        &self.event
+    }
+
+    /// 修改事件，所有中间件执行完后会重新解析事件
+    ///
+    /// 驱动事件的 json 是共享的，通过 `Arc::make_mut()` 修改。
+    pub fn event_mut(&mut self) -> &mut InternalEvent {
+        self.modified = true;
+        &mut self.event
+    }
+
+    /// 由驱动解析出的消息事件，不是消息时为 `None`
+    ///
+    /// 这是修改之前的事件，`event_mut()` 的修改在所有中间件执行完后才会生效。
+    pub fn message(&self) -> Option<&dyn MessageEventTrait> {
+        self.message.as_deref()
+    }
+
+    /// 给事件添加的标注，插件的监听中可以用 [`Annotations::current()`] 取得
+    pub fn annotations(&self) -> &Annotations {
+        &self.annotations
+    }
+
+    pub fn annotations_mut(&mut self) -> &mut Annotations {
+        &mut self.annotations
+    }
+
+    /// 不把此事件交给某个插件，例如按群开关插件
+    pub fn skip_plugin<T: Into<String>>(&mut self, plugin_name: T) {
+        self.skipped.push(plugin_name.into());
+    }
+}
+
+/// 交给 API 中间件的 API
+pub struct ApiContext {
+    pub(crate) account: Account,
+    pub(crate) api: SendApi,
+}
+
+impl ApiContext {
+    /// 发出 API 的账号
+    pub fn account(&self) -> &Account {
+        &self.account
+    }
+
+    pub fn api(&self) -> &SendApi {
+        &self.api
+    }
+
+    pub fn api_mut(&mut self) -> &mut SendApi {
+        &mut self.api
+    }
+}
+
+/// 中间件给事件添加的标注，以类型区分，每种类型最多一个
+#[derive(Default)]
+pub struct Annotations {
+    map: ahash::HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl Annotations {
+    /// 添加标注，返回同类型的旧标注
+    pub fn insert<T: Any + Send + Sync>(&mut self, value: T) -> Option<T> {
+        self.map
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|old| old.downcast().ok())
+            .map(|old| *old)
+    }
+
+    pub fn get<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.map.get(&TypeId::of::<T>())?.downcast_ref()
+    }
+
+    pub fn remove<T: Any + Send + Sync>(&mut self) -> Option<T> {
+        self.map
+            .remove(&TypeId::of::<T>())
+            .and_then(|old| old.downcast().ok())
+            .map(|old| *old)
+    }
+
+    pub fn contains<T: Any + Send + Sync>(&self) -> bool {
+        self.map.contains_key(&TypeId::of::<T>())
+    }
+
+    /// 获取当前正在处理的事件的标注
+    ///
+    /// 在插件的监听闭包中可用，其他地方会返回 `None`。
+    pub fn current() -> Option<Arc<Annotations>> {
+        ANNOTATIONS.try_with(|annotations| annotations.clone()).ok()
+    }
+}
+
+/// Bot 中注册的所有中间件
+#[derive(Clone, Default)]
+pub(crate) struct Middlewares {
+    pub(crate) event: Arc<Vec<Arc<dyn EventMiddleware>>>,
+    pub(crate) api: Arc<Vec<Arc<dyn ApiMiddleware>>>,
+}
+
+/// 依次执行事件中间件，有中间件返回 `Stop` 时返回 `Stop`
+pub(crate) fn run_event_middlewares(
+    middlewares: &[Arc<dyn EventMiddleware>],
+    ctx: &mut EventContext,
+) -> Flow {
+    for middleware in middlewares {
+        if middleware.handle(ctx) == Flow::Stop {
+            return Flow::Stop;
+        }
+    }
+    Flow::Continue
+}
+
+/// 依次执行 API 中间件，有中间件返回 `Stop` 时返回 `Stop`
+pub(crate) fn run_api_middlewares(
+    middlewares: &[Arc<dyn ApiMiddleware>],
+    ctx: &mut ApiContext,
+) -> Flow {
+    for middleware in middlewares {
+        if middleware.handle(ctx) == Flow::Stop {
+            return Flow::Stop;
+        }
+    }
+    Flow::Continue
+}
@@ -2,13 +2,16 @@ mod connect;
 
 use super::Bot;
 use crate::PluginBuilder;
+use crate::bot::dispatch::ConversationQueue;
 use crate::bot::handler::InternalInternalEvent;
-use crate::types::ApiAndOptOneshot;
+use crate::event::ParseCache;
+use crate::types::{ApiAndOptOneshot, PinFut};
 use log::error;
 use parking_lot::RwLock;
 use std::borrow::Borrow;
 use std::future::Future;
 use std::process::exit;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, LazyLock};
 use tokio::sync::{mpsc, watch};
 use tokio::task::JoinHandle;
@@ -36,43 +39,79 @@ impl Bot {
         Self::run_bot_inner(bot).await
     }
 
-    async fn run_bot_inner(bot: Arc<RwLock<Bot>>) -> ExitEvent {
+    pub(crate) async fn run_bot_inner(bot: Arc<RwLock<Bot>>) -> ExitEvent {
         //处理连接，从msg_tx返回消息
         let (self_event_tx, mut self_event_rx): (
             mpsc::Sender<InternalInternalEvent>,
             mpsc::Receiver<InternalInternalEvent>,
         ) = mpsc::channel(32);
 
-        // 接收插件的api
-        let (self_api_tx, self_api_rx): (
-            mpsc::Sender<ApiAndOptOneshot>,
-            mpsc::Receiver<ApiAndOptOneshot>,
-        ) = mpsc::channel(32);
-
         {
             let mut bot_write = bot.write();
-            let drive = bot_write.drive.clone();
 
             // drop检测
             bot_write.spawn(exit_signal_check(self_event_tx.clone()));
 
-            bot_write.spawn(connect::event_connect(self_event_tx.clone(), drive.clone()));
+            bot_write.this = Arc::downgrade(&bot);
+
+            // 插件失败事件同样经过事件通道
+            bot_write
+                .failures
+                .start(Arc::downgrade(&bot), self_event_tx.clone());
+
+            // 所有账号的驱动都退出后 Bot 才退出
+            let running = Arc::new(AtomicUsize::new(bot_write.accounts.len()));
+
+            let mut connect_tasks: Vec<PinFut> = Vec::with_capacity(bot_write.accounts.len() * 2);
+            let api_middlewares = bot_write.middlewares.api.clone();
+            let dry_run = bot_write.dry_run.clone();
+            for account_driver in bot_write.accounts.iter_mut() {
+                // 每个账号有自己的 api 通道，事件中的 api_tx 会把 api 发回收到事件的账号
+                let (api_tx, api_rx): (
+                    mpsc::Sender<ApiAndOptOneshot>,
+                    mpsc::Receiver<ApiAndOptOneshot>,
+                ) = mpsc::channel(32);
+                account_driver.api_tx = Some(api_tx);
+
+                let account = account_driver.account.clone();
+                let drive = account_driver.drive.clone();
+
+                connect_tasks.push(Box::pin(connect::event_connect(
+                    self_event_tx.clone(),
+                    drive.clone(),
+                    account.clone(),
+                    running.clone(),
+                )));
+                connect_tasks.push(Box::pin(connect::send_connect(
+                    api_rx,
+                    self_event_tx.clone(),
+                    drive,
+                    account,
+                    api_middlewares.clone(),
+                    dry_run.clone(),
+                )));
+            }
+            for task in connect_tasks {
+                bot_write.spawn(task);
+            }
 
-            bot_write.spawn(connect::send_connect(
-                self_api_rx,
-                self_event_tx,
-                drive.clone(),
-            ));
+            // 所有账号的 api 通道都已存在，生成分发表
+            bot_write.refresh_dispatch_table();
 
-            // 运行所有的main
+            // 运行所有的main，插件的 RuntimeBot 默认使用默认账号
+            let self_api_tx = bot_write.default_api_tx();
             bot_write.spawn({
                 let bot = bot.clone();
-                let self_api_tx = self_api_tx.clone();
                 async move { Self::run_mains(bot, self_api_tx) }
             });
         }
 
         let drop_task;
+        let (dispatch_table, dispatch_table_dirty) = {
+            let bot = bot.read();
+            (bot.dispatch_table(), bot.dispatch_table_dirty())
+        };
+        let mut conversations = ConversationQueue::default();
         //处理事件，每个事件都会来到这里
         let exit_event = loop {
             let event = match self_event_rx.recv().await {
@@ -83,19 +122,37 @@ impl Bot {
                 }
             };
 
-            let self_api_tx = self_api_tx.clone();
             let bot = bot.clone();
+            // 仍在运行的 main 中注册的监听同样能收到事件
+            if dispatch_table_dirty.load(Ordering::Acquire) {
+                bot.read().refresh_dirty_dispatch_table();
+            }
+            let table = dispatch_table.load_full();
+            let cache = Arc::new(ParseCache::new(match &event {
+                InternalInternalEvent::DriverEvent(account, _) => Some(account.clone()),
+                InternalInternalEvent::Exit(_) => None,
+            }));
+
+            // 需要按会话顺序处理的消息在这里排队，这里是事件唯一按收到顺序经过的地方
+            let ticket = match &event {
+                InternalInternalEvent::DriverEvent(account, msg) => cache
+                    .enter(|| table.conversation_key(account, msg))
+                    .map(|key| conversations.ticket(key)),
+                InternalInternalEvent::Exit(_) => None,
+            };
 
             // Drop为关闭事件，所以要等待，其他的不等待
             if let InternalInternalEvent::Exit(exit_event) = &event {
                 drop_task = Some(tokio::spawn(Self::handler_event(
                     bot,
+                    table,
+                    cache,
                     event.clone(),
-                    self_api_tx,
+                    None,
                 )));
                 break *exit_event;
             } else {
-                tokio::spawn(Self::handler_event(bot, event, self_api_tx));
+                tokio::spawn(Self::handler_event(bot, table, cache, event, ticket));
             }
         };
         if let Some(drop_task) = drop_task {
@@ -110,6 +167,14 @@ impl Bot {
         exit_event
     }
 
+    /// 默认账号的 api 通道，Bot 运行后才会存在
+    pub(crate) fn default_api_tx(&self) -> mpsc::Sender<ApiAndOptOneshot> {
+        self.accounts
+            .first()
+            .and_then(|v| v.api_tx.clone())
+            .expect("Bot is not running")
+    }
+
     // 运行所有main()
     fn run_mains(bot: Arc<RwLock<Self>>, api_tx: mpsc::Sender<ApiAndOptOneshot>) {
         let bot_ = bot.read();
@@ -1,22 +1,31 @@
 use crate::ExitEvent;
 use crate::bot::ApiReturn;
+use crate::bot::account::Account;
+use crate::bot::dry_run::DryRun;
 use crate::bot::handler::InternalInternalEvent;
+use crate::bot::middleware::{
+    ApiContext, ApiMiddleware, Flow, VETOED_RETCODE, run_api_middlewares,
+};
 use crate::driver::{Driver, DriverEvent};
 use crate::event::InternalEvent;
 use crate::types::ApiAndOptOneshot;
 use futures::StreamExt as _;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio::sync::mpsc::{self};
 
+/// `running` 为仍在运行的账号数，最后一个账号的驱动退出时才通知 Bot 退出
 pub(crate) async fn event_connect(
     self_event_tx: mpsc::Sender<InternalInternalEvent>,
     drive: Arc<dyn Driver>,
+    account: Account,
+    running: Arc<AtomicUsize>,
 ) {
     let mut drive_stream = match drive.event_channel().await {
         Ok(drive_stream) => drive_stream,
         Err(err) => {
             eprintln!("Failed to get drive event channel: {}", err);
-            self_event_tx.send(InternalInternalEvent::Exit(ExitEvent::FromDrive)).await.expect("Kovi kernel encountered an unrecoverable error during message forwarding (channel closed)");
+            send_drive_exit(&self_event_tx, &account, &running).await;
             return;
         }
     };
@@ -27,21 +36,22 @@ pub(crate) async fn event_connect(
             Ok(event) => event,
             Err(err) => {
                 eprintln!("Failed to get drive event: {}", err);
-                send_drive_exit(&self_event_tx).await;
+                send_drive_exit(&self_event_tx, &account, &running).await;
                 return;
             }
         };
 
         match event {
             DriverEvent::Exit => {
-                send_drive_exit(&self_event_tx).await;
+                send_drive_exit(&self_event_tx, &account, &running).await;
                 return;
             }
             DriverEvent::Normal(value) => {
                 self_event_tx
-                    .send(InternalInternalEvent::DriverEvent(Box::new(
-                        InternalEvent::DriverEvent(value),
-                    )))
+                    .send(InternalInternalEvent::DriverEvent(
+                        account.clone(),
+                        Box::new(InternalEvent::DriverEvent(Arc::new(value))),
+                    ))
                     .await
                     .expect("Kovi kernel encountered an unrecoverable error during message forwarding (channel closed)");
             }
@@ -49,10 +59,22 @@ pub(crate) async fn event_connect(
     }
 
     // 服务端正常关闭时流可能返回 None，这个时候通知退出
-    send_drive_exit(&self_event_tx).await;
+    send_drive_exit(&self_event_tx, &account, &running).await;
 }
 
-async fn send_drive_exit(tx: &mpsc::Sender<InternalInternalEvent>) {
+async fn send_drive_exit(
+    tx: &mpsc::Sender<InternalInternalEvent>,
+    account: &Account,
+    running: &AtomicUsize,
+) {
+    let remaining = running.fetch_sub(1, Ordering::SeqCst) - 1;
+    if remaining > 0 {
+        log::warn!(
+            "The driver of account '{account}' exited, {remaining} account(s) still running"
+        );
+        return;
+    }
+
     tx.send(InternalInternalEvent::Exit(ExitEvent::FromDrive))
         .await
         .expect("Kovi kernel encountered an unrecoverable error during message forwarding (channel closed)");
@@ -62,6 +84,9 @@ pub(crate) async fn send_connect(
     mut self_api_rx: mpsc::Receiver<ApiAndOptOneshot>,
     self_event_tx: mpsc::Sender<InternalInternalEvent>,
     drive: Arc<dyn Driver>,
+    account: Account,
+    middlewares: Arc<Vec<Arc<dyn ApiMiddleware>>>,
+    dry_run: Arc<DryRun>,
 ) {
     //处理事件，每个事件都会来到这里
     while let Some(api_and_oneshot) = self_api_rx.recv().await {
@@ -69,6 +94,9 @@ pub(crate) async fn send_connect(
             api_and_oneshot,
             self_event_tx.clone(),
             drive.clone(),
+            account.clone(),
+            middlewares.clone(),
+            dry_run.clone(),
         ));
     }
 }
@@ -77,9 +105,52 @@ async fn send_api_inner(
     api_and_oneshot: ApiAndOptOneshot,
     self_event_tx: mpsc::Sender<InternalInternalEvent>,
     drive: Arc<dyn Driver>,
+    account: Account,
+    middlewares: Arc<Vec<Arc<dyn ApiMiddleware>>>,
+    dry_run: Arc<DryRun>,
 ) {
     let (send_api, oneshot) = api_and_oneshot;
 
+    // API 中间件可以修改或拦截 API，被拦截的 API 不会交给驱动
+    let mut ctx = ApiContext {
+        account,
+        api: send_api,
+    };
+    if run_api_middlewares(&middlewares, &mut ctx) == Flow::Stop {
+        log::debug!("API [{}] was vetoed by middleware", ctx.api.action);
+        if let Some(oneshot) = oneshot {
+            oneshot
+                .send(Err(ApiReturn {
+                    status: "failed".to_string(),
+                    retcode: VETOED_RETCODE,
+                    message: Some("Vetoed by middleware".to_string()),
+                    data: serde_json::Value::Null,
+                }))
+                .ok();
+        }
+        return;
+    }
+    let ApiContext {
+        account,
+        api: send_api,
+    } = ctx;
+
+    // 试运行时，会改变状态的 API 由 Kovi 直接返回成功
+    if let Some(ret) = dry_run.intercept(&send_api) {
+        let result = Ok(ret);
+        if let Some(oneshot) = oneshot {
+            oneshot.send(result.clone()).ok();
+        }
+        self_event_tx
+            .send(InternalInternalEvent::DriverEvent(
+                account,
+                Box::new(InternalEvent::DriverApiEvent((send_api, result))),
+            ))
+            .await
+            .expect("Kovi kernel encountered an unrecoverable error during message forwarding (channel closed)");
+        return;
+    }
+
     let result = drive.api_handler(send_api.clone()).await;
 
     let result = match result {
@@ -107,9 +178,10 @@ async fn send_api_inner(
 
             // 继续发送 DriverApiEvent，让监听 MsgSendFromKoviEvent 的插件能感知到错误
             self_event_tx
-                .send(InternalInternalEvent::DriverEvent(Box::new(
-                    InternalEvent::DriverApiEvent((send_api, err_return)),
-                )))
+                .send(InternalInternalEvent::DriverEvent(
+                    account,
+                    Box::new(InternalEvent::DriverApiEvent((send_api, err_return))),
+                ))
                 .await
                 .expect(
                     "Kovi kernel encountered an unrecoverable error during message forwarding (channel closed)",
@@ -124,6 +196,7 @@ async fn send_api_inner(
 
     self_event_tx
         .send(InternalInternalEvent::DriverEvent(
+           account,
            Box::new(InternalEvent::DriverApiEvent((send_api, result))),
         ))
         .await.expect("Kovi kernel encountered an unrecoverable error during message forwarding (channel closed)");
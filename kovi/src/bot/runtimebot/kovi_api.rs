@@ -1,14 +1,14 @@
-use super::RuntimeBot;
+use super::{CanSendApi, RuntimeBot};
 use crate::bot::BotInformation;
+use crate::bot::account::Account;
+use crate::config::paths::KoviPaths;
 use crate::error::BotError;
 use crate::event::id::ID;
 use crate::plugin::PluginInfo;
-use crate::types::ApiAndOptOneshot;
 use crate::{Bot, PluginBuilder};
 use parking_lot::RwLock;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::mpsc;
 
 #[cfg(feature = "plugin-access-control")]
 pub use crate::plugin::SetAccessControlList;
@@ -80,6 +80,7 @@ impl RuntimeBot {
         };
 
         plugin.set_access_control(enable);
+        bot.refresh_dispatch_table();
 
         Ok(())
     }
@@ -112,6 +113,7 @@ impl RuntimeBot {
         };
 
         plugin.set_access_control_mode(access_control_mode);
+        bot.refresh_dispatch_table();
 
         Ok(())
     }
@@ -147,6 +149,7 @@ impl RuntimeBot {
         };
 
         plugin.set_access_control_list(is_group, change);
+        bot.refresh_dispatch_table();
 
         Ok(())
     }
@@ -248,18 +251,92 @@ impl RuntimeBot {
     }
 }
 
-/// 工具
+/// 多账号
 impl RuntimeBot {
-    /// 获取插件自己的路径
+    /// 获取发送 API 到指定账号的 `RuntimeBot`
     ///
-    /// # panic
+    /// 插件拿到的 `RuntimeBot` 默认发送到默认账号。
+    ///
+    /// # Error
+    ///
+    /// 如果寻找不到账号，会返回 `BotError::AccountNotFound` 错误。
+    ///
+    /// 如果此 `RuntimeBot` 实例内部的 `Bot` 中已经不存在，将会返回 `BotError::RefExpired` 错误。
+    /// 这通常出现在Bot已经关闭，可有个不受Kovi管理的线程仍然拥有此 RuntimeBot。
+    pub fn account<T: AsRef<str>>(&self, name: T) -> Result<RuntimeBot, BotError> {
+        let bot = match self.bot.upgrade() {
+            Some(b) => b,
+            None => return Err(BotError::RefExpired),
+        };
+
+        let name = name.as_ref();
+        let api_tx = bot
+            .read()
+            .accounts
+            .iter()
+            .find(|v| v.account.name() == name)
+            .and_then(|v| v.api_tx.clone())
+            .ok_or_else(|| BotError::AccountNotFound(name.to_string()))?;
+
+        Ok(RuntimeBot {
+            api_tx,
+            ..self.clone()
+        })
+    }
+
+    /// 获取发送 API 到收到此事件的账号的 `RuntimeBot`
+    pub fn for_event<E: CanSendApi>(&self, event: &E) -> RuntimeBot {
+        RuntimeBot {
+            api_tx: event.__get_api_tx().clone(),
+            ..self.clone()
+        }
+    }
+
+    /// 获取此 `RuntimeBot` 发送 API 的账号
+    ///
+    /// # Error
+    ///
+    /// 如果此 `RuntimeBot` 实例内部的 `Bot` 中已经不存在，将会返回 `BotError::RefExpired` 错误。
+    /// 这通常出现在Bot已经关闭，可有个不受Kovi管理的线程仍然拥有此 RuntimeBot。
+    pub fn get_account(&self) -> Result<Account, BotError> {
+        let bot = match self.bot.upgrade() {
+            Some(b) => b,
+            None => return Err(BotError::RefExpired),
+        };
+
+        bot.read()
+            .accounts
+            .iter()
+            .find(|v| {
+                v.api_tx
+                    .as_ref()
+                    .is_some_and(|tx| tx.same_channel(&self.api_tx))
+            })
+            .map(|v| v.account.clone())
+            .ok_or_else(|| BotError::AccountNotFound("<unknown>".to_string()))
+    }
+
+    /// 获取Bot的所有账号
+    ///
+    /// # Error
     ///
-    /// 可能会 panic 的情况：
-    ///  - 当前运行目录不存在，这种情况很少见。
-    ///  - 权限不足，无法访问当前目录，这样肯定不能运行插件。
+    /// 如果此 `RuntimeBot` 实例内部的 `Bot` 中已经不存在，将会返回 `BotError::RefExpired` 错误。
+    /// 这通常出现在Bot已经关闭，可有个不受Kovi管理的线程仍然拥有此 RuntimeBot。
+    pub fn get_accounts(&self) -> Result<Vec<Account>, BotError> {
+        let bot = match self.bot.upgrade() {
+            Some(b) => b,
+            None => return Err(BotError::RefExpired),
+        };
+
+        Ok(bot.read().get_accounts())
+    }
+}
+
+/// 工具
+impl RuntimeBot {
+    /// 获取插件自己的路径，位于 [`KoviPaths::data_dir()`] 中
     pub fn get_data_path(&self) -> PathBuf {
-        let data_root_path = crate::utils::get_data_root_path();
-        data_root_path.join(self.plugin_name.clone())
+        KoviPaths::current().plugin_data_dir(&self.plugin_name)
     }
 }
 
@@ -365,7 +442,7 @@ impl RuntimeBot {
             None => return Err(BotError::RefExpired),
         };
 
-        enable_plugin(bot, plugin_name, self.api_tx.clone())
+        enable_plugin(bot, plugin_name)
     }
 
     /// 插件是否开启
@@ -409,19 +486,19 @@ pub(crate) fn disable_plugin<T: AsRef<str>>(
             None => return Err(BotError::PluginNotFound(plugin_name.to_string())),
         };
         join = bot_plugin.shutdown();
+        bot.refresh_dispatch_table();
     }
 
     Ok(join)
 }
 
-fn enable_plugin<T: AsRef<str>>(
-    bot: Arc<RwLock<Bot>>,
-    plugin_name: T,
-    api_tx: mpsc::Sender<ApiAndOptOneshot>,
-) -> Result<(), BotError> {
+fn enable_plugin<T: AsRef<str>>(bot: Arc<RwLock<Bot>>, plugin_name: T) -> Result<(), BotError> {
     let bot_read = bot.read();
     let plugin_name = plugin_name.as_ref();
 
+    // 插件的 RuntimeBot 始终使用默认账号，而不是调用者所指向的账号
+    let api_tx = bot_read.default_api_tx();
+
     // let (host, port) = {
     //     let info = bot_read.information.read();
     //     (info.server.host.clone(), info.server.port)
@@ -434,6 +511,9 @@ fn enable_plugin<T: AsRef<str>>(
     bot_plugin.enabled.send_modify(|v| {
         *v = true;
     });
+    bot_read.refresh_dispatch_table();
+    // 重新开启的插件重新统计失败次数
+    bot_read.failures.reset(plugin_name);
 
     let plugin_ = bot_plugin.clone();
 
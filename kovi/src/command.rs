@@ -0,0 +1,377 @@
+use crate::RuntimeBot;
+use crate::error::CommandError;
+use crate::event::id::ref_id::RefID;
+use crate::event::{MessageEventTrait, RepliableEvent};
+use crate::types::PinFut;
+use args::{Arg, ArgKind, Args, Token, first_word, parse_args, tokenize};
+use log::debug;
+use std::sync::Arc;
+
+pub mod args;
+
+#[cfg(test)]
+mod test;
+
+/// 命令的权限等级，由 Bot 的管理员决定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Permission {
+    /// 所有人
+    #[default]
+    Everyone,
+    /// 主管理员与副管理员
+    Admin,
+    /// 仅主管理员
+    MainAdmin,
+}
+
+/// 一条命令的声明：名称、别名、参数与权限
+///
+/// # Examples
+/// ```
+/// use kovi::command::{Command, Permission};
+/// use kovi::command::args::Arg;
+///
+/// let command = Command::new("ban")
+///     .alias("mute")
+///     .about("禁言群成员")
+///     .arg(Arg::id("user"))
+///     .arg(Arg::int("minutes").optional())
+///     .permission(Permission::Admin);
+///
+/// assert_eq!(command.usage("/"), "/ban <user> [minutes]");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Command {
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub about: String,
+    pub args: Vec<Arg>,
+    pub permission: Permission,
+}
+
+impl Command {
+    pub fn new(name: &str) -> Self {
+        Command {
+            name: name.to_string(),
+            aliases: Vec::new(),
+            about: String::new(),
+            args: Vec::new(),
+            permission: Permission::Everyone,
+        }
+    }
+
+    pub fn alias(mut self, alias: &str) -> Self {
+        self.aliases.push(alias.to_string());
+        self
+    }
+
+    /// 在帮助中显示的说明
+    pub fn about(mut self, about: &str) -> Self {
+        self.about = about.to_string();
+        self
+    }
+
+    /// 添加一个参数
+    ///
+    /// # Panics
+    ///
+    /// 在 `Rest` 参数之后，或在可选参数之后添加必选参数时 panic。
+    pub fn arg(mut self, arg: Arg) -> Self {
+        if let Some(last) = self.args.last() {
+            assert!(
+                last.kind != ArgKind::Rest,
+                "command `{}`: argument <{}> follows rest argument <{}>",
+                self.name,
+                arg.name,
+                last.name
+            );
+            assert!(
+                !last.optional || arg.optional,
+                "command `{}`: required argument <{}> follows optional argument <{}>",
+                self.name,
+                arg.name,
+                last.name
+            );
+        }
+        self.args.push(arg);
+        self
+    }
+
+    pub fn permission(mut self, permission: Permission) -> Self {
+        self.permission = permission;
+        self
+    }
+
+    /// 用法，例如 `/ban <user> [minutes]`
+    pub fn usage(&self, prefix: &str) -> String {
+        let mut usage = format!("{prefix}{}", self.name);
+        for arg in &self.args {
+            usage.push(' ');
+            usage.push_str(&arg.usage());
+        }
+        usage
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        self.name == name || self.aliases.iter().any(|alias| alias == name)
+    }
+}
+
+type CommandHandler<E> = Arc<dyn Fn(Arc<E>, Args) -> PinFut + Send + Sync>;
+
+/// 命令与它的处理函数，通常由 `#[kovi::command]` 生成
+///
+/// 可以用 [`CommandSet::register`] 加入命令组，也可以用 `PluginBuilder::command()` 单独注册。
+pub struct CommandDef<E> {
+    pub command: Command,
+    handler: CommandHandler<E>,
+}
+
+impl<E> CommandDef<E> {
+    pub fn new<F, Fut>(command: Command, handler: F) -> Self
+    where
+        F: Fn(Arc<E>, Args) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        CommandDef {
+            command,
+            handler: Arc::new(move |event, args| Box::pin(handler(event, args))),
+        }
+    }
+}
+
+/// 一组命令，共享前缀与自动生成的帮助，使用 `PluginBuilder::commands()` 注册
+///
+/// 收到的消息以前缀加命令名开头时，解析参数并调用对应的处理函数。参数有误时会回复错误与用法，
+/// 权限不足时会回复提示。`<prefix>help` 列出发送者可用的命令，`<prefix>help <命令>` 显示单个命令的用法。
+///
+/// # Examples
+/// ```ignore
+/// use kovi::command::{Command, CommandSet};
+/// use kovi::command::args::Arg;
+///
+/// PluginBuilder::commands(
+///     CommandSet::<MsgEvent>::new()
+///         .prefixes(["/", "!"])
+///         .command(
+///             Command::new("echo").about("复读").arg(Arg::rest("text")),
+///             |event, args| async move {
+///                 event.reply(args.str("text").unwrap_or_default());
+///             },
+///         ),
+/// );
+/// ```
+pub struct CommandSet<E> {
+    prefixes: Vec<String>,
+    help: bool,
+    commands: Vec<(Command, CommandHandler<E>)>,
+}
+
+impl<E> Default for CommandSet<E>
+where
+    E: MessageEventTrait + RepliableEvent,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> CommandSet<E>
+where
+    E: MessageEventTrait + RepliableEvent,
+{
+    /// 默认前缀为 `/`，并启用 `help` 命令
+    pub fn new() -> Self {
+        CommandSet {
+            prefixes: vec!["/".to_string()],
+            help: true,
+            commands: Vec::new(),
+        }
+    }
+
+    /// 设置命令前缀，可以有多个，可以为空字符串，帮助中使用第一个前缀
+    pub fn prefixes<I, S>(mut self, prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.prefixes = prefixes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// 不生成 `help` 命令
+    pub fn disable_help(mut self) -> Self {
+        self.help = false;
+        self
+    }
+
+    /// 添加一条命令
+    pub fn command<F, Fut>(self, command: Command, handler: F) -> Self
+    where
+        F: Fn(Arc<E>, Args) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.register(CommandDef::new(command, handler))
+    }
+
+    /// 添加一条已定义的命令，例如 `#[kovi::command]` 生成的命令
+    pub fn register(mut self, def: CommandDef<E>) -> Self {
+        self.commands.push((def.command, def.handler));
+        self
+    }
+
+    pub fn get_commands(&self) -> impl Iterator<Item = &Command> {
+        self.commands.iter().map(|(command, _)| command)
+    }
+
+    fn display_prefix(&self) -> &str {
+        self.prefixes
+            .first()
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+
+    /// 去掉 `word` 的前缀，多个前缀都匹配时去掉最长的那个
+    fn strip_prefix<'a>(&self, word: &'a str) -> Option<&'a str> {
+        self.prefixes
+            .iter()
+            .filter_map(|prefix| word.strip_prefix(prefix.as_str()))
+            .min_by_key(|name| name.len())
+    }
+
+    /// 生成帮助，只包含 `permission` 等级可用的命令
+    pub fn help_text(&self, permission: Permission) -> String {
+        let prefix = self.display_prefix();
+        let mut lines = vec!["Commands:".to_string()];
+        for command in self.get_commands() {
+            if command.permission > permission {
+                continue;
+            }
+            let mut line = command.usage(prefix);
+            if !command.about.is_empty() {
+                line.push_str(" - ");
+                line.push_str(&command.about);
+            }
+            lines.push(line);
+        }
+        if self.help {
+            lines.push(format!("{prefix}help [command] - Show help"));
+        }
+        lines.join("\n")
+    }
+
+    /// 单个命令的帮助
+    pub fn command_help(&self, command: &Command) -> String {
+        let prefix = self.display_prefix();
+        let mut text = format!("Usage: {}", command.usage(prefix));
+        if !command.about.is_empty() {
+            text.push('\n');
+            text.push_str(&command.about);
+        }
+        if !command.aliases.is_empty() {
+            let aliases: Vec<String> = command
+                .aliases
+                .iter()
+                .map(|alias| format!("{prefix}{alias}"))
+                .collect();
+            text.push_str(&format!("\nAliases: {}", aliases.join(", ")));
+        }
+        text
+    }
+
+    /// 从消息的第一个词中取出命令名
+    fn command_name<'a>(&self, tokens: &'a [Token]) -> Option<&'a str> {
+        let Some(Token::Text {
+            value,
+            quoted: false,
+            ..
+        }) = tokens.first()
+        else {
+            return None;
+        };
+        self.strip_prefix(value).filter(|name| !name.is_empty())
+    }
+
+    /// 处理一条消息，消息不是本组中的命令时返回 `false`
+    pub async fn dispatch(&self, event: Arc<E>, bot: &RuntimeBot) -> bool {
+        let tokens = match tokenize(event.get_message()) {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                // 以本组中的命令开头时，与其他参数错误一样回复错误与用法
+                let command = first_word(event.get_message())
+                    .and_then(|word| self.strip_prefix(word))
+                    .and_then(|name| self.get_commands().find(|command| command.matches(name)));
+                let Some(command) = command else {
+                    debug!("Message is not a command: {e}");
+                    return false;
+                };
+                if command.permission > sender_permission(bot, event.get_sender_id()) {
+                    event.reply("You do not have permission to use this command.");
+                } else {
+                    event.reply(usage_error(&e, &command.usage(self.display_prefix())));
+                }
+                return true;
+            }
+        };
+        let Some(name) = self.command_name(&tokens) else {
+            return false;
+        };
+        let permission = sender_permission(bot, event.get_sender_id());
+
+        let Some((command, handler)) = self
+            .commands
+            .iter()
+            .find(|(command, _)| command.matches(name))
+        else {
+            if self.help && name == "help" {
+                self.reply_help(&*event, &tokens[1..], permission);
+                return true;
+            }
+            return false;
+        };
+
+        if command.permission > permission {
+            event.reply("You do not have permission to use this command.");
+            return true;
+        }
+
+        match parse_args(&command.args, &tokens[1..]) {
+            Ok(args) => handler(event, args).await,
+            Err(e) => event.reply(usage_error(&e, &command.usage(self.display_prefix()))),
+        }
+        true
+    }
+
+    fn reply_help(&self, event: &E, tokens: &[Token], permission: Permission) {
+        let command = match tokens.first() {
+            Some(Token::Text { value, .. }) => {
+                let name = self.strip_prefix(value).unwrap_or(value);
+                self.get_commands()
+                    .find(|command| command.matches(name) && command.permission <= permission)
+            }
+            _ => None,
+        };
+        match command {
+            Some(command) => event.reply(self.command_help(command)),
+            None => event.reply(self.help_text(permission)),
+        }
+    }
+}
+
+fn usage_error(error: &CommandError, usage: &str) -> String {
+    format!("{error}\nUsage: {usage}")
+}
+
+/// 按 Bot 管理员判断发送者的权限等级
+fn sender_permission(bot: &RuntimeBot, sender: RefID<'_>) -> Permission {
+    if bot.get_main_admin().is_ok_and(|admin| admin == sender) {
+        return Permission::MainAdmin;
+    }
+    if bot
+        .get_deputy_admins()
+        .is_ok_and(|admins| admins.iter().any(|admin| *admin == sender))
+    {
+        return Permission::Admin;
+    }
+    Permission::Everyone
+}
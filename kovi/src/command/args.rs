@@ -0,0 +1,425 @@
+use crate::error::CommandError;
+use crate::event::id::ID;
+use crate::message::Message;
+use ahash::{HashMap, HashMapExt as _};
+use std::sync::Arc;
+
+/// 参数类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgKind {
+    /// 整数
+    Int,
+    /// 一个词，或用双引号括起来的字符串
+    Str,
+    /// 用户 ID，可以是 at 消息段，也可以是数字
+    Id,
+    /// 剩余的全部文本，保留原本的空白，at 等非文本消息段不计入
+    Rest,
+}
+
+/// 命令的一个参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Arg {
+    pub name: String,
+    pub kind: ArgKind,
+    pub optional: bool,
+}
+
+impl Arg {
+    pub fn new(name: &str, kind: ArgKind) -> Self {
+        Arg {
+            name: name.to_string(),
+            kind,
+            optional: false,
+        }
+    }
+
+    pub fn int(name: &str) -> Self {
+        Self::new(name, ArgKind::Int)
+    }
+
+    pub fn string(name: &str) -> Self {
+        Self::new(name, ArgKind::Str)
+    }
+
+    pub fn id(name: &str) -> Self {
+        Self::new(name, ArgKind::Id)
+    }
+
+    pub fn rest(name: &str) -> Self {
+        Self::new(name, ArgKind::Rest)
+    }
+
+    /// 按 [`ArgType`] 决定类型与是否可选
+    pub fn of<T: ArgType>(name: &str) -> Self {
+        let arg = Self::new(name, T::KIND);
+        if T::OPTIONAL { arg.optional() } else { arg }
+    }
+
+    /// 设为可选参数，可选参数只能位于必选参数之后
+    pub fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+
+    /// 在帮助中显示的形式，例如 `<user>`、`[count]`、`<text...>`
+    pub fn usage(&self) -> String {
+        let rest = if self.kind == ArgKind::Rest { "..." } else { "" };
+        if self.optional {
+            format!("[{}{rest}]", self.name)
+        } else {
+            format!("<{}{rest}>", self.name)
+        }
+    }
+}
+
+/// 解析后的参数值
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgValue {
+    Int(i64),
+    Str(String),
+    Id(ID),
+}
+
+/// 解析后的命令参数，以参数名取值，未提供的可选参数不存在
+#[derive(Debug, Clone, Default)]
+pub struct Args {
+    values: HashMap<String, ArgValue>,
+}
+
+impl Args {
+    pub fn get(&self, name: &str) -> Option<&ArgValue> {
+        self.values.get(name)
+    }
+
+    pub fn int(&self, name: &str) -> Option<i64> {
+        match self.values.get(name)? {
+            ArgValue::Int(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// 获取 `Str` 或 `Rest` 参数
+    pub fn str(&self, name: &str) -> Option<&str> {
+        match self.values.get(name)? {
+            ArgValue::Str(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn id(&self, name: &str) -> Option<&ID> {
+        match self.values.get(name)? {
+            ArgValue::Id(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+}
+
+/// 剩余的全部文本，作为 `#[kovi::command]` 函数的参数类型时对应 [`ArgKind::Rest`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Rest(pub String);
+
+impl std::ops::Deref for Rest {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for Rest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<Rest> for String {
+    fn from(rest: Rest) -> Self {
+        rest.0
+    }
+}
+
+/// 可以作为 `#[kovi::command]` 函数参数的类型
+///
+/// `i64` 对应 [`ArgKind::Int`]，`String` 对应 [`ArgKind::Str`]，`ID` 对应 [`ArgKind::Id`]，
+/// [`Rest`] 对应 [`ArgKind::Rest`]，`Option<T>` 为可选参数。
+pub trait ArgType: Sized {
+    const KIND: ArgKind;
+    const OPTIONAL: bool = false;
+
+    /// 从解析后的参数中取值，必选参数不存在时返回 `None`
+    fn from_args(args: &Args, name: &str) -> Option<Self>;
+}
+
+impl ArgType for i64 {
+    const KIND: ArgKind = ArgKind::Int;
+
+    fn from_args(args: &Args, name: &str) -> Option<Self> {
+        args.int(name)
+    }
+}
+
+impl ArgType for String {
+    const KIND: ArgKind = ArgKind::Str;
+
+    fn from_args(args: &Args, name: &str) -> Option<Self> {
+        args.str(name).map(ToString::to_string)
+    }
+}
+
+impl ArgType for ID {
+    const KIND: ArgKind = ArgKind::Id;
+
+    fn from_args(args: &Args, name: &str) -> Option<Self> {
+        args.id(name).cloned()
+    }
+}
+
+impl ArgType for Rest {
+    const KIND: ArgKind = ArgKind::Rest;
+
+    fn from_args(args: &Args, name: &str) -> Option<Self> {
+        args.str(name).map(|text| Rest(text.to_string()))
+    }
+}
+
+impl<T: ArgType> ArgType for Option<T> {
+    const KIND: ArgKind = T::KIND;
+    const OPTIONAL: bool = true;
+
+    fn from_args(args: &Args, name: &str) -> Option<Self> {
+        Some(T::from_args(args, name))
+    }
+}
+
+/// 从消息中切分出的一个词
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Token {
+    Text {
+        value: String,
+        /// 是否由引号括起来
+        quoted: bool,
+        /// 消息中所有文本连在一起，所有词共用
+        text: Arc<str>,
+        /// 此词在 `text` 中的起始位置
+        start: usize,
+    },
+    Id(ID),
+}
+
+impl Token {
+    /// 从此词开始的剩余文本，不包括 at
+    pub(crate) fn rest(&self) -> Option<&str> {
+        match self {
+            Token::Text { text, start, .. } => Some(text[*start..].trim()),
+            Token::Id(_) => None,
+        }
+    }
+}
+
+/// 消息中的文本与 at
+enum Piece {
+    Text(String),
+    Id(ID),
+}
+
+/// 把消息切分为词。文本按空白切分，双引号括起来的部分为一个词，支持 `\"` 与 `\\` 转义；
+/// at 消息段（OneBot 的 `at`，Milky 的 `mention`）为一个 ID，其他消息段被忽略。
+pub(crate) fn tokenize(message: &Message) -> Result<Vec<Token>, CommandError> {
+    let pieces: Vec<Piece> = message
+        .iter()
+        .filter_map(|segment| match segment.kind.as_str() {
+            "text" => segment
+                .data
+                .get("text")
+                .and_then(|v| v.as_str())
+                .map(|text| Piece::Text(text.to_string())),
+            "at" => segment.data.get("qq").and_then(value_to_id).map(Piece::Id),
+            "mention" => segment
+                .data
+                .get("user_id")
+                .and_then(value_to_id)
+                .map(Piece::Id),
+            _ => None,
+        })
+        .collect();
+
+    let mut full = String::new();
+    for piece in &pieces {
+        if let Piece::Text(text) = piece {
+            full.push_str(text);
+        }
+    }
+    let full: Arc<str> = full.into();
+
+    let mut tokens = Vec::new();
+    let mut offset = 0;
+    for piece in &pieces {
+        let text = match piece {
+            Piece::Id(id) => {
+                tokens.push(Token::Id(id.clone()));
+                continue;
+            }
+            Piece::Text(text) => text,
+        };
+        let base = offset;
+        offset += text.len();
+
+        let mut chars = text.char_indices().peekable();
+        while let Some(&(start, c)) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+                continue;
+            }
+
+            let mut value = String::new();
+            let quoted = c == '"';
+            if quoted {
+                chars.next();
+                let mut closed = false;
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        '\\' => match chars.next() {
+                            Some((_, escaped @ ('"' | '\\'))) => value.push(escaped),
+                            Some((_, other)) => {
+                                value.push('\\');
+                                value.push(other);
+                            }
+                            None => value.push('\\'),
+                        },
+                        '"' => {
+                            closed = true;
+                            break;
+                        }
+                        _ => value.push(c),
+                    }
+                }
+                if !closed {
+                    return Err(CommandError::UnclosedQuote);
+                }
+            } else {
+                while let Some(&(_, c)) = chars.peek() {
+                    if c.is_whitespace() {
+                        break;
+                    }
+                    value.push(c);
+                    chars.next();
+                }
+            }
+
+            tokens.push(Token::Text {
+                value,
+                quoted,
+                text: full.clone(),
+                start: base + start,
+            });
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// 消息中第一个未加引号的词，在第一个词之前有 at 时为 `None`
+///
+/// 用于在无法切分消息时判断消息是否为命令。
+pub(crate) fn first_word(message: &Message) -> Option<&str> {
+    for segment in message.iter() {
+        match segment.kind.as_str() {
+            "text" => {
+                let text = segment.data.get("text").and_then(|v| v.as_str())?;
+                if let Some(word) = text.split_whitespace().next() {
+                    return (!word.starts_with('"')).then_some(word);
+                }
+            }
+            "at" | "mention" => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn value_to_id(value: &serde_json::Value) -> Option<ID> {
+    if let Some(id) = value.as_i64() {
+        return Some(ID::new(id));
+    }
+    let id = value.as_str()?;
+    Some(match id.parse::<i64>() {
+        Ok(id) => ID::new(id),
+        Err(_) => ID::new(id),
+    })
+}
+
+/// 按参数列表解析词
+pub(crate) fn parse_args(specs: &[Arg], tokens: &[Token]) -> Result<Args, CommandError> {
+    let mut values = HashMap::new();
+    let mut tokens = tokens.iter();
+
+    for spec in specs {
+        if spec.kind == ArgKind::Rest {
+            let rest = tokens
+                .find_map(Token::rest)
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            // rest 已消耗剩余所有文本
+            tokens = [].iter();
+            if rest.is_empty() {
+                if spec.optional {
+                    continue;
+                }
+                return Err(CommandError::MissingArgument(spec.name.clone()));
+            }
+            values.insert(spec.name.clone(), ArgValue::Str(rest));
+            continue;
+        }
+
+        let Some(token) = tokens.next() else {
+            if spec.optional {
+                continue;
+            }
+            return Err(CommandError::MissingArgument(spec.name.clone()));
+        };
+
+        let value = match (spec.kind, token) {
+            (ArgKind::Id, Token::Id(id)) => ArgValue::Id(id.clone()),
+            (ArgKind::Id, Token::Text { value, .. }) => {
+                let id = value
+                    .parse::<i64>()
+                    .map_err(|_| CommandError::InvalidId(spec.name.clone()))?;
+                ArgValue::Id(ID::new(id))
+            }
+            (ArgKind::Int, Token::Text { value, .. }) => {
+                let v = value
+                    .parse::<i64>()
+                    .map_err(|_| CommandError::InvalidInteger {
+                        name: spec.name.clone(),
+                        value: value.clone(),
+                    })?;
+                ArgValue::Int(v)
+            }
+            (ArgKind::Int, Token::Id(id)) => {
+                return Err(CommandError::InvalidInteger {
+                    name: spec.name.clone(),
+                    value: format!("@{id}"),
+                });
+            }
+            (ArgKind::Str, Token::Text { value, .. }) => ArgValue::Str(value.clone()),
+            (ArgKind::Str, Token::Id(id)) => ArgValue::Str(id.to_string()),
+            (ArgKind::Rest, _) => unreachable!(),
+        };
+        values.insert(spec.name.clone(), value);
+    }
+
+    if let Some(token) = tokens.next() {
+        let extra = match token {
+            Token::Text { .. } => token.rest().unwrap_or_default().to_string(),
+            Token::Id(id) => format!("@{id}"),
+        };
+        return Err(CommandError::TooManyArguments(extra));
+    }
+
+    Ok(Args { values })
+}
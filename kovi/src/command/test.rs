@@ -0,0 +1,127 @@
+use super::args::{Arg, ArgValue, Token, parse_args, tokenize};
+use super::{Command, Permission};
+use crate::error::CommandError;
+use crate::event::id::ID;
+use crate::message::{Message, Segment};
+use serde_json::json;
+
+fn text(text: &str) -> Segment {
+    Segment::new("text", json!({ "text": text }))
+}
+
+fn words(tokens: &[Token]) -> Vec<String> {
+    tokens
+        .iter()
+        .map(|token| match token {
+            Token::Text { value, .. } => value.clone(),
+            Token::Id(id) => format!("@{id}"),
+        })
+        .collect()
+}
+
+#[test]
+fn tokenize_quotes_and_mentions() {
+    let message = Message::from(vec![
+        text(r#"/say "hello \"kovi\"" to "#),
+        Segment::new("at", json!({ "qq": "123" })),
+        Segment::new("image", json!({ "file": "a.png" })),
+        Segment::new("mention", json!({ "user_id": 456 })),
+        text(" and  more"),
+    ]);
+    let tokens = tokenize(&message).expect("tokenize");
+    assert_eq!(
+        words(&tokens),
+        [
+            "/say",
+            r#"hello "kovi""#,
+            "to",
+            "@123",
+            "@456",
+            "and",
+            "more"
+        ]
+    );
+    assert_eq!(tokens[4], Token::Id(ID::new(456)));
+
+    assert_eq!(tokens[2].rest(), Some("to  and  more"));
+    assert_eq!(tokens[3].rest(), None);
+
+    assert_eq!(
+        tokenize(&Message::from(r#"/say "oops"#)),
+        Err(CommandError::UnclosedQuote)
+    );
+}
+
+#[test]
+fn parse_typed_args() {
+    let specs = [
+        Arg::id("user"),
+        Arg::int("minutes").optional(),
+        Arg::rest("reason").optional(),
+    ];
+
+    let message = Message::from(vec![
+        text("/ban "),
+        Segment::new("at", json!({ "qq": "123" })),
+        text(" 10 spamming  links"),
+    ]);
+    let tokens = tokenize(&message).expect("tokenize");
+    let args = parse_args(&specs, &tokens[1..]).expect("args");
+    assert_eq!(args.id("user"), Some(&ID::new(123)));
+    assert_eq!(args.int("minutes"), Some(10));
+    assert_eq!(args.str("reason"), Some("spamming  links"));
+
+    let tokens = tokenize(&Message::from("/ban 123")).expect("tokenize");
+    let args = parse_args(&specs, &tokens[1..]).expect("args");
+    assert_eq!(args.get("user"), Some(&ArgValue::Id(ID::new(123))));
+    assert!(!args.contains("minutes"));
+    assert!(!args.contains("reason"));
+}
+
+#[test]
+fn parse_errors() {
+    let specs = [Arg::id("user"), Arg::int("minutes")];
+    let parse = |text: &str| {
+        let tokens = tokenize(&Message::from(text)).expect("tokenize");
+        parse_args(&specs, &tokens[1..])
+    };
+
+    assert_eq!(
+        parse("/ban").err(),
+        Some(CommandError::MissingArgument("user".to_string()))
+    );
+    assert_eq!(
+        parse("/ban bob 1").err(),
+        Some(CommandError::InvalidId("user".to_string()))
+    );
+    assert_eq!(
+        parse("/ban 1 soon").err(),
+        Some(CommandError::InvalidInteger {
+            name: "minutes".to_string(),
+            value: "soon".to_string()
+        })
+    );
+    assert_eq!(
+        parse("/ban 1 2 extra words").err(),
+        Some(CommandError::TooManyArguments("extra words".to_string()))
+    );
+}
+
+#[test]
+fn usage_and_permission_order() {
+    let command = Command::new("echo")
+        .arg(Arg::string("first"))
+        .arg(Arg::rest("text").optional());
+    assert_eq!(command.usage("!"), "!echo <first> [text...]");
+
+    assert!(Permission::Everyone < Permission::Admin);
+    assert!(Permission::Admin < Permission::MainAdmin);
+}
+
+#[test]
+#[should_panic(expected = "required argument <b> follows optional argument <a>")]
+fn required_after_optional_panics() {
+    let _ = Command::new("bad")
+        .arg(Arg::int("a").optional())
+        .arg(Arg::int("b"));
+}
@@ -1 +1,4 @@
 pub mod kovi_conf;
+pub mod overrides;
+pub mod paths;
+pub mod plugin_conf;
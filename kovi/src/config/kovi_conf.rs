@@ -1,10 +1,11 @@
 use dialoguer::Input;
 use dialoguer::theme::ColorfulTheme;
 use serde::{Deserialize, Serialize};
-use std::io::Write as _;
 use std::path::Path;
 use std::{env, fs};
 
+use crate::config::overrides::{ConfigField, ConfigOverrides, FieldKind};
+use crate::config::paths::{KoviPaths, lock_conf_file, write_conf_file};
 use crate::error::BotBuildError;
 use crate::event::id::ID;
 
@@ -17,7 +18,9 @@ pub struct KoviConf {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
     pub main_admin: ID,
+    #[serde(default)]
     pub admins: Vec<ID>,
+    #[serde(default)]
     pub debug: bool,
 }
 
@@ -85,6 +88,7 @@ fn read_from_path_config_write_and_return(file_path: &Path) -> Result<KoviConf,
         false,
     );
 
+    let _lock = lock_conf_file();
     let mut doc = match fs::read_to_string(file_path) {
         Ok(content) => match content.parse::<toml_edit::DocumentMut>() {
             Ok(d) => d,
@@ -112,45 +116,34 @@ fn read_from_path_config_write_and_return(file_path: &Path) -> Result<KoviConf,
     ));
     doc["config"]["debug"] = toml_edit::value(config.config.debug);
 
-    let file = fs::File::create(file_path)?;
-    let mut writer = std::io::BufWriter::new(file);
-    writer.write_all(doc.to_string().as_bytes())?;
+    write_conf_file(file_path, &doc.to_string())?;
 
     Ok(config)
 }
 
-/// 读取本地Kovi.conf.toml文件
+/// 可以由环境变量与命令行参数设置的配置项，见 [`overrides`](crate::config::overrides)
+pub const CONFIG_FIELDS: &[ConfigField] = &[
+    ConfigField::new(
+        "main_admin",
+        "KOVI_MAIN_ADMIN",
+        "--main-admin",
+        FieldKind::Id,
+    ),
+    ConfigField::new("admins", "KOVI_ADMINS", "--admins", FieldKind::IdList),
+    ConfigField::new("debug", "KOVI_DEBUG", "--debug", FieldKind::Bool),
+];
+
+/// 读取本地Kovi.conf.toml文件，位置见 [`KoviPaths`]
+///
+/// 文件中的配置会被环境变量与命令行参数覆盖，见 [`overrides`](crate::config::overrides)。
 pub fn load_local_conf() -> Result<KoviConf, BotBuildError> {
-    let path = Path::new("kovi.conf.toml");
-    //检测文件kovi.conf.toml
-    let kovi_conf_file_exist = fs::metadata(path).is_ok();
+    let path = &KoviPaths::current().conf_file();
+    let overrides = ConfigOverrides::from_env_and_args();
 
-    #[derive(Debug, Clone, Deserialize, Serialize)]
-    struct TempKoviConf {
-        config: Option<Config>,
-    }
-
-    let conf_json: KoviConf = if kovi_conf_file_exist {
-        match fs::read_to_string(path) {
-            Ok(v) => match toml::from_str::<TempKoviConf>(&v) {
-                Ok(conf) => match conf.config {
-                    Some(config) => KoviConf { config },
-                    None => read_from_path_config_write_and_return(path)
-                        .map_err(|e| BotBuildError::FileCreateError(e.to_string()))?,
-                },
-                Err(err) => {
-                    eprintln!("Configuration file parsing error: {err}");
-                    read_from_path_config_write_and_return(path)
-                        .map_err(|e| BotBuildError::FileCreateError(e.to_string()))?
-                }
-            },
-            Err(err) => {
-                return Err(BotBuildError::FileReadError(err.to_string()));
-            }
-        }
-    } else {
-        read_from_path_config_write_and_return(path)
-            .map_err(|e| BotBuildError::FileCreateError(e.to_string()))?
+    let conf_json = match overrides.load_table::<Config>(path, "config", CONFIG_FIELDS)? {
+        Some(config) => KoviConf { config },
+        None => read_from_path_config_write_and_return(path)
+            .map_err(|e| BotBuildError::FileCreateError(e.to_string()))?,
     };
 
     unsafe {
@@ -0,0 +1,291 @@
+//! 以环境变量与命令行参数覆盖配置文件
+//!
+//! 配置按 `配置文件 < 环境变量 < 命令行参数` 的顺序叠加，后者覆盖前者。
+//! 所有必需的项都能由环境变量或参数提供时，不需要配置文件，也不会询问用户，适合在 Docker 或 systemd 中运行。
+//!
+//! | 配置文件 | 环境变量 | 命令行参数 |
+//! |---|---|---|
+//! | `config.main_admin` | `KOVI_MAIN_ADMIN` | `--main-admin` |
+//! | `config.admins` | `KOVI_ADMINS`（以 `,` 分隔） | `--admins` |
+//! | `config.debug` | `KOVI_DEBUG` | `--debug` |
+//!
+//! 驱动的 `[server]` 配置项见各驱动的 `SERVER_FIELDS`，如 `KOVI_SERVER_HOST` / `--server-host`。
+//! 这些覆盖不区分账号，多账号的 Bot 中只能有一个驱动从配置文件读取 `[server]`，见 [`ConfigOverrides::claim_table`]。
+//!
+//! 缺少配置时，默认在终端中询问用户。设置了 `KOVI_STRICT=true` 或 `--strict`，
+//! 或者标准输入不是终端时，直接返回错误，不会询问。
+//!
+//! ```sh
+//! KOVI_MAIN_ADMIN=10001 ./my-bot --server-host 127.0.0.1 --server-port 8081
+//! ```
+
+use crate::error::BotBuildError;
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::io::IsTerminal as _;
+use std::path::Path;
+
+#[cfg(test)]
+mod test;
+
+/// 配置项的类型，决定环境变量与参数的值如何转换
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    String,
+    Integer,
+    /// `true` / `false`，也接受 `1`、`yes`、`on` 等。命令行参数只有名字时为 `true`
+    Bool,
+    /// 能解析为整数时为整数，否则为字符串
+    Id,
+    /// 以 `,` 分隔的多个 [`FieldKind::Id`]
+    IdList,
+}
+
+/// 可以由环境变量与命令行参数设置的配置项
+#[derive(Debug, Clone, Copy)]
+pub struct ConfigField {
+    /// 在配置表中的键，如 `port`
+    pub key: &'static str,
+    /// 环境变量，如 `KOVI_SERVER_PORT`
+    pub env: &'static str,
+    /// 命令行参数，如 `--server-port`
+    pub arg: &'static str,
+    pub kind: FieldKind,
+}
+
+impl ConfigField {
+    pub const fn new(
+        key: &'static str,
+        env: &'static str,
+        arg: &'static str,
+        kind: FieldKind,
+    ) -> Self {
+        ConfigField {
+            key,
+            env,
+            arg,
+            kind,
+        }
+    }
+}
+
+/// 环境变量与命令行参数中的配置，见 [模块文档](self)
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOverrides {
+    env: HashMap<String, String>,
+    args: Vec<String>,
+    strict: bool,
+}
+
+impl ConfigOverrides {
+    /// 读取当前进程的环境变量与命令行参数
+    pub fn from_env_and_args() -> Self {
+        let env = std::env::vars_os()
+            .filter_map(|(k, v)| Some((k.into_string().ok()?, v.into_string().ok()?)));
+        Self::new(env, std::env::args().skip(1))
+    }
+
+    /// `args` 不包括程序名。接受 `--name=value`、`--name value` 与只有名字的 `--name`
+    ///
+    /// 只有名字的 [`FieldKind::Bool`] 参数为 `true`，不会取走后面的参数，设为 `false` 时使用 `--name=false`。
+    pub fn new(
+        env: impl IntoIterator<Item = (String, String)>,
+        args: impl IntoIterator<Item = String>,
+    ) -> Self {
+        let env: HashMap<String, String> = env
+            .into_iter()
+            .filter(|(k, _)| k.starts_with("KOVI_"))
+            .collect();
+        let args: Vec<String> = args.into_iter().collect();
+
+        let strict = arg_value(&args, "--strict", FieldKind::Bool)
+            .or_else(|| env.get("KOVI_STRICT").map(String::as_str))
+            .is_some_and(|v| parse_bool(v) == Some(true));
+        ConfigOverrides { env, args, strict }
+    }
+
+    /// 是否为严格模式，缺少配置时返回错误而不是询问
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// 设置严格模式
+    pub fn set_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// 缺少配置时能否询问用户
+    pub fn can_prompt(&self) -> bool {
+        !self.strict && std::io::stdin().is_terminal()
+    }
+
+    /// `field` 的值与来源（环境变量名或参数名），参数优先
+    fn get(&self, field: &ConfigField) -> Option<(&'static str, &str)> {
+        if let Some(v) = arg_value(&self.args, field.arg, field.kind) {
+            return Some((field.arg, v));
+        }
+        self.env.get(field.env).map(|v| (field.env, v.as_str()))
+    }
+
+    /// 记录有驱动读取了 `[table]`，同一进程中再次读取且 `fields` 中有项被覆盖时返回错误
+    ///
+    /// 环境变量与命令行参数不区分账号，多账号的 Bot 中每个驱动都读取 `[table]` 时，
+    /// 一个 `--server-port` 会覆盖所有账号。这时应只让一个账号读取配置文件，其他账号在代码中构造配置。
+    pub fn claim_table(&self, table: &str, fields: &[ConfigField]) -> Result<(), BotBuildError> {
+        static CLAIMED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+        let mut claimed = CLAIMED.lock();
+        if !claimed.iter().any(|v| v == table) {
+            claimed.push(table.to_string());
+            return Ok(());
+        }
+        if fields.iter().any(|field| self.get(field).is_some()) {
+            return Err(BotBuildError::SharedOverrides(table.to_string()));
+        }
+        Ok(())
+    }
+
+    /// 读取配置文件中的 `[table]`，再以环境变量与命令行参数覆盖 `fields`
+    ///
+    /// 文件与环境变量、参数中都没有这部分配置，或者文件中的配置无效时：
+    /// 能询问用户则返回 `Ok(None)`，由调用方询问；否则返回错误。
+    pub fn load_table<T: DeserializeOwned>(
+        &self,
+        path: &Path,
+        table: &str,
+        fields: &[ConfigField],
+    ) -> Result<Option<T>, BotBuildError> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(v) => v,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(BotBuildError::FileReadError(e.to_string())),
+        };
+        let mut doc = match toml::from_str::<toml::Table>(&content) {
+            Ok(v) => v,
+            Err(e) if self.can_prompt() => {
+                eprintln!("Configuration file parsing error: {e}");
+                return Ok(None);
+            }
+            Err(e) => return Err(BotBuildError::TomlParseError(e.to_string())),
+        };
+
+        let mut overridden = false;
+        for field in fields {
+            let Some((name, value)) = self.get(field) else {
+                continue;
+            };
+            let value = to_toml(field.kind, value).ok_or_else(|| BotBuildError::InvalidValue {
+                name: name.to_string(),
+                value: value.to_string(),
+                expected: expected(field.kind),
+            })?;
+            let section = doc
+                .entry(table)
+                .or_insert_with(|| toml::Value::Table(Default::default()));
+            if !section.is_table() {
+                *section = toml::Value::Table(Default::default());
+            }
+            if let toml::Value::Table(section) = section {
+                section.insert(field.key.to_string(), value);
+            }
+            overridden = true;
+        }
+
+        let Some(section) = doc.remove(table) else {
+            if self.can_prompt() {
+                return Ok(None);
+            }
+            return Err(BotBuildError::MissingConfig(missing_message(
+                path, table, fields,
+            )));
+        };
+        match section.try_into::<T>() {
+            Ok(v) => Ok(Some(v)),
+            Err(e) if !overridden && self.can_prompt() => {
+                eprintln!("Configuration file parsing error: {e}");
+                Ok(None)
+            }
+            Err(e) => Err(BotBuildError::TomlParseError(format!(
+                "[{table}] in {path:?} with environment variables and arguments: {e}"
+            ))),
+        }
+    }
+}
+
+/// 参数 `name` 的值，出现多次时最后一次生效
+///
+/// `--name value` 的形式只在 `kind` 不是 [`FieldKind::Bool`] 时取走后面的参数。
+fn arg_value<'a>(args: &'a [String], name: &str, kind: FieldKind) -> Option<&'a str> {
+    let mut value = None;
+    let mut args = args.iter().peekable();
+    while let Some(arg) = args.next() {
+        if let Some(v) = arg.strip_prefix(name).and_then(|v| v.strip_prefix('=')) {
+            value = Some(v);
+        } else if arg == name {
+            value = match kind {
+                FieldKind::Bool => Some("true"),
+                _ => Some(
+                    args.next_if(|next| !next.starts_with("--"))
+                        .map_or("", String::as_str),
+                ),
+            };
+        }
+    }
+    value
+}
+
+fn missing_message(path: &Path, table: &str, fields: &[ConfigField]) -> String {
+    let fields = fields
+        .iter()
+        .map(|f| format!("  {table}.{} / {} / {}", f.key, f.env, f.arg))
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "[{table}] is not set in {path:?}. Set it in the file, \
+         or with environment variables or arguments:\n{fields}"
+    )
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Some(true),
+        "false" | "0" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_id(value: &str) -> toml::Value {
+    let value = value.trim();
+    match value.parse::<i64>() {
+        Ok(v) => toml::Value::Integer(v),
+        Err(_) => toml::Value::String(value.to_string()),
+    }
+}
+
+fn to_toml(kind: FieldKind, value: &str) -> Option<toml::Value> {
+    Some(match kind {
+        FieldKind::String => toml::Value::String(value.to_string()),
+        FieldKind::Integer => toml::Value::Integer(value.trim().parse().ok()?),
+        FieldKind::Bool => toml::Value::Boolean(parse_bool(value)?),
+        FieldKind::Id => parse_id(value),
+        FieldKind::IdList => toml::Value::Array(
+            value
+                .split(',')
+                .filter(|v| !v.trim().is_empty())
+                .map(parse_id)
+                .collect(),
+        ),
+    })
+}
+
+fn expected(kind: FieldKind) -> &'static str {
+    match kind {
+        FieldKind::String => "a string",
+        FieldKind::Integer => "an integer",
+        FieldKind::Bool => "true or false",
+        FieldKind::Id => "an ID",
+        FieldKind::IdList => "IDs separated by `,`",
+    }
+}
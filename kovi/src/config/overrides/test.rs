@@ -0,0 +1,218 @@
+use super::{ConfigField, ConfigOverrides, FieldKind};
+use crate::config::kovi_conf::{CONFIG_FIELDS, Config};
+use crate::error::BotBuildError;
+use crate::event::id::ID;
+use serde::Deserialize;
+use std::path::PathBuf;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Server {
+    host: String,
+    port: u16,
+    #[serde(default)]
+    access_token: String,
+    #[serde(default)]
+    secure: bool,
+}
+
+const SERVER_FIELDS: &[ConfigField] = &[
+    ConfigField::new(
+        "host",
+        "KOVI_SERVER_HOST",
+        "--server-host",
+        FieldKind::String,
+    ),
+    ConfigField::new(
+        "port",
+        "KOVI_SERVER_PORT",
+        "--server-port",
+        FieldKind::Integer,
+    ),
+    ConfigField::new(
+        "access_token",
+        "KOVI_SERVER_ACCESS_TOKEN",
+        "--server-access-token",
+        FieldKind::String,
+    ),
+    ConfigField::new(
+        "secure",
+        "KOVI_SERVER_SECURE",
+        "--server-secure",
+        FieldKind::Bool,
+    ),
+];
+
+/// 每个测试使用自己的临时文件
+fn temp_file(name: &str, content: Option<&str>) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("kovi-overrides-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create dir");
+    let path = dir.join("kovi.conf.toml");
+    if let Some(content) = content {
+        std::fs::write(&path, content).expect("write");
+    }
+    path
+}
+
+fn overrides(env: &[(&str, &str)], args: &[&str]) -> ConfigOverrides {
+    ConfigOverrides::new(
+        env.iter().map(|(k, v)| (k.to_string(), v.to_string())),
+        args.iter().map(|v| v.to_string()),
+    )
+    .set_strict(true)
+}
+
+#[test]
+fn file_env_and_args_are_layered() {
+    let path = temp_file(
+        "layered",
+        Some("[server]\nhost = \"file\"\nport = 1\naccess_token = \"file\"\n"),
+    );
+
+    let server: Option<Server> = overrides(&[], &[])
+        .load_table(&path, "server", SERVER_FIELDS)
+        .expect("load");
+    assert_eq!(server.expect("server").host, "file");
+
+    let server: Server = overrides(
+        &[("KOVI_SERVER_HOST", "env"), ("KOVI_SERVER_PORT", "2")],
+        &["run", "--server-port", "3", "--server-secure"],
+    )
+    .load_table(&path, "server", SERVER_FIELDS)
+    .expect("load")
+    .expect("server");
+    assert_eq!(
+        server,
+        Server {
+            host: "env".to_string(),
+            port: 3,
+            access_token: "file".to_string(),
+            secure: true,
+        }
+    );
+
+    // 纯数字的 access_token 仍然是字符串
+    let server: Server = overrides(&[], &["--server-access-token=123456"])
+        .load_table(&path, "server", SERVER_FIELDS)
+        .expect("load")
+        .expect("server");
+    assert_eq!(server.access_token, "123456");
+}
+
+#[test]
+fn config_without_file() {
+    let path = temp_file("no-file", None);
+
+    let config: Config = overrides(
+        &[("KOVI_MAIN_ADMIN", "10001"), ("KOVI_ADMINS", "10002, abc")],
+        &["--debug"],
+    )
+    .load_table(&path, "config", CONFIG_FIELDS)
+    .expect("load")
+    .expect("config");
+    assert_eq!(config.main_admin, ID::new(10001i64));
+    assert_eq!(config.admins, [ID::new(10002i64), ID::new("abc")]);
+    assert!(config.debug);
+    assert!(!path.exists());
+}
+
+#[test]
+fn strict_mode_errors_instead_of_prompting() {
+    let path = temp_file("strict", Some("[config]\nmain_admin = 1\n"));
+
+    let Err(BotBuildError::MissingConfig(message)) =
+        overrides(&[], &[]).load_table::<Server>(&path, "server", SERVER_FIELDS)
+    else {
+        panic!("expected missing config");
+    };
+    assert!(message.contains("KOVI_SERVER_HOST"), "{message}");
+    assert!(message.contains("--server-port"), "{message}");
+
+    // 只提供部分配置项
+    assert!(matches!(
+        overrides(&[("KOVI_SERVER_HOST", "env")], &[]).load_table::<Server>(
+            &path,
+            "server",
+            SERVER_FIELDS
+        ),
+        Err(BotBuildError::TomlParseError(_))
+    ));
+
+    let Err(BotBuildError::InvalidValue { name, value, .. }) = overrides(
+        &[("KOVI_SERVER_PORT", "80")],
+        &["--server-port", "http"],
+    )
+    .load_table::<Server>(&path, "server", SERVER_FIELDS) else {
+        panic!("expected an invalid value");
+    };
+    assert_eq!((name.as_str(), value.as_str()), ("--server-port", "http"));
+}
+
+#[test]
+fn strict_flag() {
+    let strict = |env: &[(&str, &str)], args: &[&str]| {
+        ConfigOverrides::new(
+            env.iter().map(|(k, v)| (k.to_string(), v.to_string())),
+            args.iter().map(|v| v.to_string()),
+        )
+        .is_strict()
+    };
+    assert!(!strict(&[], &[]));
+    assert!(strict(&[("KOVI_STRICT", "true")], &[]));
+    assert!(strict(&[], &["--strict"]));
+    assert!(strict(&[], &["--strict", "serve"]));
+    assert!(!strict(&[("KOVI_STRICT", "1")], &["--strict=false"]));
+}
+
+#[test]
+fn bool_flags_do_not_take_positionals() {
+    let path = temp_file("positional", None);
+
+    let config: Config = overrides(&[], &["--debug", "serve", "--main-admin", "10001"])
+        .load_table(&path, "config", CONFIG_FIELDS)
+        .expect("load")
+        .expect("config");
+    assert!(config.debug);
+    assert_eq!(config.main_admin, ID::new(10001i64));
+
+    let server: Server = overrides(
+        &[],
+        &[
+            "--server-secure",
+            "run",
+            "--server-host",
+            "example.com",
+            "--server-port=80",
+        ],
+    )
+    .load_table(&path, "server", SERVER_FIELDS)
+    .expect("load")
+    .expect("server");
+    assert!(server.secure);
+    assert_eq!(server.host, "example.com");
+
+    let config: Config = overrides(&[], &["--main-admin", "1", "--debug=false", "serve"])
+        .load_table(&path, "config", CONFIG_FIELDS)
+        .expect("load")
+        .expect("config");
+    assert!(!config.debug);
+}
+
+#[test]
+fn second_claim_with_overrides_is_rejected() {
+    let none = overrides(&[], &[]);
+    let port = overrides(&[], &["--server-port", "8081"]);
+
+    // 没有覆盖时多个驱动可以读取同一部分
+    none.claim_table("claim-plain", SERVER_FIELDS)
+        .expect("first");
+    none.claim_table("claim-plain", SERVER_FIELDS)
+        .expect("second");
+
+    port.claim_table("claim-overridden", SERVER_FIELDS)
+        .expect("first");
+    assert!(matches!(
+        port.claim_table("claim-overridden", SERVER_FIELDS),
+        Err(BotBuildError::SharedOverrides(table)) if table == "claim-overridden"
+    ));
+}
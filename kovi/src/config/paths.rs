@@ -0,0 +1,173 @@
+//! 配置与数据的目录
+//!
+//! Kovi 读写的所有文件（`kovi.conf.toml`、`kovi.plugin.toml`、插件的数据目录）都通过 [`KoviPaths`] 取得路径。
+//!
+//! 目录按以下顺序决定：
+//!
+//! 1. 在 `main` 开头通过 [`KoviPaths::set_current()`] 设置的目录
+//! 2. 环境变量 `KOVI_CONFIG_DIR` 与 `KOVI_DATA_DIR`，没有时使用 `KOVI_HOME`（数据目录为 `$KOVI_HOME/data`）
+//! 3. 当前目录中已有 `kovi.conf.toml` 时使用当前目录，与以前的版本相同
+//! 4. `$XDG_CONFIG_HOME/kovi` 与 `$XDG_DATA_HOME/kovi`，
+//!    没有设置时为 `~/.config/kovi` 与 `~/.local/share/kovi`
+//!
+//! 在同一台机器上运行多个 Bot 时，为每个 Bot 设置不同的 `KOVI_HOME` 即可。
+//!
+//! # Examples
+//! ```ignore
+//! // 打包为系统服务时
+//! kovi::config::paths::KoviPaths::new("/etc/my-bot", "/var/lib/my-bot").set_current();
+//!
+//! let bot = kovi::build_bot!(...);
+//! ```
+
+use parking_lot::{Mutex, MutexGuard, RwLock};
+use std::env;
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+mod test;
+
+/// Kovi 配置文件的文件名
+pub const CONF_FILE: &str = "kovi.conf.toml";
+/// 插件状态文件的文件名
+pub const PLUGIN_STATUS_FILE: &str = "kovi.plugin.toml";
+
+static CURRENT: RwLock<Option<KoviPaths>> = RwLock::new(None);
+
+static CONF_FILE_LOCK: Mutex<()> = Mutex::new(());
+
+/// 读改写 [`kovi.conf.toml`](KoviPaths::conf_file) 时，从读取到写入完成都需要持有返回的锁
+///
+/// Kovi 与驱动都通过它修改配置文件，同时修改不同部分时不会互相覆盖。
+pub fn lock_conf_file() -> MutexGuard<'static, ()> {
+    CONF_FILE_LOCK.lock()
+}
+
+/// 写入配置文件，先写入临时文件再重命名，写入中途出错时原文件不受影响
+///
+/// 需要持有 [`lock_conf_file()`] 返回的锁。
+pub fn write_conf_file(path: &Path, text: &str) -> std::io::Result<()> {
+    crate::storage::write_atomic(path, text.as_bytes())
+}
+
+/// 配置与数据的目录，见 [模块文档](self)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KoviPaths {
+    config_dir: PathBuf,
+    data_dir: PathBuf,
+}
+
+impl KoviPaths {
+    pub fn new(config_dir: impl Into<PathBuf>, data_dir: impl Into<PathBuf>) -> Self {
+        KoviPaths {
+            config_dir: config_dir.into(),
+            data_dir: data_dir.into(),
+        }
+    }
+
+    /// 配置文件放在 `dir` 中，数据放在 `dir/data` 中
+    pub fn in_dir(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        KoviPaths {
+            data_dir: dir.join("data"),
+            config_dir: dir,
+        }
+    }
+
+    /// 按环境变量与默认位置决定目录，不考虑 [`set_current()`](Self::set_current)
+    pub fn from_env() -> Self {
+        resolve(env_path, env::current_dir().unwrap_or_default())
+    }
+
+    /// 当前使用的目录
+    ///
+    /// 没有 [`set_current()`](Self::set_current) 时，第一次调用时按 [`from_env()`](Self::from_env) 决定，之后不再改变。
+    pub fn current() -> KoviPaths {
+        if let Some(paths) = CURRENT.read().as_ref() {
+            return paths.clone();
+        }
+        CURRENT
+            .write()
+            .get_or_insert_with(KoviPaths::from_env)
+            .clone()
+    }
+
+    /// 设置之后 Kovi 与驱动使用的目录，需要在读取配置之前调用
+    pub fn set_current(self) {
+        log::debug!(
+            "Using config dir {:?}, data dir {:?}",
+            self.config_dir,
+            self.data_dir
+        );
+        *CURRENT.write() = Some(self);
+    }
+
+    /// 配置文件所在的目录
+    pub fn config_dir(&self) -> &Path {
+        &self.config_dir
+    }
+
+    /// 数据目录，每个插件的数据在其中以插件名命名的目录中
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// `kovi.conf.toml` 的路径
+    pub fn conf_file(&self) -> PathBuf {
+        self.config_dir.join(CONF_FILE)
+    }
+
+    /// `kovi.plugin.toml` 的路径
+    pub fn plugin_status_file(&self) -> PathBuf {
+        self.config_dir.join(PLUGIN_STATUS_FILE)
+    }
+
+    /// 插件 `name` 的数据目录
+    pub fn plugin_data_dir(&self, name: &str) -> PathBuf {
+        self.data_dir.join(name)
+    }
+}
+
+fn env_path(key: &str) -> Option<PathBuf> {
+    env::var_os(key)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+/// 按模块文档中的顺序（除 `set_current()` 外）决定目录，`env` 读取环境变量
+fn resolve(env: impl Fn(&str) -> Option<PathBuf>, current_dir: PathBuf) -> KoviPaths {
+    // XDG 规定环境变量不是绝对路径时视为未设置
+    let xdg_dir = |key: &str, home_relative: &str| match env(key) {
+        Some(dir) if dir.is_absolute() => Some(dir),
+        _ => env("HOME").map(|home| home.join(home_relative)),
+    };
+    let default = || {
+        if let Some(home) = env("KOVI_HOME") {
+            return KoviPaths::in_dir(home);
+        }
+        if current_dir.join(CONF_FILE).exists() {
+            return KoviPaths::in_dir(&current_dir);
+        }
+        match (
+            xdg_dir("XDG_CONFIG_HOME", ".config"),
+            xdg_dir("XDG_DATA_HOME", ".local/share"),
+        ) {
+            (Some(config), Some(data)) => KoviPaths::new(config.join("kovi"), data.join("kovi")),
+            _ => KoviPaths::in_dir(&current_dir),
+        }
+    };
+
+    KoviPaths {
+        config_dir: env("KOVI_CONFIG_DIR").unwrap_or_else(|| default().config_dir),
+        data_dir: env("KOVI_DATA_DIR").unwrap_or_else(|| default().data_dir),
+    }
+}
+
+/// 创建文件所在的目录
+#[cfg(feature = "save_plugin_status")]
+pub(crate) fn create_parent_dir(path: &Path) -> std::io::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => std::fs::create_dir_all(parent),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,92 @@
+use super::{CONF_FILE, KoviPaths, resolve};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<PathBuf> {
+    let vars: HashMap<String, PathBuf> = vars
+        .iter()
+        .map(|(k, v)| (k.to_string(), PathBuf::from(v)))
+        .collect();
+    move |key| vars.get(key).cloned()
+}
+
+/// 不存在 `kovi.conf.toml` 的目录
+fn empty_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("kovi-paths-empty-{}", std::process::id()))
+}
+
+#[test]
+fn xdg_defaults() {
+    let paths = resolve(env(&[("HOME", "/home/kovi")]), empty_dir());
+    assert_eq!(
+        paths,
+        KoviPaths::new("/home/kovi/.config/kovi", "/home/kovi/.local/share/kovi")
+    );
+    assert_eq!(
+        paths.conf_file(),
+        PathBuf::from("/home/kovi/.config/kovi/kovi.conf.toml")
+    );
+    assert_eq!(
+        paths.plugin_data_dir("weather"),
+        PathBuf::from("/home/kovi/.local/share/kovi/weather")
+    );
+
+    let paths = resolve(
+        env(&[
+            ("HOME", "/home/kovi"),
+            ("XDG_CONFIG_HOME", "/xdg/config"),
+            ("XDG_DATA_HOME", "relative"),
+        ]),
+        empty_dir(),
+    );
+    assert_eq!(
+        paths,
+        KoviPaths::new("/xdg/config/kovi", "/home/kovi/.local/share/kovi")
+    );
+
+    // 没有 HOME 时使用当前目录
+    assert_eq!(
+        resolve(env(&[]), empty_dir()),
+        KoviPaths::in_dir(empty_dir())
+    );
+}
+
+#[test]
+fn existing_conf_in_current_dir_is_kept() {
+    let dir = std::env::temp_dir().join(format!("kovi-paths-legacy-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("create dir");
+    std::fs::write(dir.join(CONF_FILE), "").expect("write");
+
+    let paths = resolve(env(&[("HOME", "/home/kovi")]), dir.clone());
+    assert_eq!(paths.conf_file(), dir.join(CONF_FILE));
+    assert_eq!(paths.data_dir(), dir.join("data"));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn env_vars_take_precedence() {
+    let paths = resolve(
+        env(&[("HOME", "/home/kovi"), ("KOVI_HOME", "/srv/bot-a")]),
+        empty_dir(),
+    );
+    assert_eq!(paths, KoviPaths::new("/srv/bot-a", "/srv/bot-a/data"));
+
+    let paths = resolve(
+        env(&[
+            ("KOVI_HOME", "/srv/bot-a"),
+            ("KOVI_CONFIG_DIR", "/etc/bot-a"),
+        ]),
+        empty_dir(),
+    );
+    assert_eq!(paths, KoviPaths::new("/etc/bot-a", "/srv/bot-a/data"));
+
+    let paths = resolve(
+        env(&[
+            ("KOVI_CONFIG_DIR", "/etc/bot-a"),
+            ("KOVI_DATA_DIR", "/var/lib/bot-a"),
+        ]),
+        empty_dir(),
+    );
+    assert_eq!(paths, KoviPaths::new("/etc/bot-a", "/var/lib/bot-a"));
+}
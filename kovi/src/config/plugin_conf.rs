@@ -0,0 +1,276 @@
+//! 插件的配置
+//!
+//! 每个插件的配置保存在 `kovi.conf.toml` 的 `[plugins.<插件名>]` 中，
+//! 通过 [`PluginBuilder::config()`](crate::PluginBuilder::config) 读取，
+//! 需要检查配置时使用 [`PluginBuilder::config_with()`](crate::PluginBuilder::config_with)。
+//!
+//! ```toml
+//! [config]
+//! main_admin = 10001
+//! admins = []
+//! debug = false
+//!
+//! [plugins.weather]
+//! api_key = "..."
+//! cities = ["Beijing"]
+//! ```
+
+use crate::config::paths::lock_conf_file;
+use crate::error::ConfigError;
+use crate::storage::write_atomic;
+use serde::de::{DeserializeOwned, DeserializeSeed, IgnoredAny, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::convert::Infallible;
+use std::fmt;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+
+#[cfg(test)]
+mod test;
+
+/// 检查配置文件是否修改的间隔
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 只反序列化 `keys` 所指的值，其余的值被跳过
+struct KeyPath<'a, T> {
+    keys: &'a [&'a str],
+    _marker: PhantomData<T>,
+}
+
+impl<'a, T> KeyPath<'a, T> {
+    fn new(keys: &'a [&'a str]) -> Self {
+        KeyPath {
+            keys,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> DeserializeSeed<'de> for KeyPath<'_, T> {
+    type Value = Option<T>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for KeyPath<'_, T> {
+    type Value = Option<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a table containing `{}`", self.keys[0])
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut value = None;
+        while let Some(key) = map.next_key::<String>()? {
+            if key != self.keys[0] {
+                map.next_value::<IgnoredAny>()?;
+            } else if self.keys.len() == 1 {
+                value = Some(map.next_value()?);
+            } else {
+                value = map.next_value_seed(KeyPath::new(&self.keys[1..]))?;
+            }
+        }
+        Ok(value)
+    }
+}
+
+/// 从 `content` 中读取 `[plugins.<plugin>]`，没有时返回 `None`
+///
+/// 错误信息中带有出错的行与列。
+fn parse_section<T: DeserializeOwned>(
+    path: &Path,
+    content: &str,
+    plugin: &str,
+) -> Result<Option<T>, ConfigError> {
+    let deserializer = toml::Deserializer::parse(content).map_err(|e| ConfigError::Parse {
+        path: path.to_path_buf(),
+        error: e.to_string(),
+    })?;
+    KeyPath::new(&["plugins", plugin])
+        .deserialize(deserializer)
+        .map_err(|e| ConfigError::Invalid {
+            path: path.to_path_buf(),
+            plugin: plugin.to_string(),
+            error: e.to_string(),
+        })
+}
+
+fn read_to_string(path: &Path) -> Result<String, ConfigError> {
+    match std::fs::read_to_string(path) {
+        Ok(v) => Ok(v),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 不做任何检查的 `validate`
+pub(crate) fn accept<T>(_: &T) -> Result<(), Infallible> {
+    Ok(())
+}
+
+fn check<T, E: fmt::Display>(
+    path: &Path,
+    plugin: &str,
+    config: &T,
+    validate: &impl Fn(&T) -> Result<(), E>,
+) -> Result<(), ConfigError> {
+    validate(config).map_err(|e| ConfigError::Invalid {
+        path: path.to_path_buf(),
+        plugin: plugin.to_string(),
+        error: e.to_string(),
+    })
+}
+
+/// 读取插件的配置并以 `validate` 检查，没有时写入 `T::default()`
+///
+/// 默认配置同样需要通过检查，没有通过时写入后返回错误，由用户修改。
+pub(crate) fn load<T, E: fmt::Display>(
+    path: &Path,
+    plugin: &str,
+    validate: &impl Fn(&T) -> Result<(), E>,
+) -> Result<T, ConfigError>
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    let _lock = lock_conf_file();
+    let content = read_to_string(path)?;
+    let config = match parse_section(path, &content, plugin)? {
+        Some(config) => config,
+        None => {
+            let config = T::default();
+            write_section(path, &content, plugin, &config)?;
+            log::info!("Wrote default config of plugin {plugin} to {path:?}");
+            config
+        }
+    };
+    check(path, plugin, &config, validate)?;
+    Ok(config)
+}
+
+/// 保留文件中的其他内容，写入 `[plugins.<plugin>]`
+fn write_section<T: Serialize>(
+    path: &Path,
+    content: &str,
+    plugin: &str,
+    config: &T,
+) -> Result<(), ConfigError> {
+    let mut doc = content
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|e| ConfigError::Parse {
+            path: path.to_path_buf(),
+            error: e.to_string(),
+        })?;
+    let section = toml::to_string(config)
+        .map_err(|e| e.to_string())
+        .and_then(|v| {
+            v.parse::<toml_edit::DocumentMut>()
+                .map_err(|e| e.to_string())
+        })
+        .map_err(|error| ConfigError::Serialize {
+            plugin: plugin.to_string(),
+            error,
+        })?;
+
+    let plugins = doc.entry("plugins").or_insert_with(|| {
+        let mut table = toml_edit::Table::new();
+        table.set_implicit(true);
+        toml_edit::Item::Table(table)
+    });
+    let Some(plugins) = plugins.as_table_mut() else {
+        return Err(ConfigError::Invalid {
+            path: path.to_path_buf(),
+            plugin: plugin.to_string(),
+            error: "`plugins` is not a table".to_string(),
+        });
+    };
+    plugins.insert(plugin, toml_edit::Item::Table(section.as_table().clone()));
+
+    write_atomic(path, doc.to_string().as_bytes())?;
+    Ok(())
+}
+
+fn modified(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// 读取时也持有锁，不会读到写入一半的文件
+fn read_locked(path: &Path) -> Result<String, ConfigError> {
+    let _lock = lock_conf_file();
+    read_to_string(path)
+}
+
+/// 每隔 `interval` 检查文件，插件的配置改变后以 `validate` 检查，通过后发送到 `tx`
+///
+/// 新的配置无效时记录错误，保留原有的配置。所有接收端都被丢弃后结束。
+pub(crate) async fn watch<T, E, V>(
+    path: PathBuf,
+    plugin: String,
+    tx: watch::Sender<T>,
+    interval: Duration,
+    validate: V,
+) where
+    T: DeserializeOwned,
+    E: fmt::Display,
+    V: Fn(&T) -> Result<(), E>,
+{
+    let mut last_modified = modified(&path);
+    let mut last = read_locked(&path)
+        .and_then(|content| parse_section::<toml::Value>(&path, &content, &plugin))
+        .ok()
+        .flatten();
+
+    let mut interval = tokio::time::interval(interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    loop {
+        interval.tick().await;
+        if tx.is_closed() {
+            return;
+        }
+        let now_modified = modified(&path);
+        if now_modified == last_modified {
+            continue;
+        }
+        last_modified = now_modified;
+
+        let content = match read_locked(&path) {
+            Ok(v) => v,
+            Err(e) => {
+                log::error!("Failed to reload config of plugin {plugin}: {e}");
+                continue;
+            }
+        };
+        let raw = match parse_section::<toml::Value>(&path, &content, &plugin) {
+            Ok(v) => v,
+            Err(e) => {
+                log::error!("Failed to reload config of plugin {plugin}: {e}");
+                continue;
+            }
+        };
+        if raw == last {
+            continue;
+        }
+        let config = parse_section::<T>(&path, &content, &plugin).and_then(|config| {
+            config
+                .map(|config| check(&path, &plugin, &config, &validate).map(|_| config))
+                .transpose()
+        });
+        match config {
+            Ok(Some(config)) => {
+                last = raw;
+                tx.send_replace(config);
+                log::info!("Reloaded config of plugin {plugin}");
+            }
+            Ok(None) => {
+                log::warn!("Config of plugin {plugin} was removed, keeping the current one");
+            }
+            Err(e) => {
+                log::error!("Failed to reload config of plugin {plugin}: {e}");
+            }
+        }
+    }
+}
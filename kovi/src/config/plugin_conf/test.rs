@@ -0,0 +1,239 @@
+use super::{accept, watch};
+use crate::error::ConfigError;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WeatherConf {
+    api_key: String,
+    cities: Vec<String>,
+    retry: Retry,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Retry {
+    times: u8,
+}
+
+impl Default for WeatherConf {
+    fn default() -> Self {
+        WeatherConf {
+            api_key: String::new(),
+            cities: vec!["Beijing".to_string()],
+            retry: Retry { times: 3 },
+        }
+    }
+}
+
+fn load<T>(path: &Path, plugin: &str) -> Result<T, ConfigError>
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    super::load(path, plugin, &accept)
+}
+
+/// 每个测试使用自己的临时文件
+fn temp_file(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("kovi-config-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir.join("kovi.conf.toml")
+}
+
+const CONF: &str = r#"[config]
+main_admin = 10001
+admins = []
+debug = false
+"#;
+
+#[test]
+fn defaults_written_on_first_run() {
+    let path = temp_file("defaults");
+    std::fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+    std::fs::write(&path, CONF).expect("write");
+
+    let conf: WeatherConf = load(&path, "weather").expect("load");
+    assert_eq!(conf, WeatherConf::default());
+
+    let content = std::fs::read_to_string(&path).expect("read");
+    assert!(content.starts_with(CONF), "{content}");
+    assert!(content.contains("[plugins.weather]"), "{content}");
+    assert!(content.contains("[plugins.weather.retry]"), "{content}");
+    assert!(!content.contains("[plugins]\n"), "{content}");
+
+    // 第二次读取文件中的配置
+    std::fs::write(&path, content.replace("\"Beijing\"", "\"Tokyo\"")).expect("write");
+    let conf: WeatherConf = load(&path, "weather").expect("load");
+    assert_eq!(conf.cities, ["Tokyo"]);
+
+    // 其他插件的配置互不影响
+    let other: WeatherConf = load(&path, "other").expect("load");
+    assert_eq!(other, WeatherConf::default());
+    assert_eq!(
+        load::<WeatherConf>(&path, "weather").expect("load").cities,
+        ["Tokyo"]
+    );
+}
+
+#[test]
+fn invalid_values_report_location() {
+    let path = temp_file("invalid");
+    std::fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+    let content = format!(
+        "{CONF}\n[plugins.other]\nanything = 1\n\n[plugins.weather]\napi_key = \"k\"\ncities = []\n\n[plugins.weather.retry]\ntimes = 300\n"
+    );
+    std::fs::write(&path, &content).expect("write");
+
+    let Err(ConfigError::Invalid { plugin, error, .. }) = load::<WeatherConf>(&path, "weather")
+    else {
+        panic!("expected an invalid config");
+    };
+    assert_eq!(plugin, "weather");
+    assert!(error.contains("line 14"), "{error}");
+    assert!(error.contains("times = 300"), "{error}");
+
+    // 无效的配置不会被默认值覆盖
+    assert_eq!(std::fs::read_to_string(&path).expect("read"), content);
+
+    std::fs::write(
+        &path,
+        "[plugins.weather]\napi_key = \"k\"\ncities = []\ncolor = true\n",
+    )
+    .expect("write");
+    let error = load::<WeatherConf>(&path, "weather")
+        .expect_err("unknown field")
+        .to_string();
+    assert!(error.contains("line 4"), "{error}");
+
+    std::fs::write(&path, "[plugins.weather\n").expect("write");
+    assert!(matches!(
+        load::<WeatherConf>(&path, "weather"),
+        Err(ConfigError::Parse { .. })
+    ));
+}
+
+#[tokio::test]
+async fn changes_are_pushed_to_watchers() {
+    let path = temp_file("watch");
+    std::fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+    std::fs::write(&path, CONF).expect("write");
+    let conf: WeatherConf = load(&path, "weather").expect("load");
+    let content = std::fs::read_to_string(&path).expect("read");
+
+    let (tx, mut rx) = tokio::sync::watch::channel(conf);
+    tokio::spawn(watch(
+        path.clone(),
+        "weather".to_string(),
+        tx,
+        Duration::from_millis(20),
+        accept,
+    ));
+    let changed = |rx: &mut tokio::sync::watch::Receiver<WeatherConf>| {
+        let mut rx = rx.clone();
+        async move {
+            tokio::time::timeout(Duration::from_secs(2), rx.changed())
+                .await
+                .is_ok()
+        }
+    };
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    // 其他部分的修改不会推送
+    std::fs::write(&path, content.replace("debug = false", "debug = true")).expect("write");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!rx.has_changed().expect("watching"));
+
+    let content = content.replace("debug = false", "debug = true");
+    std::fs::write(&path, content.replace("times = 3", "times = 5")).expect("write");
+    assert!(changed(&mut rx).await);
+    assert_eq!(rx.borrow_and_update().retry.times, 5);
+
+    // 无效的配置被忽略
+    std::fs::write(&path, content.replace("times = 3", "times = -1")).expect("write");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!rx.has_changed().expect("watching"));
+    assert_eq!(rx.borrow().retry.times, 5);
+
+    std::fs::write(&path, content.replace("times = 3", "times = 7")).expect("write");
+    assert!(changed(&mut rx).await);
+    assert_eq!(rx.borrow_and_update().retry.times, 7);
+
+    let _ = std::fs::remove_dir_all(path.parent().expect("parent"));
+}
+
+#[tokio::test]
+async fn validation_applies_on_load_and_reload() {
+    let path = temp_file("validate");
+    std::fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+    std::fs::write(&path, CONF).expect("write");
+    let validate = |conf: &WeatherConf| {
+        if conf.retry.times > 5 {
+            return Err(format!("retry.times is {}, at most 5", conf.retry.times));
+        }
+        Ok(())
+    };
+
+    let conf: WeatherConf = super::load(&path, "weather", &validate).expect("load");
+    let content = std::fs::read_to_string(&path).expect("read");
+
+    std::fs::write(&path, content.replace("times = 3", "times = 9")).expect("write");
+    let Err(ConfigError::Invalid { error, .. }) =
+        super::load::<WeatherConf, _>(&path, "weather", &validate)
+    else {
+        panic!("expected an invalid config");
+    };
+    assert_eq!(error, "retry.times is 9, at most 5");
+    std::fs::write(&path, &content).expect("write");
+
+    let (tx, rx) = tokio::sync::watch::channel(conf);
+    tokio::spawn(watch(
+        path.clone(),
+        "weather".to_string(),
+        tx,
+        Duration::from_millis(20),
+        validate,
+    ));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    // 没有通过检查的配置被忽略
+    std::fs::write(&path, content.replace("times = 3", "times = 9")).expect("write");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!rx.has_changed().expect("watching"));
+
+    std::fs::write(&path, content.replace("times = 3", "times = 4")).expect("write");
+    let mut changed = rx.clone();
+    tokio::time::timeout(Duration::from_secs(2), changed.changed())
+        .await
+        .expect("changed")
+        .expect("watching");
+    assert_eq!(rx.borrow().retry.times, 4);
+
+    let _ = std::fs::remove_dir_all(path.parent().expect("parent"));
+}
+
+#[test]
+fn concurrent_writers_keep_every_section() {
+    let path = temp_file("concurrent");
+    std::fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+    std::fs::write(&path, CONF).expect("write");
+
+    std::thread::scope(|s| {
+        for i in 0..16 {
+            let path = &path;
+            s.spawn(move || load::<WeatherConf>(path, &format!("plugin{i}")).expect("load"));
+        }
+    });
+
+    let content = std::fs::read_to_string(&path).expect("read");
+    assert!(content.starts_with(CONF), "{content}");
+    for i in 0..16 {
+        assert!(
+            content.contains(&format!("[plugins.plugin{i}]")),
+            "{content}"
+        );
+    }
+
+    let _ = std::fs::remove_dir_all(path.parent().expect("parent"));
+}
@@ -1,12 +1,15 @@
 use crate::ApiReturn;
 use crate::bot::SendApi;
-use crate::event::MessageEventTrait;
+use crate::event::id::ID;
+use crate::event::{MessageEventTrait, parse_cached};
 use crate::types::ArcTypeDeMsgEventFn;
 use futures_util::Stream;
 use serde_json::Value;
 use std::pin::Pin;
 use std::sync::Arc;
 
+pub mod record;
+
 pub enum DriverEvent {
     /// Drive 的退出事件
     Exit,
@@ -28,8 +31,15 @@ pub trait Driver: Send + Sync {
     fn api_handler(&self, value: SendApi) -> ApiHandlerResult;
 
     fn message_event_register(&self) -> MessageEventRegister;
+
+    /// 向用户私聊发送一条文本消息的 API，Kovi 用它通知管理员。不支持时返回 `None`
+    fn private_message_api(&self, user_id: &ID, text: &str) -> Option<SendApi> {
+        let _ = (user_id, text);
+        None
+    }
 }
 
+#[derive(Clone)]
 pub struct MessageEventRegister {
     pub(crate) type_de: ArcTypeDeMsgEventFn,
 }
@@ -37,8 +47,16 @@ impl MessageEventRegister {
     pub fn register<T: MessageEventTrait + Send + Sync>() -> Self {
         MessageEventRegister {
             type_de: Arc::new(|value, bot_info, sender| {
-                Some(Arc::new(T::de(value, bot_info, sender)?))
+                Some(parse_cached::<T>(value, bot_info, sender)?)
             }),
         }
     }
+
+    /// 不解析消息事件
+    #[cfg(feature = "testing")]
+    pub(crate) fn none() -> Self {
+        MessageEventRegister {
+            type_de: Arc::new(|_, _, _| None),
+        }
+    }
 }
@@ -0,0 +1,358 @@
+//! 录制与回放
+//!
+//! [`RecordingDriver`] 包裹一个驱动，把收到的每个事件、发出的每个 API 及其返回按行写入 JSONL 文件。
+//! [`ReplayDriver`] 读取录制的文件，重新推送其中的事件，并用录制的返回回答 API，
+//! 从而可以离线地用新版本的插件重跑一段真实的会话。
+//!
+//! # Examples
+//! ```ignore
+//! use kovi::driver::record::{RecordingDriver, ReplayDriver, ReplayPace};
+//!
+//! // 录制
+//! let driver = RecordingDriver::new(OneBotDriver::new(server), "session.jsonl")?;
+//! let bot = Bot::build(conf, driver);
+//!
+//! // 回放
+//! let register = MessageEventRegister::register::<kovi_onebot::MsgEvent>();
+//! let driver = ReplayDriver::open("session.jsonl", register)?.set_pace(ReplayPace::Fast);
+//! let bot = Bot::build(conf, driver);
+//! ```
+
+use crate::ApiReturn;
+use crate::bot::SendApi;
+use crate::driver::{AnyError, ApiHandlerResult, Driver, DriverEvent, MessageEventRegister};
+use crate::error::ReplayError;
+use crate::event::id::ID;
+use futures_util::{Stream, StreamExt as _};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead as _, BufReader, BufWriter, Write as _};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// 回放中没有录制到的 API 的返回码
+pub const NOT_RECORDED_RETCODE: i32 = -404;
+
+/// 录制文件中的一行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    /// 距离开始录制的毫秒数
+    pub elapsed_ms: u64,
+    /// 录制时的 Unix 时间戳（毫秒）
+    pub time: i64,
+    #[serde(flatten)]
+    pub entry: RecordEntry,
+}
+
+/// 录制的内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordEntry {
+    /// 驱动推送的事件
+    Event { event: Value },
+    /// 驱动的退出事件
+    Exit,
+    /// 驱动的事件流出错
+    StreamError { error: String },
+    /// 发出的 API 与它的返回
+    Api {
+        api: SendApi,
+        result: Result<ApiReturn, ApiReturn>,
+    },
+    /// 驱动发送 API 失败
+    ApiError { api: SendApi, error: String },
+}
+
+/// 按行写入录制文件，文件在单独的线程中写入，不阻塞事件与 API
+struct Recorder {
+    start: Instant,
+    tx: Option<std::sync::mpsc::Sender<String>>,
+    writer: Option<std::thread::JoinHandle<()>>,
+}
+
+impl Recorder {
+    fn new(file: File) -> std::io::Result<Self> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let writer = std::thread::Builder::new()
+            .name("kovi-recorder".to_string())
+            .spawn(move || write_lines(BufWriter::new(file), rx))?;
+        Ok(Recorder {
+            start: Instant::now(),
+            tx: Some(tx),
+            writer: Some(writer),
+        })
+    }
+
+    fn write(&self, entry: RecordEntry) {
+        let record = Record {
+            elapsed_ms: self.start.elapsed().as_millis() as u64,
+            time: chrono::Utc::now().timestamp_millis(),
+            entry,
+        };
+        let line = match serde_json::to_string(&record) {
+            Ok(v) => v,
+            Err(e) => {
+                log::error!("Failed to serialize record: {e}");
+                return;
+            }
+        };
+
+        if let Some(tx) = &self.tx
+            && tx.send(line).is_err()
+        {
+            log::error!("Failed to write record: the writer thread has stopped");
+        }
+    }
+}
+
+impl Drop for Recorder {
+    /// 等待已经录制的内容全部写入文件
+    fn drop(&mut self) {
+        drop(self.tx.take());
+        if let Some(writer) = self.writer.take()
+            && writer.join().is_err()
+        {
+            log::error!("The recorder writer thread panicked");
+        }
+    }
+}
+
+/// 写入收到的每一行，每次写完已经收到的行后 flush
+fn write_lines(mut file: BufWriter<File>, rx: std::sync::mpsc::Receiver<String>) {
+    while let Ok(line) = rx.recv() {
+        let result = std::iter::once(line)
+            .chain(rx.try_iter())
+            .try_for_each(|line| writeln!(file, "{line}"))
+            .and_then(|_| file.flush());
+        if let Err(e) = result {
+            log::error!("Failed to write record: {e}");
+        }
+    }
+}
+
+/// 录制经过内部驱动的事件与 API，见 [模块文档](self)
+pub struct RecordingDriver<D> {
+    inner: D,
+    recorder: Arc<Recorder>,
+}
+
+impl<D: Driver> RecordingDriver<D> {
+    /// 录制到 `path`，文件已经存在时会被覆盖
+    pub fn new(inner: D, path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(RecordingDriver {
+            inner,
+            recorder: Arc::new(Recorder::new(file)?),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl<D: Driver> Driver for RecordingDriver<D> {
+    async fn event_channel(
+        &self,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<DriverEvent, AnyError>> + Send>>, AnyError> {
+        let recorder = self.recorder.clone();
+        let stream = self.inner.event_channel().await?.inspect(move |event| {
+            let entry = match event {
+                Ok(DriverEvent::Normal(event)) => RecordEntry::Event {
+                    event: event.clone(),
+                },
+                Ok(DriverEvent::Exit) => RecordEntry::Exit,
+                Err(e) => RecordEntry::StreamError {
+                    error: e.to_string(),
+                },
+            };
+            recorder.write(entry);
+        });
+        Ok(Box::pin(stream))
+    }
+
+    fn api_handler(&self, value: SendApi) -> ApiHandlerResult {
+        let recorder = self.recorder.clone();
+        let handle = self.inner.api_handler(value.clone());
+        Box::pin(async move {
+            let result = handle.await;
+            let entry = match &result {
+                Ok(result) => RecordEntry::Api {
+                    api: value,
+                    result: result.clone(),
+                },
+                Err(e) => RecordEntry::ApiError {
+                    api: value,
+                    error: e.to_string(),
+                },
+            };
+            recorder.write(entry);
+            result
+        })
+    }
+
+    fn message_event_register(&self) -> MessageEventRegister {
+        self.inner.message_event_register()
+    }
+
+    fn private_message_api(&self, user_id: &ID, text: &str) -> Option<SendApi> {
+        self.inner.private_message_api(user_id, text)
+    }
+}
+
+/// 录制的 API 参数与返回
+type RecordedReturn = (Value, Result<ApiReturn, ApiReturn>);
+
+/// 回放事件的速度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReplayPace {
+    /// 按录制时的间隔推送事件
+    #[default]
+    Original,
+    /// 尽快推送所有事件
+    Fast,
+}
+
+/// 回放录制的文件，见 [模块文档](self)
+pub struct ReplayDriver {
+    /// 按录制顺序的事件与退出
+    events: Mutex<Option<Vec<(u64, DriverEvent)>>>,
+    /// 以 action 为索引，按录制顺序的返回
+    responses: Mutex<ahash::HashMap<String, VecDeque<RecordedReturn>>>,
+    register: MessageEventRegister,
+    pace: ReplayPace,
+    linger: Duration,
+}
+
+impl ReplayDriver {
+    /// 读取 `path` 的录制，`register` 为录制时驱动的消息事件
+    pub fn open(
+        path: impl AsRef<Path>,
+        register: MessageEventRegister,
+    ) -> Result<Self, ReplayError> {
+        let file = File::open(path)?;
+        let mut events = Vec::new();
+        let mut responses: ahash::HashMap<String, VecDeque<_>> = Default::default();
+
+        for (i, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: Record =
+                serde_json::from_str(&line).map_err(|e| ReplayError::InvalidRecord {
+                    line: i + 1,
+                    error: e.to_string(),
+                })?;
+
+            match record.entry {
+                RecordEntry::Event { event } => {
+                    events.push((record.elapsed_ms, DriverEvent::Normal(event)))
+                }
+                RecordEntry::Exit => events.push((record.elapsed_ms, DriverEvent::Exit)),
+                RecordEntry::Api { api, result } => responses
+                    .entry(api.action)
+                    .or_default()
+                    .push_back((api.params, result)),
+                // 出错的事件与 API 不回放
+                RecordEntry::StreamError { .. } | RecordEntry::ApiError { .. } => {}
+            }
+        }
+
+        Ok(ReplayDriver {
+            events: Mutex::new(Some(events)),
+            responses: Mutex::new(responses),
+            register,
+            pace: ReplayPace::default(),
+            linger: Duration::from_secs(1),
+        })
+    }
+
+    /// 设置回放的速度，默认为 [`ReplayPace::Original`]
+    pub fn set_pace(mut self, pace: ReplayPace) -> Self {
+        self.pace = pace;
+        self
+    }
+
+    /// 推送完所有事件后，等待插件处理完再结束事件流的时间，默认为 1 秒
+    pub fn set_linger(mut self, linger: Duration) -> Self {
+        self.linger = linger;
+        self
+    }
+
+    /// 优先使用参数相同的返回，其次使用同一 action 中最早的返回
+    fn response(&self, api: &SendApi) -> Option<Result<ApiReturn, ApiReturn>> {
+        let mut responses = self.responses.lock();
+        let queue = responses.get_mut(&api.action)?;
+        let index = queue
+            .iter()
+            .position(|(params, _)| *params == api.params)
+            .unwrap_or(0);
+        // 只剩一个时保留，之后同样的调用都使用它
+        if queue.len() == 1 {
+            return queue.front().map(|(_, result)| result.clone());
+        }
+        queue.remove(index).map(|(_, result)| result)
+    }
+}
+
+#[async_trait::async_trait]
+impl Driver for ReplayDriver {
+    async fn event_channel(
+        &self,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<DriverEvent, AnyError>> + Send>>, AnyError> {
+        let events = self
+            .events
+            .lock()
+            .take()
+            .ok_or("ReplayDriver can only be replayed once")?;
+        let pace = self.pace;
+        let linger = self.linger;
+
+        let stream = replay_stream(events, pace, linger);
+        Ok(Box::pin(stream))
+    }
+
+    fn api_handler(&self, value: SendApi) -> ApiHandlerResult {
+        let result = self.response(&value).unwrap_or_else(|| {
+            log::warn!("Api '{}' was not recorded", value.action);
+            Err(ApiReturn {
+                status: "failed".to_string(),
+                retcode: NOT_RECORDED_RETCODE,
+                message: Some(format!("Api '{}' was not recorded", value.action)),
+                data: Value::Null,
+            })
+        });
+        Box::pin(async move { Ok(result) })
+    }
+
+    fn message_event_register(&self) -> MessageEventRegister {
+        self.register.clone()
+    }
+}
+
+/// 按 `pace` 推送事件，推送完后等待 `linger` 再结束
+fn replay_stream(
+    events: Vec<(u64, DriverEvent)>,
+    pace: ReplayPace,
+    linger: Duration,
+) -> impl Stream<Item = Result<DriverEvent, AnyError>> + Send {
+    // 从第一个事件开始计时
+    let first = events
+        .first()
+        .map(|(elapsed_ms, _)| *elapsed_ms)
+        .unwrap_or(0);
+    let start = Instant::now();
+    let events = futures_util::stream::iter(events).then(move |(elapsed_ms, event)| async move {
+        if pace == ReplayPace::Original {
+            let at = start + Duration::from_millis(elapsed_ms.saturating_sub(first));
+            tokio::time::sleep_until(at.into()).await;
+        }
+        Ok(event)
+    });
+    let linger = futures_util::stream::once(tokio::time::sleep(linger))
+        .filter_map(|_| async { None::<Result<DriverEvent, AnyError>> });
+    events.chain(linger)
+}
@@ -7,6 +7,9 @@ pub enum BotError {
     PluginNotFound(String),
     #[error("Bot's Weak reference has expired")]
     RefExpired,
+    /// 没有寻找到账号
+    #[error("Account not found: {0}")]
+    AccountNotFound(String),
 }
 
 #[derive(Error, Debug)]
@@ -20,6 +23,21 @@ pub enum BotBuildError {
     /// 无法读取TOML文件
     #[error("Failed to read TOML file: {0}")]
     FileReadError(String),
+    /// 缺少配置，且不能询问用户
+    #[error("Missing config: {0}")]
+    MissingConfig(String),
+    /// 环境变量或命令行参数的值无效
+    #[error("Invalid value `{value}` for {name}, expected {expected}")]
+    InvalidValue {
+        name: String,
+        value: String,
+        expected: &'static str,
+    },
+    /// 多个驱动读取同一部分配置，且其中有项被覆盖，覆盖无法区分账号
+    #[error(
+        "Environment variables and arguments for [{0}] would apply to every account, configure the other accounts in code instead"
+    )]
+    SharedOverrides(String),
 }
 
 #[derive(Error, Debug)]
@@ -37,3 +55,112 @@ pub enum MessageError {
     // #[error("Error, and no one knows why something went wrong")]
     // UnknownError(),
 }
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CommandError {
+    /// 缺少必选参数
+    #[error("Missing argument <{0}>")]
+    MissingArgument(String),
+    /// 参数不是整数
+    #[error("Argument <{name}> must be an integer, got `{value}`")]
+    InvalidInteger { name: String, value: String },
+    /// 参数不是用户 ID
+    #[error("Argument <{0}> must be a user ID or a mention")]
+    InvalidId(String),
+    /// 引号没有闭合
+    #[error("Unclosed quote")]
+    UnclosedQuote,
+    /// 多余的参数
+    #[error("Too many arguments: {0}")]
+    TooManyArguments(String),
+}
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionError {
+    /// 超时前没有等到下一条消息
+    #[error("Timed out waiting for the next message")]
+    Timeout,
+    /// 插件被关闭，等待被取消
+    #[error("The plugin was disabled while waiting for the next message")]
+    Cancelled,
+}
+
+#[derive(Error, Debug)]
+pub enum ReplayError {
+    /// 无法读取录制文件
+    #[error("Failed to read recording: {0}")]
+    Io(#[from] std::io::Error),
+    /// 录制文件中有无法解析的行
+    #[error("Invalid record at line {line}: {error}")]
+    InvalidRecord { line: usize, error: String },
+}
+
+#[derive(Error, Debug)]
+pub enum StorageError {
+    /// 无法读写存储文件
+    #[error("Storage io error: {0}")]
+    Io(#[from] std::io::Error),
+    /// 存储文件无法解析
+    #[error("Storage file {path:?} is corrupted: {error}")]
+    Corrupted {
+        path: std::path::PathBuf,
+        error: String,
+    },
+    /// 数据库读写失败
+    #[error("Storage database error: {0}")]
+    Database(String),
+    /// 值无法序列化
+    #[error("Failed to serialize value of '{key}': {error}")]
+    Serialize { key: String, error: String },
+    /// 值与要求的类型不符
+    #[error("Failed to deserialize value of '{key}': {error}")]
+    Deserialize { key: String, error: String },
+}
+
+#[derive(Error, Debug)]
+pub enum StateError {
+    /// 无法读写状态文件
+    #[error("State io error: {0}")]
+    Io(#[from] std::io::Error),
+    /// 状态文件无法解析
+    #[error("State file {path:?} is corrupted: {error}")]
+    Corrupted {
+        path: std::path::PathBuf,
+        error: String,
+    },
+    /// 状态无法序列化
+    #[error("Failed to serialize state: {0}")]
+    Serialize(String),
+    /// 文件的版本比插件的更新
+    #[error("State version {found} is newer than the plugin's version {current}")]
+    FutureVersion { found: u32, current: u32 },
+    /// 缺少从此版本开始的迁移
+    #[error("No migration registered from version {0}")]
+    MissingMigration(u32),
+    /// 迁移失败
+    #[error("Migration from version {from} failed: {error}")]
+    Migration { from: u32, error: String },
+}
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    /// 无法读写配置文件
+    #[error("Config io error: {0}")]
+    Io(#[from] std::io::Error),
+    /// 配置文件不是有效的 TOML
+    #[error("Failed to parse config file {path:?}:\n{error}")]
+    Parse {
+        path: std::path::PathBuf,
+        error: String,
+    },
+    /// 插件的配置无效
+    #[error("Invalid config of plugin {plugin} in {path:?}:\n{error}")]
+    Invalid {
+        path: std::path::PathBuf,
+        plugin: String,
+        error: String,
+    },
+    /// 默认配置无法写为 TOML 表
+    #[error("Failed to serialize default config of plugin {plugin}: {error}")]
+    Serialize { plugin: String, error: String },
+}
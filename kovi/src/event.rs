@@ -1,11 +1,18 @@
 pub mod id;
+mod parse_cache;
+
+pub(crate) use parse_cache::ParseCache;
+pub use parse_cache::parse_cached;
 
 use crate::bot::BotInformation;
+use crate::bot::failure::PluginFailureEvent;
 use crate::event::id::ref_id::RefID;
 use crate::message::Message;
 use crate::types::{ApiAndOptOneshot, ApiAndRuturn};
 use serde_json::Value;
 use std::any::Any;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 /// 满足此 trait 即可在Kovi运行时中监听并处理
 ///
@@ -24,7 +31,7 @@ pub trait Event: Any + Send + Sync {
     ///
     /// 如果认为此 json 不符合事件要求，请返回 `None`。
     ///
-    /// 在一个消息周期内，Kovi 运行时会缓存此事件。
+    /// 在一个消息周期内，Kovi 运行时会缓存此事件。从其他事件派生的事件可以用 [`parse_cached()`] 取得已经解析好的事件。
     ///
     /// 不需要的信息用 `_` 忽略，例如：
     ///
@@ -80,10 +87,12 @@ pub trait Event: Any + Send + Sync {
 /// 事件
 #[derive(Debug, Clone)]
 pub enum InternalEvent {
-    /// 来自OneBot的事件
-    DriverEvent(Value),
+    /// 来自OneBot的事件，所有事件类型共享同一份 json
+    DriverEvent(Arc<Value>),
     /// 来自Kovi发送给服务端并包含了返回结果
     DriverApiEvent(ApiAndRuturn),
+    /// 由Kovi发出的插件失败事件
+    PluginFailure(Arc<PluginFailureEvent>),
 }
 
 pub trait MessageEventTrait: Event {
@@ -131,3 +140,23 @@ pub trait MessageEventUtil {
     /// 借用 event 的 text，只是做了一下self.text.as_deref()的包装
     fn borrow_text(&self) -> Option<&str>;
 }
+
+tokio::task_local! {
+    pub(crate) static PROPAGATION: Arc<AtomicBool>;
+}
+
+/// 在监听闭包中调用，此事件不再交给优先级更低的监听（包括其他插件的监听）。
+///
+/// 优先级相同的监听并发执行，不受影响。在监听闭包之外调用没有效果。
+///
+/// # Examples
+/// ```ignore
+/// PluginBuilder::on_with_priority(100, |event: Arc<MsgEvent>| async move {
+///     if is_spam(&event) {
+///         kovi::event::stop_propagation();
+///     }
+/// });
+/// ```
+pub fn stop_propagation() {
+    let _ = PROPAGATION.try_with(|stopped| stopped.store(true, Ordering::Relaxed));
+}
@@ -0,0 +1,87 @@
+use crate::bot::BotInformation;
+use crate::bot::account::{ACCOUNT, Account};
+use crate::bot::dispatch_table::TypeMap;
+use crate::event::{Event, InternalEvent};
+use crate::types::ApiAndOptOneshot;
+use parking_lot::Mutex;
+use std::any::{Any, TypeId};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+tokio::task_local! {
+    static PARSE_CACHE: Arc<ParseCache>;
+}
+
+type Parsed = Option<Arc<dyn Any + Send + Sync>>;
+
+/// 一个事件已经解析出的各种事件类型，解析失败的结果也会记录
+#[derive(Default)]
+pub(crate) struct ParseCache {
+    parsed: Mutex<TypeMap<Parsed>>,
+    /// 收到此事件的账号
+    account: Option<Account>,
+}
+
+impl ParseCache {
+    pub(crate) fn new(account: Option<Account>) -> Self {
+        ParseCache {
+            parsed: Default::default(),
+            account,
+        }
+    }
+
+    /// 在 `f` 中通过 [`parse_cached()`] 解析的事件使用此缓存，[`Account::current()`] 为收到事件的账号
+    pub(crate) fn enter<R>(self: &Arc<Self>, f: impl FnOnce() -> R) -> R {
+        match &self.account {
+            Some(account) => {
+                ACCOUNT.sync_scope(account.clone(), || PARSE_CACHE.sync_scope(self.clone(), f))
+            }
+            None => PARSE_CACHE.sync_scope(self.clone(), f),
+        }
+    }
+
+    /// 事件被修改后需要清空
+    pub(crate) fn clear(&self) {
+        self.parsed.lock().clear();
+    }
+}
+
+/// 解析事件，在 Kovi 分发一个事件的过程中，每种事件类型只会解析一次
+///
+/// 在 [`Event::de`] 中可以借此取得已经解析好的基础事件，再由基础事件构造派生事件，
+/// 不必再次从 json 解析。在分发事件以外的地方调用时等同于 `T::de()`。
+///
+/// # Examples
+/// ```ignore
+/// impl Event for GroupMsgEvent {
+///     fn de(
+///         event: &InternalEvent,
+///         bot_info: &BotInformation,
+///         api_tx: &mpsc::Sender<ApiAndOptOneshot>,
+///     ) -> Option<Self> {
+///         let msg_event = parse_cached::<MsgEvent>(event, bot_info, api_tx)?;
+///         GroupMsgEvent::try_from((*msg_event).clone()).ok()
+///     }
+/// }
+/// ```
+pub fn parse_cached<T: Event>(
+    event: &InternalEvent,
+    bot_info: &BotInformation,
+    api_tx: &mpsc::Sender<ApiAndOptOneshot>,
+) -> Option<Arc<T>> {
+    let Ok(cache) = PARSE_CACHE.try_with(Arc::clone) else {
+        return T::de(event, bot_info, api_tx).map(Arc::new);
+    };
+
+    let downcast = |parsed: &Parsed| parsed.clone()?.downcast::<T>().ok();
+
+    if let Some(parsed) = cache.parsed.lock().get(&TypeId::of::<T>()) {
+        return downcast(parsed);
+    }
+
+    // 解析时不持有锁，派生事件会在解析中再次使用缓存
+    let parsed: Parsed = T::de(event, bot_info, api_tx).map(|v| Arc::new(v) as _);
+
+    let mut cached = cache.parsed.lock();
+    downcast(cached.entry(TypeId::of::<T>()).or_insert(parsed))
+}
@@ -9,6 +9,8 @@
 
 /// Everything about bots is inside
 pub mod bot;
+/// 命令解析与分发
+pub mod command;
 pub mod config;
 /// 连接服务端的驱动
 pub mod driver;
@@ -20,8 +22,15 @@ pub mod logger;
 pub mod message;
 /// 关于插件的一切
 pub mod plugin;
+/// 多步对话，等待同一会话中的下一条消息
+pub mod session;
+/// 插件的键值存储
+pub mod storage;
 /// task 提供 kovi 运行时的多线程处理
 pub mod task;
+/// 插件测试工具
+#[cfg(feature = "testing")]
+pub mod testing;
 /// 这里包含一些集成类型
 pub mod types;
 /// 提供一些方便的插件开发函数
@@ -31,7 +40,9 @@ pub use bot::run::ExitEvent;
 pub use bot::runtimebot::RuntimeBot;
 pub use bot::{ApiReturn, Bot};
 pub use config::kovi_conf::load_local_conf;
-pub use kovi_macros::plugin;
+#[cfg(feature = "testing")]
+pub use kovi_macros::test;
+pub use kovi_macros::{command, plugin};
 pub use message::{Message, Segment};
 pub use plugin::plugin_builder::PluginBuilder;
 pub use task::spawn;
@@ -1,7 +1,12 @@
+pub mod event_stream;
+pub mod listener;
 pub mod plugin_builder;
 pub mod plugin_set;
+pub mod state;
 
 use crate::PluginBuilder;
+use crate::RuntimeBot;
+use crate::bot::dispatch::DispatchMode;
 #[cfg(feature = "plugin-access-control")]
 use crate::bot::runtimebot::kovi_api::AccessList;
 #[cfg(feature = "plugin-access-control")]
@@ -16,6 +21,7 @@ use tokio::task::JoinHandle;
 #[cfg(feature = "plugin-access-control")]
 pub use crate::bot::runtimebot::kovi_api::AccessControlMode;
 
+use crate::session::SESSIONS;
 use crate::task::TASK_MANAGER;
 
 tokio::task_local! {
@@ -26,6 +32,11 @@ tokio::task_local! {
     pub(crate) static PLUGIN_NAME: Arc<String>;
 }
 
+tokio::task_local! {
+    /// 插件的监听、定时任务与 `kovi::spawn()` 的任务中可用，在其中可以注册监听
+    pub(crate) static PLUGIN_RUNTIME_BOT: Arc<RuntimeBot>;
+}
+
 #[derive(Clone)]
 pub struct Plugin {
     pub(crate) enable_on_startup: bool,
@@ -35,6 +46,8 @@ pub struct Plugin {
     pub version: String,
     pub(crate) main: Arc<KoviAsyncFn>,
     pub(crate) listen: Listen,
+    /// 为 `None` 时使用 Bot 的分发方式
+    pub(crate) dispatch_mode: Option<DispatchMode>,
 
     #[cfg(feature = "plugin-access-control")]
     pub(crate) access_control: bool,
@@ -56,6 +69,7 @@ impl Plugin {
             version: version.into(),
             main,
             listen: Listen::default(),
+            dispatch_mode: None,
             #[cfg(feature = "plugin-access-control")]
             access_control: false,
             #[cfg(feature = "plugin-access-control")]
@@ -68,6 +82,12 @@ impl Plugin {
     /// 运行单个插件的main()
     pub(crate) fn run(&self, plugin_builder: PluginBuilder) {
         let plugin_name = plugin_builder.runtime_bot.plugin_name.clone();
+        let bot = plugin_builder.bot.clone();
+        // main 中注册的监听在 main 返回后一起加入分发表
+        let plugin_builder = PluginBuilder {
+            in_main: true,
+            ..plugin_builder
+        };
 
         let mut enabled = self.enabled.subscribe();
         let main = self.main.clone();
@@ -77,7 +97,7 @@ impl Plugin {
                 _ = PLUGIN_NAME.scope(
                         Arc::new(plugin_name),
                         PLUGIN_BUILDER.scope(plugin_builder, main()),
-                ) =>{}
+                ) => bot.read().refresh_dirty_dispatch_table(),
                 _ = async {
                         loop {
                             enabled.changed().await.expect("Failed to change enabled status");
@@ -90,6 +110,11 @@ impl Plugin {
         });
     }
 
+    /// 设置此插件的事件分发方式，优先于 Bot 的设置
+    pub fn set_dispatch_mode(&mut self, mode: DispatchMode) {
+        self.dispatch_mode = Some(mode);
+    }
+
     pub(crate) fn shutdown(&mut self) -> JoinHandle<()> {
         log::debug!("Plugin '{}' is dropping.", self.name,);
 
@@ -98,7 +123,7 @@ impl Plugin {
         let mut task_vec = Vec::new();
 
         for listen in &self.listen.drop {
-            let listen_clone = listen.clone();
+            let listen_clone = listen.handler.clone();
             let plugin_name_ = plugin_name_.clone();
             let task = tokio::spawn(async move {
                 PLUGIN_NAME.scope(plugin_name_, listen_clone()).await;
@@ -107,6 +132,7 @@ impl Plugin {
         }
 
         TASK_MANAGER.disable_plugin(&self.name);
+        SESSIONS.disable_plugin(&self.name);
 
         self.enabled.send_modify(|v| {
             *v = false;
@@ -0,0 +1,70 @@
+use crate::plugin::listener::ListenerHandle;
+use futures_util::Stream;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::{mpsc, watch};
+
+/// 事件流中最多排队的事件数
+pub const EVENT_STREAM_CAPACITY: usize = 256;
+
+/// 插件订阅的事件流，由 [`PluginBuilder::subscribe()`](crate::PluginBuilder::subscribe) 创建
+///
+/// 插件关闭时结束，被丢弃时注销监听。可以使用 `futures_util::StreamExt` 等组合子处理。
+pub struct EventStream<T> {
+    inner: Pin<Box<dyn Stream<Item = Arc<T>> + Send>>,
+    handle: ListenerHandle,
+}
+
+impl<T: Send + Sync + 'static> EventStream<T> {
+    pub(crate) fn new(
+        event_rx: mpsc::Receiver<Arc<T>>,
+        enabled: watch::Receiver<bool>,
+        handle: ListenerHandle,
+    ) -> Self {
+        let inner = futures_util::stream::unfold(
+            (event_rx, enabled),
+            |(mut event_rx, mut enabled)| async move {
+                tokio::select! {
+                    // 已经收到的事件先交出去
+                    biased;
+                    event = event_rx.recv() => event.map(|event| (event, (event_rx, enabled))),
+                    _ = wait_disabled(&mut enabled) => None,
+                }
+            },
+        );
+        EventStream {
+            inner: Box::pin(inner),
+            handle,
+        }
+    }
+}
+
+impl<T> EventStream<T> {
+    /// 流所用的监听，注销后流会在取完已排队的事件后结束
+    pub fn listener_handle(&self) -> &ListenerHandle {
+        &self.handle
+    }
+}
+
+async fn wait_disabled(enabled: &mut watch::Receiver<bool>) {
+    while *enabled.borrow_and_update() {
+        if enabled.changed().await.is_err() {
+            return;
+        }
+    }
+}
+
+impl<T> Drop for EventStream<T> {
+    fn drop(&mut self) {
+        self.handle.unregister();
+    }
+}
+
+impl<T> Stream for EventStream<T> {
+    type Item = Arc<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
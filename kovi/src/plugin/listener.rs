@@ -0,0 +1,114 @@
+use crate::bot::Bot;
+use parking_lot::RwLock;
+use std::sync::Weak;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::task::AbortHandle;
+
+/// 生成监听的 ID，在所有 Bot 中唯一
+pub(crate) fn next_listener_id() -> u64 {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// 已注册的监听、定时任务或结束处理，可以用来注销
+///
+/// 丢弃句柄不会注销。插件关闭时所有监听都会被注销，之后再调用 [`unregister()`](Self::unregister) 不会有任何效果。
+///
+/// # Examples
+/// ```ignore
+/// let handle = PluginBuilder::on(|event: Arc<MsgEvent>| async move { ... });
+///
+/// // 之后不再需要时
+/// handle.unregister();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ListenerHandle {
+    bot: Weak<RwLock<Bot>>,
+    plugin: String,
+    kind: ListenerKind,
+}
+
+#[derive(Debug, Clone)]
+enum ListenerKind {
+    Listen(u64),
+    Drop(u64),
+    Cron(AbortHandle),
+}
+
+impl ListenerHandle {
+    pub(crate) fn listen(bot: Weak<RwLock<Bot>>, plugin: String, id: u64) -> Self {
+        ListenerHandle {
+            bot,
+            plugin,
+            kind: ListenerKind::Listen(id),
+        }
+    }
+
+    pub(crate) fn drop(bot: Weak<RwLock<Bot>>, plugin: String, id: u64) -> Self {
+        ListenerHandle {
+            bot,
+            plugin,
+            kind: ListenerKind::Drop(id),
+        }
+    }
+
+    pub(crate) fn cron(bot: Weak<RwLock<Bot>>, plugin: String, abort: AbortHandle) -> Self {
+        ListenerHandle {
+            bot,
+            plugin,
+            kind: ListenerKind::Cron(abort),
+        }
+    }
+
+    /// 所属的插件
+    pub fn plugin_name(&self) -> &str {
+        &self.plugin
+    }
+
+    /// 注销，之后不再收到事件。已经注销时不会有任何效果
+    pub fn unregister(&self) {
+        let id = match &self.kind {
+            ListenerKind::Cron(abort) => {
+                abort.abort();
+                return;
+            }
+            ListenerKind::Listen(id) | ListenerKind::Drop(id) => *id,
+        };
+        let Some(bot) = self.bot.upgrade() else {
+            return;
+        };
+        let mut bot = bot.write();
+        let Some(plugin) = bot.plugins.get_mut(&self.plugin) else {
+            return;
+        };
+        match self.kind {
+            ListenerKind::Listen(_) => {
+                let len = plugin.listen.list.len();
+                plugin.listen.list.retain(|listen| listen.id != id);
+                if plugin.listen.list.len() != len {
+                    bot.refresh_dispatch_table();
+                }
+            }
+            _ => plugin.listen.drop.retain(|drop| drop.id != id),
+        }
+    }
+
+    /// 是否仍然注册着
+    pub fn is_registered(&self) -> bool {
+        let id = match &self.kind {
+            ListenerKind::Cron(abort) => return !abort.is_finished(),
+            ListenerKind::Listen(id) | ListenerKind::Drop(id) => *id,
+        };
+        let Some(bot) = self.bot.upgrade() else {
+            return false;
+        };
+        let bot = bot.read();
+        let Some(plugin) = bot.plugins.get(&self.plugin) else {
+            return false;
+        };
+        match self.kind {
+            ListenerKind::Listen(_) => plugin.listen.list.iter().any(|v| v.id == id),
+            _ => plugin.listen.drop.iter().any(|v| v.id == id),
+        }
+    }
+}
@@ -1,16 +1,25 @@
 use crate::bot::Bot;
+use crate::bot::dispatch::DispatchMode;
 use crate::bot::runtimebot::RuntimeBot;
-use crate::event::Event;
-use crate::plugin::{PLUGIN_BUILDER, PLUGIN_NAME};
-use crate::types::{ApiAndOptOneshot, ArcTypeDeFn, NoArgsFn, PinFut};
+use crate::command::{CommandDef, CommandSet};
+use crate::config::paths::KoviPaths;
+use crate::config::plugin_conf;
+use crate::error::ConfigError;
+use crate::event::{Event, MessageEventTrait, RepliableEvent, parse_cached};
+use crate::plugin::event_stream::{EVENT_STREAM_CAPACITY, EventStream};
+use crate::plugin::listener::{ListenerHandle, next_listener_id};
+use crate::plugin::{PLUGIN_BUILDER, PLUGIN_NAME, PLUGIN_RUNTIME_BOT};
+use crate::types::{ApiAndOptOneshot, ArcTypeDeFn, ListenFut, NoArgsFn};
 use croner::Cron;
 use croner::errors::CronError;
 use log::error;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
 use std::any::Any;
 use std::future::Future;
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 
 macro_rules! assert_right_place {
     ($expr:expr) => {
@@ -38,7 +47,7 @@ impl<T: ?Sized + Any> DowncastArc for T {
 #[derive(Clone, Default)]
 pub(crate) struct Listen {
     pub(crate) list: Vec<Arc<ListenInner>>,
-    pub(crate) drop: Vec<NoArgsFn>,
+    pub(crate) drop: Vec<DropListen>,
 }
 impl Listen {
     pub(crate) fn clear(&mut self) {
@@ -49,27 +58,67 @@ impl Listen {
     }
 }
 
+#[derive(Clone)]
+pub(crate) struct DropListen {
+    pub(crate) id: u64,
+    pub(crate) handler: NoArgsFn,
+}
+
 #[derive(Clone)]
 pub(crate) struct ListenInner {
+    /// 见 [`ListenerHandle`]
+    pub(crate) id: u64,
     pub(crate) type_id: std::any::TypeId,
+    /// 数值越大越先执行
+    pub(crate) priority: i32,
     pub(crate) type_de: ArcTypeDeFn,
-    pub(crate) handler: Arc<dyn Fn(Arc<dyn Event>) -> PinFut + Send + Sync>,
+    pub(crate) handler: Arc<dyn Fn(Arc<dyn Event>) -> ListenFut + Send + Sync>,
 }
 
 impl Listen {
-    pub(crate) fn on<T, F, Fut>(&mut self, handler: F)
+    pub(crate) fn on<T, F, Fut>(&mut self, priority: i32, handler: F) -> u64
     where
         T: Event,
         F: Fn(Arc<T>) -> Fut + Send + Sync + 'static,
         Fut: Future + Send,
         Fut::Output: Send,
+    {
+        self.try_on(priority, move |event: Arc<T>| {
+            let fut = handler(event);
+            async move {
+                fut.await;
+                Ok::<_, std::convert::Infallible>(())
+            }
+        })
+    }
+
+    pub(crate) fn try_on<T, F, Fut, R, E>(&mut self, priority: i32, handler: F) -> u64
+    where
+        T: Event,
+        F: Fn(Arc<T>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R, E>> + Send,
+        E: std::fmt::Display + 'static,
+    {
+        let id = next_listener_id();
+        self.try_on_with_id(id, priority, handler);
+        id
+    }
+
+    pub(crate) fn try_on_with_id<T, F, Fut, R, E>(&mut self, id: u64, priority: i32, handler: F)
+    where
+        T: Event,
+        F: Fn(Arc<T>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R, E>> + Send,
+        E: std::fmt::Display + 'static,
     {
         let handler = Arc::new(handler);
 
         self.list.push(Arc::new(ListenInner {
+            id,
             type_id: std::any::TypeId::of::<T>(),
+            priority,
             type_de: Arc::new(|value, bot_info, sender| {
-                Some(Arc::new(T::de(value, bot_info, sender)?))
+                Some(parse_cached::<T>(value, bot_info, sender)?)
             }),
             handler: Arc::new(move |evt: Arc<dyn Event>| {
                 let downcasted = evt.downcast_arc::<T>();
@@ -78,7 +127,10 @@ impl Listen {
                     Ok(downcasted) => Box::pin({
                         let handler = handler.clone();
                         async move {
-                            handler(downcasted).await;
+                            match handler(downcasted).await {
+                                Ok(_) => Ok(()),
+                                Err(e) => Err(e.to_string()),
+                            }
                         }
                     }),
                     Err(_) => panic!("Type downcasted error!"),
@@ -92,6 +144,8 @@ impl Listen {
 pub struct PluginBuilder {
     pub(crate) bot: Arc<RwLock<Bot>>,
     pub(crate) runtime_bot: Arc<RuntimeBot>,
+    /// 在插件的 `main` 中，注册监听时只标记分发表，`main` 返回后统一重新生成
+    pub(crate) in_main: bool,
 }
 
 impl PluginBuilder {
@@ -108,15 +162,61 @@ impl PluginBuilder {
             api_tx,
         });
 
-        PluginBuilder { bot, runtime_bot }
+        PluginBuilder {
+            bot,
+            runtime_bot,
+            in_main: false,
+        }
     }
 
     pub fn get_runtime_bot() -> Arc<RuntimeBot> {
-        assert_right_place!(PLUGIN_BUILDER.try_with(|p| p.runtime_bot.clone()))
+        Self::current_runtime_bot()
     }
 
     pub fn get_plugin_name() -> String {
-        assert_right_place!(PLUGIN_BUILDER.try_with(|p| p.runtime_bot.plugin_name.to_string()))
+        Self::current_runtime_bot().plugin_name.clone()
+    }
+
+    /// 插件的 `main`、监听、定时任务以及其中 `kovi::spawn()` 的任务都属于插件
+    fn current_runtime_bot() -> Arc<RuntimeBot> {
+        match PLUGIN_BUILDER.try_with(|p| p.runtime_bot.clone()) {
+            Ok(v) => v,
+            Err(_) => assert_right_place!(PLUGIN_RUNTIME_BOT.try_with(|v| v.clone())),
+        }
+    }
+
+    /// 当前插件的 `PluginBuilder`，在 `main` 之外也可以注册监听
+    pub(crate) fn current() -> PluginBuilder {
+        if let Ok(p) = PLUGIN_BUILDER.try_with(|p| p.clone()) {
+            return p;
+        }
+        let runtime_bot = Self::current_runtime_bot();
+        let bot = runtime_bot
+            .bot
+            .upgrade()
+            .expect("Bot's Weak reference has expired");
+        PluginBuilder {
+            bot,
+            runtime_bot,
+            in_main: false,
+        }
+    }
+
+    /// 监听或插件设置变化后更新分发表
+    fn refresh_dispatch_table(&self, bot: &Bot) {
+        if self.in_main {
+            bot.mark_dispatch_table_dirty();
+        } else {
+            bot.refresh_dispatch_table();
+        }
+    }
+
+    fn listener_handle(&self, id: u64) -> ListenerHandle {
+        ListenerHandle::listen(
+            Arc::downgrade(&self.bot),
+            self.runtime_bot.plugin_name.clone(),
+            id,
+        )
     }
 
     // pub fn get_plugin_host() -> (Host, u16) {
@@ -127,53 +227,310 @@ impl PluginBuilder {
 }
 
 impl PluginBuilder {
-    pub fn on<T: Event, Fut>(handler: impl Fn(Arc<T>) -> Fut + Send + Sync + 'static)
+    /// 注册事件监听，优先级为 0。
+    ///
+    /// 返回的 [`ListenerHandle`] 可以注销此监听。
+    pub fn on<T: Event, Fut>(
+        handler: impl Fn(Arc<T>) -> Fut + Send + Sync + 'static,
+    ) -> ListenerHandle
+    where
+        Fut: Future + Send,
+        Fut::Output: Send,
+    {
+        Self::on_with_priority(0, handler)
+    }
+
+    /// 以指定优先级注册事件监听。
+    ///
+    /// 一个事件的所有监听（包括所有插件、所有事件类型）按优先级从高到低分组执行，数值越大越先执行，
+    /// 同一优先级的监听并发执行。一组监听全部结束后才会执行下一组，其中有监听调用了
+    /// [`stop_propagation()`](crate::event::stop_propagation) 时，之后的监听都不再执行。
+    ///
+    /// 高优先级的监听会推迟低优先级的监听，应尽快返回，耗时的处理请交给 `kovi::spawn()`。
+    pub fn on_with_priority<T: Event, Fut>(
+        priority: i32,
+        handler: impl Fn(Arc<T>) -> Fut + Send + Sync + 'static,
+    ) -> ListenerHandle
     where
         Fut: Future + Send,
         Fut::Output: Send,
     {
-        assert_right_place!(PLUGIN_BUILDER.try_with(|p| {
+        let p = Self::current();
+        let id = {
             let mut bot = p.bot.write();
             let bot_plugin = bot.plugins.get_mut(&p.runtime_bot.plugin_name).expect("");
 
-            bot_plugin.listen.on(handler);
-        }));
+            let id = bot_plugin.listen.on(priority, handler);
+            p.refresh_dispatch_table(&bot);
+            id
+        };
+        p.listener_handle(id)
+    }
+
+    /// 注册会失败的事件监听，优先级为 0。
+    ///
+    /// 监听返回的错误会记录到日志，并按 Bot 的 [`FailurePolicy`](crate::bot::failure::FailurePolicy) 处理。
+    ///
+    /// # Examples
+    /// ```ignore
+    /// PluginBuilder::try_on(|event: Arc<MsgEvent>| async move {
+    ///     let n: i64 = event.borrow_text().unwrap_or_default().parse()?;
+    ///     event.reply(format!("{}", n * 2));
+    ///     Ok::<_, std::num::ParseIntError>(())
+    /// });
+    /// ```
+    pub fn try_on<T: Event, Fut, R, E>(
+        handler: impl Fn(Arc<T>) -> Fut + Send + Sync + 'static,
+    ) -> ListenerHandle
+    where
+        Fut: Future<Output = Result<R, E>> + Send,
+        E: std::fmt::Display + 'static,
+    {
+        Self::try_on_with_priority(0, handler)
+    }
+
+    /// 以指定优先级注册会失败的事件监听，优先级见 [`on_with_priority()`](Self::on_with_priority)。
+    pub fn try_on_with_priority<T: Event, Fut, R, E>(
+        priority: i32,
+        handler: impl Fn(Arc<T>) -> Fut + Send + Sync + 'static,
+    ) -> ListenerHandle
+    where
+        Fut: Future<Output = Result<R, E>> + Send,
+        E: std::fmt::Display + 'static,
+    {
+        let p = Self::current();
+        let id = {
+            let mut bot = p.bot.write();
+            let bot_plugin = bot
+                .plugins
+                .get_mut(&p.runtime_bot.plugin_name)
+                .expect("unreachable");
+
+            let id = bot_plugin.listen.try_on(priority, handler);
+            p.refresh_dispatch_table(&bot);
+            id
+        };
+        p.listener_handle(id)
+    }
+
+    /// 注册只执行一次的事件监听，优先级为 0。
+    ///
+    /// 收到第一个此类型的事件后，监听会注销自己。
+    ///
+    /// # Examples
+    /// ```ignore
+    /// PluginBuilder::on(|event: Arc<MsgEvent>| async move {
+    ///     if event.borrow_text() == Some("/subscribe") {
+    ///         // 只等待下一条消息
+    ///         PluginBuilder::once(|next: Arc<MsgEvent>| async move {
+    ///             next.reply("got it");
+    ///         });
+    ///     }
+    /// });
+    /// ```
+    pub fn once<T: Event, Fut>(
+        handler: impl FnOnce(Arc<T>) -> Fut + Send + 'static,
+    ) -> ListenerHandle
+    where
+        Fut: Future + Send,
+        Fut::Output: Send,
+    {
+        Self::once_with_priority(0, handler)
+    }
+
+    /// 以指定优先级注册只执行一次的事件监听，优先级见 [`on_with_priority()`](Self::on_with_priority)。
+    pub fn once_with_priority<T: Event, Fut>(
+        priority: i32,
+        handler: impl FnOnce(Arc<T>) -> Fut + Send + 'static,
+    ) -> ListenerHandle
+    where
+        Fut: Future + Send,
+        Fut::Output: Send,
+    {
+        let p = Self::current();
+        let id = next_listener_id();
+        let handle = p.listener_handle(id);
+        {
+            let mut bot = p.bot.write();
+            let bot_plugin = bot
+                .plugins
+                .get_mut(&p.runtime_bot.plugin_name)
+                .expect("unreachable");
+
+            // 同时到达的事件只有一个能取得处理函数
+            let handler = Mutex::new(Some(handler));
+            let this = handle.clone();
+            bot_plugin
+                .listen
+                .try_on_with_id(id, priority, move |event: Arc<T>| {
+                    let fut = handler.lock().take().map(|handler| {
+                        this.unregister();
+                        handler(event)
+                    });
+                    async move {
+                        if let Some(fut) = fut {
+                            fut.await;
+                        }
+                        Ok::<_, std::convert::Infallible>(())
+                    }
+                });
+            p.refresh_dispatch_table(&bot);
+        }
+        handle
+    }
+
+    /// 订阅事件，以流的形式取得，插件关闭时流结束，流被丢弃时注销监听。
+    ///
+    /// 与 [`on()`](Self::on) 一样以优先级 0 接收事件，事件在流中排队，直到被取出。
+    /// 流中最多排队 [`EVENT_STREAM_CAPACITY`] 个事件，排满时新的事件会被丢弃并记录警告。
+    ///
+    /// # Examples
+    /// ```ignore
+    /// use kovi::futures_util::StreamExt as _;
+    ///
+    /// // 等待 30 秒内的一条 "yes"
+    /// let mut replies = PluginBuilder::subscribe::<MsgEvent>()
+    ///     .filter(|e| std::future::ready(e.borrow_text() == Some("yes")));
+    /// kovi::spawn(async move {
+    ///     match tokio::time::timeout(Duration::from_secs(30), replies.next()).await {
+    ///         Ok(Some(e)) => e.reply("ok"),
+    ///         _ => log::info!("no reply"),
+    ///     }
+    /// });
+    /// ```
+    pub fn subscribe<T: Event>() -> EventStream<T> {
+        let p = Self::current();
+        let (event_tx, event_rx) = mpsc::channel(EVENT_STREAM_CAPACITY);
+        let (id, enabled) = {
+            let mut bot = p.bot.write();
+            let bot_plugin = bot
+                .plugins
+                .get_mut(&p.runtime_bot.plugin_name)
+                .expect("unreachable");
+
+            let enabled = bot_plugin.enabled.subscribe();
+            let plugin_name = p.runtime_bot.plugin_name.clone();
+            let id = bot_plugin.listen.on(0, move |event: Arc<T>| {
+                if let Err(mpsc::error::TrySendError::Full(_)) = event_tx.try_send(event) {
+                    log::warn!("Event stream of plugin {plugin_name} is full, dropping an event");
+                }
+                async {}
+            });
+            p.refresh_dispatch_table(&bot);
+            (id, enabled)
+        };
+
+        EventStream::new(event_rx, enabled, p.listener_handle(id))
+    }
+
+    /// 读取 [`kovi.conf.toml`](KoviPaths::conf_file) 中此插件的配置 `[plugins.<插件名>]`，没有时写入 `T::default()`
+    ///
+    /// 配置文件修改后，新的配置会发送到返回的 `watch::Receiver`，插件关闭后不再检查。
+    /// 新的配置无效时会记录错误并保留原有的配置。
+    ///
+    /// # error
+    ///
+    /// 配置无效时返回错误，错误信息中带有出错的位置。
+    ///
+    /// # Examples
+    /// ```ignore
+    /// #[derive(Default, Serialize, Deserialize)]
+    /// struct WeatherConf {
+    ///     api_key: String,
+    ///     cities: Vec<String>,
+    /// }
+    ///
+    /// let conf = PluginBuilder::config::<WeatherConf>()?;
+    /// PluginBuilder::on(move |e: Arc<MsgEvent>| {
+    ///     let cities = conf.borrow().cities.join(", ");
+    ///     async move { e.reply(cities) }
+    /// });
+    /// ```
+    pub fn config<T>() -> Result<watch::Receiver<T>, ConfigError>
+    where
+        T: Serialize + DeserializeOwned + Default + Send + Sync + 'static,
+    {
+        Self::config_with(plugin_conf::accept)
+    }
+
+    /// 与 [`config()`](Self::config) 相同，但读取与重新加载时都以 `validate` 检查配置
+    ///
+    /// 读取时没有通过检查则返回 [`ConfigError::Invalid`]，默认配置也需要通过检查，没有通过时写入文件后返回错误。
+    /// 重新加载时没有通过检查则记录错误并保留原有的配置。
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let conf = PluginBuilder::config_with(|conf: &WeatherConf| {
+    ///     if conf.api_key.is_empty() {
+    ///         return Err("api_key is not set");
+    ///     }
+    ///     Ok(())
+    /// })?;
+    /// ```
+    pub fn config_with<T, E, V>(validate: V) -> Result<watch::Receiver<T>, ConfigError>
+    where
+        T: Serialize + DeserializeOwned + Default + Send + Sync + 'static,
+        E: std::fmt::Display + 'static,
+        V: Fn(&T) -> Result<(), E> + Send + Sync + 'static,
+    {
+        let plugin = Self::get_plugin_name();
+        let path = KoviPaths::current().conf_file();
+        let config = plugin_conf::load::<T, E>(&path, &plugin, &validate)?;
+
+        let (tx, rx) = watch::channel(config);
+        crate::spawn(plugin_conf::watch(
+            path,
+            plugin,
+            tx,
+            plugin_conf::POLL_INTERVAL,
+            validate,
+        ));
+        Ok(rx)
+    }
+
+    /// 设置此插件的事件分发方式，优先于 Bot 的设置，见 [`DispatchMode`]。
+    ///
+    /// 需要按顺序处理同一会话中的消息时（例如多步对话、计数器），使用 [`DispatchMode::Ordered`]。
+    pub fn set_dispatch_mode(mode: DispatchMode) {
+        let p = Self::current();
+        let mut bot = p.bot.write();
+        let bot_plugin = bot
+            .plugins
+            .get_mut(&p.runtime_bot.plugin_name)
+            .expect("unreachable");
+
+        bot_plugin.set_dispatch_mode(mode);
+        p.refresh_dispatch_table(&bot);
     }
 
     /// 注册定时任务。
     ///
     /// 传入 Cron 。
-    pub fn cron<F, Fut>(cron: &str, handler: F) -> Result<(), CronError>
+    pub fn cron<F, Fut>(cron: &str, handler: F) -> Result<ListenerHandle, CronError>
     where
         F: Fn() -> Fut + Send + Sync + 'static,
         Fut: Future + Send,
         Fut::Output: Send,
     {
-        assert_right_place!(PLUGIN_BUILDER.try_with(|p| {
-            let cron = match Cron::new(cron).with_seconds_optional().parse() {
-                Ok(v) => v,
-                Err(e) => return Err(e),
-            };
-            Self::run_cron_task(p, cron, handler);
-            Ok(())
-        }))
+        let p = Self::current();
+        let cron = Cron::new(cron).with_seconds_optional().parse()?;
+        Ok(Self::run_cron_task(&p, cron, handler))
     }
 
     /// 注册定时任务。
     ///
     /// 传入 Cron 。
-    pub fn cron_use_croner<F, Fut>(cron: Cron, handler: F)
+    pub fn cron_use_croner<F, Fut>(cron: Cron, handler: F) -> ListenerHandle
     where
         F: Fn() -> Fut + Send + Sync + 'static,
         Fut: Future + Send,
         Fut::Output: Send,
     {
-        assert_right_place!(PLUGIN_BUILDER.try_with(|p| {
-            Self::run_cron_task(p, cron, handler);
-        }));
+        let p = Self::current();
+        Self::run_cron_task(&p, cron, handler)
     }
 
-    fn run_cron_task<F, Fut>(p: &PluginBuilder, cron: Cron, handler: F)
+    fn run_cron_task<F, Fut>(p: &PluginBuilder, cron: Cron, handler: F) -> ListenerHandle
     where
         F: Fn() -> Fut + Send + Sync + 'static,
         Fut: Future + Send,
@@ -185,8 +542,7 @@ impl PluginBuilder {
             let plugin = bot.plugins.get(&*name).expect("unreachable");
             plugin.enabled.subscribe()
         };
-        tokio::spawn(PLUGIN_NAME.scope(name.clone(), async move {
-
+        let task = async move {
             tokio::select! {
                 _ = async {
                         loop {
@@ -213,37 +569,83 @@ impl PluginBuilder {
                         }
                 } => {}
             }
-        }));
+        };
+        let join = tokio::spawn(PLUGIN_NAME.scope(
+            Arc::new(p.runtime_bot.plugin_name.clone()),
+            PLUGIN_RUNTIME_BOT.scope(p.runtime_bot.clone(), task),
+        ));
+
+        ListenerHandle::cron(
+            Arc::downgrade(&p.bot),
+            p.runtime_bot.plugin_name.clone(),
+            join.abort_handle(),
+        )
     }
 
     /// 注册程序结束事件处理函数。
     ///
     /// 注册处理程序，用于处理接收到的程序结束事件。
-    pub fn drop<F, Fut>(handler: F)
+    pub fn drop<F, Fut>(handler: F) -> ListenerHandle
     where
         F: Fn() -> Fut + Send + Sync + 'static,
         Fut: Future + Send,
         Fut::Output: Send,
     {
-        assert_right_place!(PLUGIN_BUILDER.try_with(|p| {
+        let p = Self::current();
+        let id = next_listener_id();
+        {
             let mut bot = p.bot.write();
             let bot_plugin = bot
                 .plugins
                 .get_mut(&p.runtime_bot.plugin_name)
                 .expect("unreachable");
 
-            bot_plugin.listen.drop.push(Arc::new({
-                let handler = Arc::new(handler);
-                move || {
+            let handler = Arc::new(handler);
+            bot_plugin.listen.drop.push(DropListen {
+                id,
+                handler: Arc::new(move || {
                     Box::pin({
                         let handler = handler.clone();
                         async move {
                             handler().await;
                         }
                     })
-                }
-            }));
-        }));
+                }),
+            });
+        }
+        ListenerHandle::drop(
+            Arc::downgrade(&p.bot),
+            p.runtime_bot.plugin_name.clone(),
+            id,
+        )
+    }
+
+    /// 注册一组命令。
+    ///
+    /// 命令的匹配、参数解析与帮助见 [`CommandSet`]。
+    pub fn commands<E>(commands: CommandSet<E>) -> ListenerHandle
+    where
+        E: MessageEventTrait + RepliableEvent,
+    {
+        let bot = Self::get_runtime_bot();
+        let commands = Arc::new(commands);
+        Self::on(move |event: Arc<E>| {
+            let bot = bot.clone();
+            let commands = commands.clone();
+            async move {
+                commands.dispatch(event, &bot).await;
+            }
+        })
+    }
+
+    /// 单独注册一条命令，不生成 `help`。
+    ///
+    /// 需要帮助时请把命令加入 [`CommandSet`]，再用 [`PluginBuilder::commands`] 注册。
+    pub fn command<E>(def: CommandDef<E>) -> ListenerHandle
+    where
+        E: MessageEventTrait + RepliableEvent,
+    {
+        Self::commands(CommandSet::new().disable_help().register(def))
     }
 }
 
@@ -0,0 +1,325 @@
+//! 自动保存的插件状态
+//!
+//! [`PluginState`] 在插件中加载一次数据，通过 [`read()`](PluginState::read) / [`write()`](PluginState::write) 访问。
+//! 修改后会在一段时间内合并保存，插件关闭时也会保存。
+//!
+//! 文件中记录数据的版本（`{"kovi_state_version": n, "data": ...}`），加载旧版本的数据时依次执行注册的迁移（v1 → v2 → v3），
+//! 迁移前的文件会备份为 `<文件名>.v<版本>.bak`。没有版本的文件（如由 `save_json_data` 保存的）视为版本 1。
+//!
+//! # Examples
+//! ```ignore
+//! use kovi::plugin::state::PluginState;
+//!
+//! #[derive(Default, Serialize, Deserialize)]
+//! struct Data {
+//!     // v2 把 `users: Vec<i64>` 改为了 `users: Vec<User>`
+//!     users: Vec<User>,
+//! }
+//!
+//! let state = PluginState::<Data>::builder("data.json")
+//!     .set_version(2)
+//!     .migrate(1, |mut v| {
+//!         v["users"] = v["users"]
+//!             .as_array()
+//!             .into_iter()
+//!             .flatten()
+//!             .map(|id| json!({ "id": id, "name": null }))
+//!             .collect();
+//!         Ok(v)
+//!     })
+//!     .load(Data::default)?;
+//!
+//! state.write().users.push(user);
+//! let count = state.read().users.len();
+//! ```
+
+use crate::PluginBuilder;
+use crate::error::StateError;
+use crate::storage::write_atomic;
+use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+#[cfg(test)]
+mod test;
+
+/// 文件中记录版本的键，不会与插件数据中常见的 `version` 混淆
+const VERSION_KEY: &str = "kovi_state_version";
+
+type Migration = Box<dyn Fn(Value) -> Result<Value, String> + Send + Sync>;
+
+/// 自动保存的插件状态，见 [模块文档](self)
+///
+/// 可以廉价地 clone，所有 clone 共享同一份数据。
+pub struct PluginState<T> {
+    inner: Arc<StateInner<T>>,
+}
+
+impl<T> Clone for PluginState<T> {
+    fn clone(&self) -> Self {
+        PluginState {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+struct StateInner<T> {
+    data: RwLock<T>,
+    path: PathBuf,
+    version: u32,
+    dirty: AtomicBool,
+    changed: Notify,
+}
+
+/// 加载 [`PluginState`] 的选项
+pub struct PluginStateBuilder<T> {
+    file: PathBuf,
+    version: u32,
+    migrations: BTreeMap<u32, Migration>,
+    debounce: Duration,
+    _marker: std::marker::PhantomData<fn() -> T>,
+}
+
+impl<T> PluginState<T>
+where
+    T: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    /// 加载插件数据目录下的 `file`，不存在时使用 `default()` 并保存，版本为 1
+    ///
+    /// 只能在插件中调用。
+    pub fn load(file: impl AsRef<Path>, default: impl FnOnce() -> T) -> Result<Self, StateError> {
+        Self::builder(file).load(default)
+    }
+
+    /// 设置版本与迁移后再加载，`file` 为插件数据目录下的路径
+    pub fn builder(file: impl AsRef<Path>) -> PluginStateBuilder<T> {
+        PluginStateBuilder {
+            file: file.as_ref().to_path_buf(),
+            version: 1,
+            migrations: BTreeMap::new(),
+            debounce: Duration::from_secs(1),
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    /// 读取状态
+    ///
+    /// 持有守卫时不能跨越 `.await`。
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.inner.data.read()
+    }
+
+    /// 修改状态，守卫释放后安排保存
+    ///
+    /// 持有守卫时不能跨越 `.await`。
+    pub fn write(&self) -> StateWriteGuard<'_, T> {
+        StateWriteGuard {
+            guard: self.inner.data.write(),
+            inner: &self.inner,
+        }
+    }
+
+    /// 立即保存
+    pub fn save(&self) -> Result<(), StateError> {
+        self.inner.dirty.store(false, Ordering::SeqCst);
+        self.inner.save().inspect_err(|_| {
+            self.inner.dirty.store(true, Ordering::SeqCst);
+        })
+    }
+
+    /// 保存的文件路径
+    pub fn path(&self) -> &Path {
+        &self.inner.path
+    }
+}
+
+impl<T: Serialize> StateInner<T> {
+    fn save(&self) -> Result<(), StateError> {
+        let data = {
+            let data = self.data.read();
+            let file = json!({ VERSION_KEY: self.version, "data": &*data });
+            serde_json::to_vec_pretty(&file).map_err(|e| StateError::Serialize(e.to_string()))?
+        };
+        write_atomic(&self.path, &data)?;
+        Ok(())
+    }
+
+    fn save_if_dirty(&self) {
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return;
+        }
+        if let Err(e) = self.save() {
+            self.dirty.store(true, Ordering::SeqCst);
+            log::error!("Failed to save plugin state {:?}: {e}", self.path);
+        }
+    }
+}
+
+impl<T> PluginStateBuilder<T>
+where
+    T: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    /// 当前数据的版本，默认为 1
+    pub fn set_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// 注册从版本 `from` 到 `from + 1` 的迁移
+    pub fn migrate(
+        mut self,
+        from: u32,
+        f: impl Fn(Value) -> Result<Value, String> + Send + Sync + 'static,
+    ) -> Self {
+        self.migrations.insert(from, Box::new(f));
+        self
+    }
+
+    /// 修改后等待多久再保存，期间的修改一起保存，默认为 1 秒
+    pub fn set_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// 加载状态，文件不存在时使用 `default()` 并保存
+    ///
+    /// 只能在插件中调用。插件关闭时会保存未保存的修改。
+    pub fn load(self, default: impl FnOnce() -> T) -> Result<PluginState<T>, StateError> {
+        let path = PluginBuilder::get_runtime_bot()
+            .get_data_path()
+            .join(&self.file);
+
+        let (data, save) = match std::fs::read(&path) {
+            Ok(bytes) => {
+                let value: Value =
+                    serde_json::from_slice(&bytes).map_err(|e| StateError::Corrupted {
+                        path: path.clone(),
+                        error: e.to_string(),
+                    })?;
+                let (version, value) = split_version(value);
+                let migrated = version != self.version;
+                let value = self.run_migrations(&path, version, value)?;
+                let data = serde_json::from_value(value).map_err(|e| StateError::Corrupted {
+                    path: path.clone(),
+                    error: e.to_string(),
+                })?;
+                (data, migrated)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (default(), true),
+            Err(e) => return Err(e.into()),
+        };
+
+        let inner = Arc::new(StateInner {
+            data: RwLock::new(data),
+            path,
+            version: self.version,
+            dirty: AtomicBool::new(false),
+            changed: Notify::new(),
+        });
+        if save {
+            inner.save()?;
+        }
+
+        crate::spawn(save_debounced(inner.clone(), self.debounce));
+        PluginBuilder::drop({
+            let inner = inner.clone();
+            move || {
+                let inner = inner.clone();
+                async move { inner.save_if_dirty() }
+            }
+        });
+
+        Ok(PluginState { inner })
+    }
+
+    fn run_migrations(
+        &self,
+        path: &Path,
+        mut version: u32,
+        mut value: Value,
+    ) -> Result<Value, StateError> {
+        if version > self.version {
+            return Err(StateError::FutureVersion {
+                found: version,
+                current: self.version,
+            });
+        }
+        if version < self.version {
+            let mut backup = path.as_os_str().to_owned();
+            backup.push(format!(".v{version}.bak"));
+            std::fs::copy(path, PathBuf::from(backup))?;
+        }
+        while version < self.version {
+            let migration = self
+                .migrations
+                .get(&version)
+                .ok_or(StateError::MissingMigration(version))?;
+            value = migration(value).map_err(|error| StateError::Migration {
+                from: version,
+                error,
+            })?;
+            log::info!(
+                "Migrated plugin state {path:?} from v{version} to v{}",
+                version + 1
+            );
+            version += 1;
+        }
+        Ok(value)
+    }
+}
+
+/// 文件为 `{"kovi_state_version": n, "data": ...}`，其他内容视为版本 1 的数据
+fn split_version(value: Value) -> (u32, Value) {
+    if let Value::Object(map) = &value
+        && map.len() == 2
+        && let Some(version) = map.get(VERSION_KEY).and_then(Value::as_u64)
+        && let Some(data) = map.get("data")
+    {
+        return (version as u32, data.clone());
+    }
+    (1, value)
+}
+
+/// 收到修改后等待 `debounce` 再保存，随插件关闭而结束
+async fn save_debounced<T: Serialize>(inner: Arc<StateInner<T>>, debounce: Duration) {
+    loop {
+        inner.changed.notified().await;
+        tokio::time::sleep(debounce).await;
+        inner.save_if_dirty();
+    }
+}
+
+/// [`PluginState::write()`] 的守卫，释放后安排保存
+pub struct StateWriteGuard<'a, T> {
+    guard: RwLockWriteGuard<'a, T>,
+    inner: &'a StateInner<T>,
+}
+
+impl<T> Deref for StateWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for StateWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T> Drop for StateWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.inner.dirty.store(true, Ordering::SeqCst);
+        self.inner.changed.notify_one();
+    }
+}
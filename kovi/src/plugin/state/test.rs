@@ -0,0 +1,18 @@
+use super::split_version;
+use serde_json::json;
+
+#[test]
+fn versioned_file_is_split() {
+    let file = json!({ "kovi_state_version": 3, "data": { "users": [] } });
+    assert_eq!(split_version(file), (3, json!({ "users": [] })));
+}
+
+#[test]
+fn plain_data_is_version_one() {
+    // 插件自己的数据恰好只有 `version` 与 `data` 两个键
+    let data = json!({ "version": 2, "data": [1, 2] });
+    assert_eq!(split_version(data.clone()), (1, data));
+
+    let data = json!({ "users": [1, 2] });
+    assert_eq!(split_version(data.clone()), (1, data));
+}
@@ -0,0 +1,224 @@
+use crate::bot::BotInformation;
+use crate::bot::account::{ACCOUNT, Account};
+use crate::error::SessionError;
+use crate::event::id::ID;
+use crate::event::{Event, InternalEvent, MessageEventTrait, parse_cached};
+use crate::plugin::PLUGIN_NAME;
+use crate::plugin::plugin_builder::DowncastArc;
+use crate::types::{ApiAndOptOneshot, ArcTypeDeFn};
+use ahash::HashSet;
+use parking_lot::Mutex;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+pub(crate) static SESSIONS: LazyLock<SessionManager> = LazyLock::new(SessionManager::default);
+
+/// 多步对话中的一个会话，等待同一发送者在同一群（或私聊）中的下一条消息
+///
+/// 等到的消息只交给这个会话，不会再触发本插件的其他监听，其他插件照常收到。
+/// 插件被关闭时，正在进行的等待会返回 [`SessionError::Cancelled`]。
+///
+/// # Examples
+/// ```ignore
+/// use kovi::session::Session;
+///
+/// PluginBuilder::on_msg(|event| async move {
+///     if event.borrow_text() != Some("天气") {
+///         return;
+///     }
+///     let session = Session::new(&event).timeout(Duration::from_secs(30));
+///     event.reply("你在哪个城市？");
+///     match session.next().await {
+///         Ok(answer) => event.reply(format!("{} 今天晴", answer.get_text())),
+///         Err(e) => event.reply(e.to_string()),
+///     }
+/// });
+/// ```
+pub struct Session<E> {
+    plugin: Arc<String>,
+    account: Option<Account>,
+    sender: ID,
+    group: Option<ID>,
+    timeout: Duration,
+    _event: PhantomData<fn() -> E>,
+}
+
+impl<E: MessageEventTrait> Session<E> {
+    /// 以 `event` 的发送者与群创建会话，默认超时时间为 60 秒
+    ///
+    /// # Panics
+    ///
+    /// 在插件的监听闭包或 `kovi::spawn()` 创建的线程之外调用时 panic。
+    pub fn new(event: &E) -> Self {
+        let plugin = PLUGIN_NAME.with(|name| name.clone());
+        Session {
+            plugin,
+            account: ACCOUNT.try_with(|account| account.clone()).ok(),
+            sender: ID::new(event.get_sender_id()),
+            group: event.get_group_id().map(ID::new),
+            timeout: Duration::from_secs(60),
+            _event: PhantomData,
+        }
+    }
+
+    /// 设置每次等待的超时时间
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 等待下一条消息
+    pub async fn next(&self) -> Result<Arc<E>, SessionError> {
+        self.next_matching(|_| true).await
+    }
+
+    /// 等待下一条满足 `filter` 的消息，不满足的消息照常交给本插件的监听
+    pub async fn next_matching<F>(&self, filter: F) -> Result<Arc<E>, SessionError>
+    where
+        F: Fn(&E) -> bool + Send + Sync + 'static,
+    {
+        let sender = self.sender.clone();
+        let group = self.group.clone();
+        let matches = move |event: &Arc<dyn Event>| {
+            let Ok(event) = event.clone().downcast_arc::<E>() else {
+                return false;
+            };
+            let same_group = match (&group, event.get_group_id()) {
+                (Some(group), Some(id)) => *group == id,
+                (None, None) => true,
+                _ => false,
+            };
+            same_group && sender == event.get_sender_id() && filter(&event)
+        };
+
+        let (tx, rx) = oneshot::channel();
+        let guard = SESSIONS.register(Waiter {
+            id: 0,
+            plugin: self.plugin.clone(),
+            account: self.account.clone(),
+            type_de: Arc::new(|value, bot_info, sender| {
+                Some(parse_cached::<E>(value, bot_info, sender)?)
+            }),
+            matches: Box::new(matches),
+            tx,
+        });
+
+        let result = match tokio::time::timeout(self.timeout, rx).await {
+            Ok(Ok(event)) => event
+                .downcast_arc::<E>()
+                .map_err(|_| SessionError::Cancelled),
+            Ok(Err(_)) => Err(SessionError::Cancelled),
+            Err(_) => Err(SessionError::Timeout),
+        };
+        drop(guard);
+        result
+    }
+}
+
+/// 等待同一会话中的下一条消息
+pub trait WaitNext: MessageEventTrait + Sized {
+    /// 等待同一发送者在同一群（或私聊）中、满足 `filter` 的下一条消息，详见 [`Session`]
+    fn wait_next<F>(
+        &self,
+        filter: F,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<Arc<Self>, SessionError>> + Send
+    where
+        F: Fn(&Self) -> bool + Send + Sync + 'static,
+    {
+        let session = Session::new(self).timeout(timeout);
+        async move { session.next_matching(filter).await }
+    }
+}
+
+impl<E: MessageEventTrait> WaitNext for E {}
+
+type MatchFn = Box<dyn Fn(&Arc<dyn Event>) -> bool + Send + Sync>;
+
+pub(crate) struct Waiter {
+    id: u64,
+    plugin: Arc<String>,
+    account: Option<Account>,
+    type_de: ArcTypeDeFn,
+    matches: MatchFn,
+    tx: oneshot::Sender<Arc<dyn Event>>,
+}
+
+#[derive(Default)]
+pub(crate) struct SessionManager {
+    next_id: AtomicU64,
+    waiters: Mutex<Vec<Waiter>>,
+}
+
+/// 等待结束（包括被取消）时移除对应的 Waiter
+struct WaiterGuard {
+    id: u64,
+}
+
+impl Drop for WaiterGuard {
+    fn drop(&mut self) {
+        SESSIONS
+            .waiters
+            .lock()
+            .retain(|waiter| waiter.id != self.id);
+    }
+}
+
+impl SessionManager {
+    fn register(&self, mut waiter: Waiter) -> WaiterGuard {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        waiter.id = id;
+        self.waiters.lock().push(waiter);
+        WaiterGuard { id }
+    }
+
+    /// 把事件交给等待它的会话，返回接收了事件的插件，这些插件不再处理此事件
+    ///
+    /// `can_receive` 判断插件能否收到此事件，即插件已开启且名单允许。
+    pub(crate) fn deliver(
+        &self,
+        account: &Account,
+        event: &InternalEvent,
+        bot_info: &BotInformation,
+        api_tx: &mpsc::Sender<ApiAndOptOneshot>,
+        can_receive: impl Fn(&Arc<String>) -> bool,
+    ) -> HashSet<Arc<String>> {
+        let mut consumed = HashSet::default();
+        let mut waiters = self.waiters.lock();
+        if waiters.is_empty() {
+            return consumed;
+        }
+
+        let mut index = 0;
+        while index < waiters.len() {
+            let waiter = &waiters[index];
+            let skip = consumed.contains(&waiter.plugin)
+                || waiter.account.as_ref().is_some_and(|a| a != account)
+                || !can_receive(&waiter.plugin);
+            let event = (!skip)
+                .then(|| (waiter.type_de)(event, bot_info, api_tx))
+                .flatten()
+                .filter(|event| (waiter.matches)(event));
+            let Some(event) = event else {
+                index += 1;
+                continue;
+            };
+
+            let waiter = waiters.remove(index);
+            if waiter.tx.send(event).is_ok() {
+                consumed.insert(waiter.plugin);
+            }
+        }
+        consumed
+    }
+
+    /// 插件关闭时取消它的所有等待
+    pub(crate) fn disable_plugin(&self, plugin_name: &str) {
+        self.waiters
+            .lock()
+            .retain(|waiter| waiter.plugin.as_str() != plugin_name);
+    }
+}
@@ -0,0 +1,415 @@
+//! 插件的键值存储
+//!
+//! 每个插件的数据保存在 `get_data_path()` 下的 `storage.redb` 中，由 [redb](https://docs.rs/redb) 读写。
+//! 每次写入只修改改动的键，写入中途崩溃不会损坏已有的数据。
+//!
+//! # Examples
+//! ```ignore
+//! let storage = bot.storage()?;
+//!
+//! storage.set("greeting", &"hello")?;
+//! let count = storage.update("count", |n: &mut i64| *n += 1)?;
+//!
+//! // 一小时后过期
+//! storage.set_with_ttl("cooldown:10001", &true, Duration::from_secs(3600))?;
+//!
+//! // 多个键一起写入，闭包返回错误时都不写入
+//! storage.transaction(|tx| {
+//!     let from: i64 = tx.get("coins:1")?.unwrap_or_default();
+//!     tx.set("coins:1", &(from - 10))?;
+//!     tx.update("coins:2", |n: &mut i64| *n += 10)?;
+//!     Ok::<_, StorageError>(())
+//! })?;
+//! ```
+
+use crate::RuntimeBot;
+use crate::error::StorageError;
+use parking_lot::Mutex;
+use redb::{Database, ReadableDatabase as _, ReadableTable as _, TableDefinition};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, File};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Weak};
+use std::time::Duration;
+
+#[cfg(test)]
+mod test;
+
+/// 存储文件的文件名
+pub const STORAGE_FILE: &str = "storage.redb";
+
+const TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("storage");
+
+/// 同一文件只打开一次，redb 不允许重复打开
+static OPENED: LazyLock<Mutex<ahash::HashMap<PathBuf, Weak<Store>>>> =
+    LazyLock::new(Default::default);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    value: Value,
+    /// 过期时的 Unix 时间戳（毫秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<i64>,
+}
+
+impl Entry {
+    fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|v| v <= now)
+    }
+}
+
+struct Store {
+    path: PathBuf,
+    db: Database,
+}
+
+impl Store {
+    /// 打开数据库，并清除过期的键
+    fn open(path: PathBuf) -> Result<Self, StorageError> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)?;
+        }
+        // 不是 redb 的文件时，redb 返回 InvalidData
+        let db = Database::create(&path).map_err(|e| match e.into() {
+            redb::Error::Io(e) if e.kind() != std::io::ErrorKind::InvalidData => {
+                StorageError::Io(e)
+            }
+            e => StorageError::Corrupted {
+                path: path.clone(),
+                error: e.to_string(),
+            },
+        })?;
+
+        let store = Store { path, db };
+        let now = now();
+        let txn = store.db.begin_write().map_err(db_error)?;
+        {
+            let mut table = txn.open_table(TABLE).map_err(db_error)?;
+            // 只清除过期的键，无法解析的键保留，读取时报告错误
+            table
+                .retain(|_, data| {
+                    serde_json::from_slice::<Entry>(data)
+                        .map_or(true, |entry| !entry.is_expired(now))
+                })
+                .map_err(db_error)?;
+        }
+        txn.commit().map_err(db_error)?;
+        Ok(store)
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<Entry, StorageError> {
+        serde_json::from_slice(data).map_err(|e| StorageError::Corrupted {
+            path: self.path.clone(),
+            error: e.to_string(),
+        })
+    }
+
+    /// 读取未过期的 `key`
+    fn get(&self, key: &str) -> Result<Option<Entry>, StorageError> {
+        let txn = self.db.begin_read().map_err(db_error)?;
+        let table = txn.open_table(TABLE).map_err(db_error)?;
+        let Some(data) = table.get(key).map_err(db_error)? else {
+            return Ok(None);
+        };
+        let entry = self.decode(data.value())?;
+        Ok((!entry.is_expired(now())).then_some(entry))
+    }
+
+    fn keys(&self) -> Result<Vec<String>, StorageError> {
+        let now = now();
+        let txn = self.db.begin_read().map_err(db_error)?;
+        let table = txn.open_table(TABLE).map_err(db_error)?;
+        let mut keys = Vec::new();
+        for item in table.iter().map_err(db_error)? {
+            let (key, data) = item.map_err(db_error)?;
+            if !self.decode(data.value())?.is_expired(now) {
+                keys.push(key.value().to_string());
+            }
+        }
+        Ok(keys)
+    }
+}
+
+fn db_error(e: impl Into<redb::Error>) -> StorageError {
+    match e.into() {
+        redb::Error::Io(e) => StorageError::Io(e),
+        e => StorageError::Database(e.to_string()),
+    }
+}
+
+/// 在 tokio 的多线程运行时中时，让运行时把其他任务移到别的线程，再执行会阻塞的 `f`
+fn block_in_place<R>(f: impl FnOnce() -> R) -> R {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(f)
+        }
+        _ => f(),
+    }
+}
+
+/// 先写入同目录的临时文件再重命名，重命名是原子的
+///
+/// 临时文件名在进程中唯一，同时写入同一文件时不会互相干扰，最后完成的写入生效。
+pub(crate) fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    static NEXT_TMP: AtomicU64 = AtomicU64::new(0);
+
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        NEXT_TMP.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp = PathBuf::from(tmp);
+
+    let result = (|| {
+        let mut file = File::create(&tmp)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+fn expires_at(ttl: Duration) -> i64 {
+    now().saturating_add(ttl.as_millis().try_into().unwrap_or(i64::MAX))
+}
+
+fn to_value<T: Serialize + ?Sized>(key: &str, value: &T) -> Result<Value, StorageError> {
+    serde_json::to_value(value).map_err(|e| StorageError::Serialize {
+        key: key.to_string(),
+        error: e.to_string(),
+    })
+}
+
+fn from_value<T: DeserializeOwned>(key: &str, value: Value) -> Result<T, StorageError> {
+    serde_json::from_value(value).map_err(|e| StorageError::Deserialize {
+        key: key.to_string(),
+        error: e.to_string(),
+    })
+}
+
+/// 插件的键值存储，见 [模块文档](self)
+///
+/// 可以廉价地 clone，所有 clone 共享同一份数据。
+#[derive(Clone)]
+pub struct Storage {
+    store: Arc<Store>,
+}
+
+impl Storage {
+    /// 打开 `path` 处的存储文件，不存在时创建
+    ///
+    /// 同一个文件在进程中只会打开一次，之后打开得到的是同一份数据。
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        let path = path.as_ref().to_path_buf();
+        let mut opened = OPENED.lock();
+        if let Some(store) = opened.get(&path).and_then(Weak::upgrade) {
+            return Ok(Storage { store });
+        }
+
+        opened.retain(|_, store| store.strong_count() > 0);
+        let store = Arc::new(block_in_place(|| Store::open(path.clone()))?);
+        opened.insert(path, Arc::downgrade(&store));
+        Ok(Storage { store })
+    }
+
+    /// 存储文件的路径
+    pub fn path(&self) -> PathBuf {
+        self.store.path.clone()
+    }
+
+    /// 读取 `key`，不存在或已过期时返回 `None`
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, StorageError> {
+        match self.store.get(key)? {
+            Some(entry) => from_value(key, entry.value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// `key` 是否存在且未过期，读取失败时记录错误并返回 `false`
+    pub fn contains(&self, key: &str) -> bool {
+        self.store
+            .get(key)
+            .inspect_err(|e| log::error!("Failed to read storage {:?}: {e}", self.store.path))
+            .is_ok_and(|entry| entry.is_some())
+    }
+
+    /// 所有未过期的键，按字典序。读取失败时记录错误并返回空列表
+    pub fn keys(&self) -> Vec<String> {
+        self.store
+            .keys()
+            .inspect_err(|e| log::error!("Failed to read storage {:?}: {e}", self.store.path))
+            .unwrap_or_default()
+    }
+
+    /// 写入 `key`，不会过期
+    pub fn set<T: Serialize + ?Sized>(&self, key: &str, value: &T) -> Result<(), StorageError> {
+        self.transaction(|tx| tx.set(key, value))
+    }
+
+    /// 写入 `key`，`ttl` 后过期
+    pub fn set_with_ttl<T: Serialize + ?Sized>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> Result<(), StorageError> {
+        self.transaction(|tx| tx.set_with_ttl(key, value, ttl))
+    }
+
+    /// 以 `f` 修改 `key`，不存在或已过期时从 `T::default()` 开始，保留原有的过期时间
+    ///
+    /// 返回修改后的值。
+    pub fn update<T, F>(&self, key: &str, f: F) -> Result<T, StorageError>
+    where
+        T: Serialize + DeserializeOwned + Default,
+        F: FnOnce(&mut T),
+    {
+        self.transaction(|tx| tx.update(key, f))
+    }
+
+    /// 删除 `key`，返回它是否存在
+    pub fn remove(&self, key: &str) -> Result<bool, StorageError> {
+        self.transaction(|tx| tx.remove(key))
+    }
+
+    /// 在一次写入中修改多个键
+    ///
+    /// `f` 返回 `Ok` 时所有修改一起写入，返回 `Err` 时所有修改都被丢弃。
+    /// 事务进行时其他写入会等待，读取不受影响，读到的是事务开始前的数据。
+    ///
+    /// 写入会阻塞当前线程，在 tokio 的多线程运行时中会先让出工作线程。
+    pub fn transaction<R, E, F>(&self, f: F) -> Result<R, E>
+    where
+        F: FnOnce(&mut Transaction<'_>) -> Result<R, E>,
+        E: From<StorageError>,
+    {
+        block_in_place(|| {
+            let txn = self.store.db.begin_write().map_err(db_error)?;
+            let (result, changed) = {
+                let table = txn.open_table(TABLE).map_err(db_error)?;
+                let mut tx = Transaction {
+                    store: &self.store,
+                    table,
+                    changed: false,
+                };
+                let result = f(&mut tx);
+                (result, tx.changed)
+            };
+            // 没有提交的事务在丢弃时撤销
+            if changed && result.is_ok() {
+                txn.commit().map_err(db_error)?;
+            }
+            result
+        })
+    }
+}
+
+/// [`Storage::transaction()`] 中的修改，提交前对其他读写不可见
+pub struct Transaction<'a> {
+    store: &'a Store,
+    table: redb::Table<'a, &'static str, &'static [u8]>,
+    changed: bool,
+}
+
+impl Transaction<'_> {
+    /// 读取未过期的 `key`，包括此事务中的修改
+    fn entry(&self, key: &str) -> Result<Option<Entry>, StorageError> {
+        let Some(data) = self.table.get(key).map_err(db_error)? else {
+            return Ok(None);
+        };
+        let entry = self.store.decode(data.value())?;
+        Ok((!entry.is_expired(now())).then_some(entry))
+    }
+
+    /// 读取 `key`，包括此事务中的修改
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, StorageError> {
+        match self.entry(key)? {
+            Some(entry) => from_value(key, entry.value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// 写入 `key`，不会过期
+    pub fn set<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), StorageError> {
+        self.insert(key, to_value(key, value)?, None)
+    }
+
+    /// 写入 `key`，`ttl` 后过期
+    pub fn set_with_ttl<T: Serialize + ?Sized>(
+        &mut self,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> Result<(), StorageError> {
+        self.insert(key, to_value(key, value)?, Some(expires_at(ttl)))
+    }
+
+    /// 见 [`Storage::update()`]
+    pub fn update<T, F>(&mut self, key: &str, f: F) -> Result<T, StorageError>
+    where
+        T: Serialize + DeserializeOwned + Default,
+        F: FnOnce(&mut T),
+    {
+        let (mut value, expires_at) = match self.entry(key)? {
+            Some(entry) => (from_value(key, entry.value)?, entry.expires_at),
+            None => (T::default(), None),
+        };
+        f(&mut value);
+        self.insert(key, to_value(key, &value)?, expires_at)?;
+        Ok(value)
+    }
+
+    /// 删除 `key`，返回它是否存在
+    pub fn remove(&mut self, key: &str) -> Result<bool, StorageError> {
+        let existed = self.entry(key)?.is_some();
+        if self.table.remove(key).map_err(db_error)?.is_some() {
+            self.changed = true;
+        }
+        Ok(existed)
+    }
+
+    fn insert(
+        &mut self,
+        key: &str,
+        value: Value,
+        expires_at: Option<i64>,
+    ) -> Result<(), StorageError> {
+        let data =
+            serde_json::to_vec(&Entry { value, expires_at }).map_err(std::io::Error::from)?;
+        self.table.insert(key, data.as_slice()).map_err(db_error)?;
+        self.changed = true;
+        Ok(())
+    }
+}
+
+impl RuntimeBot {
+    /// 插件的键值存储，保存在 [`get_data_path()`](RuntimeBot::get_data_path) 下的 [`STORAGE_FILE`]
+    ///
+    /// # error
+    ///
+    /// 存储文件无法读取或已损坏时返回错误。
+    pub fn storage(&self) -> Result<Storage, StorageError> {
+        Storage::open(self.get_data_path().join(STORAGE_FILE))
+    }
+}
@@ -0,0 +1,199 @@
+use super::{STORAGE_FILE, Storage, TABLE, write_atomic};
+use crate::error::StorageError;
+use redb::ReadableDatabase as _;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// 每个测试使用自己的临时目录
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("kovi-storage-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn typed_get_set_update_and_remove() {
+    let dir = temp_dir("basic");
+    let storage = Storage::open(dir.join(STORAGE_FILE)).expect("open");
+
+    assert_eq!(storage.get::<String>("name").expect("get"), None);
+    storage.set("name", "kovi").expect("set");
+    assert_eq!(
+        storage.get::<String>("name").expect("get").as_deref(),
+        Some("kovi")
+    );
+    assert!(matches!(
+        storage.get::<i64>("name"),
+        Err(StorageError::Deserialize { .. })
+    ));
+
+    assert_eq!(
+        storage
+            .update("count", |n: &mut i64| *n += 1)
+            .expect("update"),
+        1
+    );
+    assert_eq!(
+        storage
+            .update("count", |n: &mut i64| *n += 1)
+            .expect("update"),
+        2
+    );
+    assert_eq!(storage.keys(), ["count", "name"]);
+
+    assert!(storage.remove("name").expect("remove"));
+    assert!(!storage.remove("name").expect("remove"));
+    assert!(!storage.contains("name"));
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn data_survives_reopen_and_leaves_no_temp_file() {
+    let dir = temp_dir("reopen");
+    let path = dir.join(STORAGE_FILE);
+    {
+        let storage = Storage::open(&path).expect("open");
+        storage.set("list", &[1, 2, 3]).expect("set");
+    }
+
+    let storage = Storage::open(&path).expect("open");
+    assert_eq!(
+        storage.get::<Vec<i32>>("list").expect("get"),
+        Some(vec![1, 2, 3])
+    );
+    let files: Vec<_> = std::fs::read_dir(&dir)
+        .expect("read_dir")
+        .map(|v| v.expect("entry").file_name())
+        .collect();
+    assert_eq!(files, [STORAGE_FILE]);
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn failed_transaction_writes_nothing() {
+    let dir = temp_dir("transaction");
+    let storage = Storage::open(dir.join(STORAGE_FILE)).expect("open");
+    storage.set("a", &10).expect("set");
+
+    let result: Result<(), StorageError> = storage.transaction(|tx| {
+        tx.set("a", &0)?;
+        tx.set("b", &10)?;
+        assert_eq!(tx.get::<i32>("a")?, Some(0));
+        tx.get::<String>("a")?;
+        Ok(())
+    });
+    assert!(result.is_err());
+    assert_eq!(storage.get::<i32>("a").expect("get"), Some(10));
+    assert!(!storage.contains("b"));
+
+    storage
+        .transaction(|tx| {
+            tx.update("a", |n: &mut i32| *n -= 5)?;
+            tx.update("b", |n: &mut i32| *n += 5)?;
+            Ok::<_, StorageError>(())
+        })
+        .expect("transaction");
+    assert_eq!(storage.get::<i32>("a").expect("get"), Some(5));
+    assert_eq!(storage.get::<i32>("b").expect("get"), Some(5));
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn entries_expire_after_ttl() {
+    let dir = temp_dir("ttl");
+    let storage = Storage::open(dir.join(STORAGE_FILE)).expect("open");
+
+    storage
+        .set_with_ttl("cooldown", &true, Duration::from_millis(50))
+        .expect("set");
+    storage
+        .update("cooldown_hits", |n: &mut i32| *n += 1)
+        .expect("update");
+    assert!(storage.contains("cooldown"));
+
+    std::thread::sleep(Duration::from_millis(80));
+    assert!(!storage.contains("cooldown"));
+    assert_eq!(storage.get::<bool>("cooldown").expect("get"), None);
+    assert_eq!(storage.keys(), ["cooldown_hits"]);
+
+    // 过期的键在下次打开时清除
+    let path = storage.path();
+    drop(storage);
+    let storage = Storage::open(&path).expect("open");
+    let txn = storage.store.db.begin_read().expect("begin_read");
+    let table = txn.open_table(TABLE).expect("open_table");
+    assert!(table.get("cooldown").expect("get").is_none());
+    assert!(table.get("cooldown_hits").expect("get").is_some());
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn undecodable_entry_survives_reopen() {
+    let dir = temp_dir("bad-entry");
+    let storage = Storage::open(dir.join(STORAGE_FILE)).expect("open");
+    storage.set("good", &1).expect("set");
+    let txn = storage.store.db.begin_write().expect("begin_write");
+    {
+        let mut table = txn.open_table(TABLE).expect("open_table");
+        table.insert("bad", b"not json".as_slice()).expect("insert");
+    }
+    txn.commit().expect("commit");
+
+    let path = storage.path();
+    drop(storage);
+    let storage = Storage::open(&path).expect("open");
+    assert!(matches!(
+        storage.get::<i32>("bad"),
+        Err(StorageError::Corrupted { .. })
+    ));
+    assert_eq!(storage.get::<i32>("good").expect("get"), Some(1));
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn corrupted_file_is_reported() {
+    let dir = temp_dir("corrupted");
+    std::fs::create_dir_all(&dir).expect("create_dir");
+    std::fs::write(dir.join(STORAGE_FILE), "{not json").expect("write");
+
+    let result = Storage::open(dir.join(STORAGE_FILE));
+    assert!(
+        matches!(result, Err(StorageError::Corrupted { .. })),
+        "{:?}",
+        result.err()
+    );
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn concurrent_atomic_writes_do_not_collide() {
+    let dir = temp_dir("atomic");
+    let path = dir.join("data.json");
+
+    std::thread::scope(|s| {
+        for i in 0..8 {
+            let path = &path;
+            s.spawn(move || {
+                for _ in 0..20 {
+                    write_atomic(path, format!("{i}").as_bytes()).expect("write");
+                }
+            });
+        }
+    });
+
+    let data = std::fs::read_to_string(&path).expect("read");
+    assert!(data.parse::<u32>().is_ok_and(|v| v < 8), "{data}");
+    let files: Vec<_> = std::fs::read_dir(&dir)
+        .expect("read_dir")
+        .map(|v| v.expect("entry").file_name())
+        .collect();
+    assert_eq!(files, ["data.json"]);
+
+    let _ = std::fs::remove_dir_all(dir);
+}
@@ -1,5 +1,7 @@
-use crate::plugin::PLUGIN_NAME;
+use crate::bot::account::ACCOUNT;
+use crate::plugin::{PLUGIN_BUILDER, PLUGIN_NAME, PLUGIN_RUNTIME_BOT};
 use ahash::RandomState;
+use futures_util::future::Either;
 use parking_lot::Mutex;
 use std::borrow::BorrowMut;
 use std::collections::HashMap;
@@ -82,6 +84,8 @@ impl TaskAbortHandles {
 /// 2. 插件的监听闭包。
 /// 3. 由 kovi::spawn() 创建的新线程。
 ///
+/// 在这些地方都可以使用 `PluginBuilder` 注册监听。
+///
 /// # panic!
 ///
 /// 如果在 Kovi 管理之外的地方（tokio线程或者系统线程）运行此函数，此函数会 panic!
@@ -93,7 +97,21 @@ where
     PLUGIN_NAME.with(|name| {
         let join = {
             let name = name.clone();
-            tokio::spawn(PLUGIN_NAME.scope(name, future))
+            // 新线程中同样可以注册监听
+            let future = match PLUGIN_BUILDER
+                .try_with(|p| p.runtime_bot.clone())
+                .or_else(|_| PLUGIN_RUNTIME_BOT.try_with(|v| v.clone()))
+            {
+                Ok(runtime_bot) => Either::Left(PLUGIN_RUNTIME_BOT.scope(runtime_bot, future)),
+                Err(_) => Either::Right(future),
+            };
+            // 在监听闭包中创建时，新线程同样可以获取事件所属的账号
+            match ACCOUNT.try_with(|account| account.clone()) {
+                Ok(account) => {
+                    tokio::spawn(PLUGIN_NAME.scope(name, ACCOUNT.scope(account, future)))
+                }
+                Err(_) => tokio::spawn(PLUGIN_NAME.scope(name, future)),
+            }
         };
 
         let about_join = join.abort_handle();
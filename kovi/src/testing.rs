@@ -0,0 +1,327 @@
+//! 插件测试工具
+//!
+//! 需要启用 `testing` feature。[`MockDriver`] 是可以由测试控制的驱动，
+//! [`PluginTest`] 在它之上运行一个 Bot，并在插件的作用域中执行测试，插件的 `main` 可以直接在测试中运行。
+//!
+//! # Examples
+//! ```ignore
+//! use kovi::testing::PluginTest;
+//!
+//! #[kovi::test(driver = kovi_onebot::testing::mock_driver())]
+//! async fn replies_hello(test: PluginTest) {
+//!     crate::main().await;
+//!
+//!     test.driver().send(kovi_onebot::testing::private_msg(2, "hi")).await;
+//!     let sent = test.api().wait("send_msg", 1).await;
+//!     assert_eq!(sent[0].params["message"][0]["data"]["text"], "hello");
+//! }
+//! ```
+
+use crate::bot::{ApiReturn, Bot, SendApi};
+use crate::config::kovi_conf::KoviConf;
+use crate::driver::{AnyError, ApiHandlerResult, Driver, DriverEvent, MessageEventRegister};
+use crate::event::MessageEventTrait;
+use crate::event::id::ID;
+use crate::plugin::{PLUGIN_BUILDER, PLUGIN_NAME, Plugin};
+use crate::{ExitEvent, PluginBuilder, RuntimeBot};
+use futures_util::Stream;
+use parking_lot::{Mutex, RwLock};
+use serde_json::{Value, json};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Notify, mpsc};
+use tokio::task::JoinHandle;
+
+/// [`PluginTest`] 中 Bot 的主管理员
+pub const MAIN_ADMIN: i64 = 1;
+
+/// 等待驱动连接或 API 请求的时间
+const WAIT_TIMEOUT: Duration = Duration::from_secs(3);
+
+type ApiResponder = Arc<dyn Fn(&SendApi) -> Result<ApiReturn, ApiReturn> + Send + Sync>;
+
+/// 成功的 API 返回
+pub fn ok_return(data: Value) -> ApiReturn {
+    ApiReturn {
+        status: "ok".to_string(),
+        retcode: 0,
+        message: None,
+        data,
+    }
+}
+
+/// 失败的 API 返回
+pub fn failed_return(retcode: i32, message: &str) -> ApiReturn {
+    ApiReturn {
+        status: "failed".to_string(),
+        retcode,
+        message: Some(message.to_string()),
+        data: Value::Null,
+    }
+}
+
+/// 记录经过驱动的每一个 API，并按 action 给出预设的返回
+///
+/// 没有预设返回的 API 返回 `ok_return(json!({}))`。
+#[derive(Clone, Default)]
+pub struct ApiRecorder {
+    requests: Arc<Mutex<Vec<SendApi>>>,
+    responders: Arc<Mutex<ahash::HashMap<String, ApiResponder>>>,
+    arrived: Arc<Notify>,
+}
+
+impl ApiRecorder {
+    /// 之后 `action` 的 API 都返回 `ret`
+    pub fn respond(&self, action: &str, ret: Result<ApiReturn, ApiReturn>) {
+        self.respond_with(action, move |_| ret.clone());
+    }
+
+    /// 之后 `action` 的 API 由 `f` 根据请求给出返回
+    pub fn respond_with(
+        &self,
+        action: &str,
+        f: impl Fn(&SendApi) -> Result<ApiReturn, ApiReturn> + Send + Sync + 'static,
+    ) {
+        self.responders
+            .lock()
+            .insert(action.to_string(), Arc::new(f));
+    }
+
+    /// 到目前为止的所有 API，按发出顺序
+    pub fn requests(&self) -> Vec<SendApi> {
+        self.requests.lock().clone()
+    }
+
+    /// 到目前为止 `action` 的 API，按发出顺序
+    pub fn requests_of(&self, action: &str) -> Vec<SendApi> {
+        self.requests
+            .lock()
+            .iter()
+            .filter(|api| api.action == action)
+            .cloned()
+            .collect()
+    }
+
+    /// 等待至少 `n` 个 `action` 的 API，超时会 panic
+    pub async fn wait(&self, action: &str, n: usize) -> Vec<SendApi> {
+        let wait = async {
+            loop {
+                let arrived = self.arrived.notified();
+                let requests = self.requests_of(action);
+                if requests.len() >= n {
+                    return requests;
+                }
+                arrived.await;
+            }
+        };
+        match tokio::time::timeout(WAIT_TIMEOUT, wait).await {
+            Ok(requests) => requests,
+            Err(_) => panic!(
+                "Timed out waiting for {n} '{action}' api, got {:?}",
+                self.requests_of(action)
+            ),
+        }
+    }
+
+    /// 清空已记录的 API
+    pub fn clear(&self) {
+        self.requests.lock().clear();
+    }
+
+    fn record(&self, api: SendApi) -> Result<ApiReturn, ApiReturn> {
+        let responder = self.responders.lock().get(&api.action).cloned();
+        let ret = match responder {
+            Some(f) => f(&api),
+            None => Ok(ok_return(json!({}))),
+        };
+        self.requests.lock().push(api);
+        self.arrived.notify_waiters();
+        ret
+    }
+}
+
+/// 由测试控制的驱动
+///
+/// 通过对应的 [`MockHandle`] 推送事件、查看发出的 API。
+pub struct MockDriver {
+    event_rx: Mutex<Option<mpsc::Receiver<Result<DriverEvent, AnyError>>>>,
+    register: fn() -> MessageEventRegister,
+    api: ApiRecorder,
+    connected: Arc<Notify>,
+}
+
+/// 控制 [`MockDriver`]
+#[derive(Clone)]
+pub struct MockHandle {
+    event_tx: mpsc::Sender<Result<DriverEvent, AnyError>>,
+    api: ApiRecorder,
+    connected: Arc<Notify>,
+}
+
+impl MockDriver {
+    /// 以 `M` 作为消息事件的驱动
+    pub fn new<M: MessageEventTrait>() -> (MockDriver, MockHandle) {
+        Self::with_register(MessageEventRegister::register::<M>)
+    }
+
+    /// 没有消息事件的驱动，插件仍然可以监听自己解析的事件
+    pub fn without_message_event() -> (MockDriver, MockHandle) {
+        Self::with_register(MessageEventRegister::none)
+    }
+
+    fn with_register(register: fn() -> MessageEventRegister) -> (MockDriver, MockHandle) {
+        let (event_tx, event_rx) = mpsc::channel(64);
+        let api = ApiRecorder::default();
+        let connected = Arc::new(Notify::new());
+        let driver = MockDriver {
+            event_rx: Mutex::new(Some(event_rx)),
+            register,
+            api: api.clone(),
+            connected: connected.clone(),
+        };
+        let handle = MockHandle {
+            event_tx,
+            api,
+            connected,
+        };
+        (driver, handle)
+    }
+}
+
+#[async_trait::async_trait]
+impl Driver for MockDriver {
+    async fn event_channel(
+        &self,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<DriverEvent, AnyError>> + Send>>, AnyError> {
+        let rx = self
+            .event_rx
+            .lock()
+            .take()
+            .ok_or("MockDriver can only be connected once")?;
+        self.connected.notify_one();
+        let stream = futures_util::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|item| (item, rx))
+        });
+        Ok(Box::pin(stream))
+    }
+
+    fn api_handler(&self, value: SendApi) -> ApiHandlerResult {
+        let ret = self.api.record(value);
+        Box::pin(async move { Ok(ret) })
+    }
+
+    fn message_event_register(&self) -> MessageEventRegister {
+        (self.register)()
+    }
+}
+
+impl MockHandle {
+    /// 推送一个事件
+    pub async fn send(&self, event: Value) {
+        self.event_tx
+            .send(Ok(DriverEvent::Normal(event)))
+            .await
+            .expect("MockDriver was dropped");
+    }
+
+    /// 推送驱动的退出事件，Bot 随之退出
+    pub async fn exit(&self) {
+        self.event_tx
+            .send(Ok(DriverEvent::Exit))
+            .await
+            .expect("MockDriver was dropped");
+    }
+
+    /// 经过驱动的 API
+    pub fn api(&self) -> &ApiRecorder {
+        &self.api
+    }
+
+    /// 等待 Bot 连接驱动，超时会 panic
+    pub async fn wait_connected(&self) {
+        tokio::time::timeout(WAIT_TIMEOUT, self.connected.notified())
+            .await
+            .expect("MockDriver was not connected");
+    }
+}
+
+/// 在 [`MockDriver`] 上运行的 Bot，测试在其中一个插件的作用域中执行
+///
+/// 通常由 [`#[kovi::test]`](crate::test) 创建。离开作用域时 Bot 停止运行。
+pub struct PluginTest {
+    name: String,
+    bot: Arc<RwLock<Bot>>,
+    driver: MockHandle,
+    run: JoinHandle<ExitEvent>,
+}
+
+impl PluginTest {
+    /// 启动 Bot，并挂载名为 `name` 的空插件，测试中注册的监听都属于这个插件
+    pub async fn start(name: &str, (driver, handle): (MockDriver, MockHandle)) -> PluginTest {
+        let conf = KoviConf::new(ID::new(MAIN_ADMIN), None, false);
+        let mut bot = Bot::build(conf, driver);
+        bot.mount_plugin(Plugin::new(name, "0.0.0", Arc::new(|| Box::pin(async {}))));
+        let bot = Arc::new(RwLock::new(bot));
+
+        let connected = handle.wait_connected();
+        let run = tokio::spawn(Bot::run_bot_inner(bot.clone()));
+        connected.await;
+
+        PluginTest {
+            name: name.to_string(),
+            bot,
+            driver: handle,
+            run,
+        }
+    }
+
+    /// 在插件的作用域中执行 `f`，其中可以使用 [`PluginBuilder`]
+    pub fn scope<F: Future>(&self, f: F) -> impl Future<Output = F::Output> + use<F> {
+        PLUGIN_NAME.scope(
+            Arc::new(self.name.clone()),
+            PLUGIN_BUILDER.scope(self.builder(), f),
+        )
+    }
+
+    /// 在插件的作用域中执行 `f`，`f` 取得此 `PluginTest`
+    pub async fn run<F: Future>(self, f: impl FnOnce(PluginTest) -> F) -> F::Output {
+        let builder = self.builder();
+        PLUGIN_NAME
+            .scope(
+                Arc::new(self.name.clone()),
+                PLUGIN_BUILDER.scope(builder, f(self)),
+            )
+            .await
+    }
+
+    /// 测试插件的 [`RuntimeBot`]
+    pub fn runtime_bot(&self) -> Arc<RuntimeBot> {
+        self.builder().runtime_bot
+    }
+
+    fn builder(&self) -> PluginBuilder {
+        let api_tx = self.bot.read().default_api_tx();
+        PluginBuilder::new(self.name.clone(), self.bot.clone(), api_tx)
+    }
+
+    /// 控制驱动
+    pub fn driver(&self) -> &MockHandle {
+        &self.driver
+    }
+
+    /// 经过驱动的 API
+    pub fn api(&self) -> &ApiRecorder {
+        self.driver.api()
+    }
+}
+
+impl Drop for PluginTest {
+    fn drop(&mut self) {
+        self.run.abort();
+        for task in self.bot.read().run_abort.iter() {
+            task.abort();
+        }
+    }
+}
@@ -27,6 +27,9 @@ pub(crate) type KoviAsyncFn = dyn Fn() -> Pin<Box<dyn Future<Output = ()> + Send
 
 pub type PinFut = Pin<Box<dyn Future<Output = ()> + Send>>;
 
+/// 监听的返回，错误已经转为文本
+pub(crate) type ListenFut = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+
 pub type NoArgsFn = Arc<dyn Fn() -> PinFut + Send + Sync>;
 
 pub type ApiOneshotSender = oneshot::Sender<Result<ApiReturn, ApiReturn>>;
@@ -1,19 +1,13 @@
+use crate::config::paths::KoviPaths;
 use serde::Serialize;
 use serde::de::DeserializeOwned;
-use std::fs::{self, File};
-use std::io::{Read, Write};
+use std::fs::File;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 
+/// 先写入临时文件再重命名，写入中途崩溃不会损坏原有的文件
 fn save_data(data: &[u8], file_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
-    if let Some(parent) = file_path.parent()
-        && !parent.exists()
-    {
-        fs::create_dir_all(parent)?;
-    }
-
-    let mut file = File::create(file_path)?;
-    file.write_all(data)?;
-
+    crate::storage::write_atomic(file_path, data)?;
     Ok(())
 }
 
@@ -85,9 +79,7 @@ where
 ///
 /// 那么这个插件的数据目录就是 `/path/data/`
 pub fn get_data_root_path() -> PathBuf {
-    let mut current_dir = std::env::current_dir().expect("Get current directory failed");
-    current_dir.push("data");
-    current_dir
+    KoviPaths::current().data_dir().to_path_buf()
 }
 
 // /// 计算pskey值
@@ -0,0 +1,277 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use kovi::bot::account::{Account, AccountEvent};
+use kovi::bot::runtimebot::CanSendApi;
+use kovi::driver::DriverEvent;
+use kovi::event::{Event, InternalEvent};
+use kovi::serde_json::{Value, json};
+use kovi::types::ApiAndOptOneshot;
+use kovi::{Bot, PluginBuilder};
+use tokio::sync::mpsc;
+
+use crate::harness::{MockDriver, RunOutcome, conf, observe_run, status_file_guard, wait_ready};
+
+/// 任意驱动事件
+struct RawEvent {
+    value: Arc<Value>,
+    api_tx: mpsc::Sender<ApiAndOptOneshot>,
+}
+
+impl Event for RawEvent {
+    fn de(
+        event: &InternalEvent,
+        _: &kovi::bot::BotInformation,
+        api_tx: &mpsc::Sender<ApiAndOptOneshot>,
+    ) -> Option<Self> {
+        let InternalEvent::DriverEvent(value) = event else {
+            return None;
+        };
+        Some(RawEvent {
+            value: value.clone(),
+            api_tx: api_tx.clone(),
+        })
+    }
+}
+
+impl CanSendApi for RawEvent {
+    fn __get_api_tx(&self) -> &mpsc::Sender<ApiAndOptOneshot> {
+        &self.api_tx
+    }
+}
+
+type Received = Arc<std::sync::Mutex<Vec<(String, String)>>>;
+
+/// 记录每个事件的账号，并按事件内容调用 API
+fn probe_plugin(received: Received, main_runs: Arc<AtomicUsize>) -> kovi::plugin::Plugin {
+    kovi::plugin::Plugin::new(
+        "probe",
+        "0.0.0",
+        Arc::new(move || {
+            let received = received.clone();
+            let main_runs = main_runs.clone();
+            Box::pin(async move {
+                main_runs.fetch_add(1, Ordering::SeqCst);
+                let bot = PluginBuilder::get_runtime_bot();
+                PluginBuilder::on(move |event: Arc<RawEvent>| {
+                    let received = received.clone();
+                    let bot = bot.clone();
+                    async move {
+                        let account = Account::current().expect("account in listener");
+                        let text = event.value["text"].as_str().unwrap_or_default().to_string();
+                        received
+                            .lock()
+                            .expect("received poisoned")
+                            .push((account.name().to_string(), text.clone()));
+
+                        match text.as_str() {
+                            "reply" => event.send_api("reply", json!({})),
+                            "for_event" => bot.for_event(&*event).send_api("for_event", json!({})),
+                            "to_second" => bot
+                                .account("second")
+                                .expect("second account")
+                                .send_api("to_second", json!({})),
+                            "default" => bot.send_api("default", json!({})),
+                            _ => {}
+                        }
+                    }
+                });
+            })
+        }),
+    )
+}
+
+fn event(text: &str) -> Result<DriverEvent, kovi::driver::AnyError> {
+    Ok(DriverEvent::Normal(json!({ "text": text })))
+}
+
+async fn wait_until(mut check: impl FnMut() -> bool) {
+    tokio::time::timeout(Duration::from_secs(3), async {
+        while !check() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("condition was not met in time");
+}
+
+/// 每个事件都带有收到它的账号，事件上的 API 通过收到事件的账号发送。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn events_carry_receiving_account() {
+    let (first, first_tx, first_ready) = MockDriver::new();
+    let (second, second_tx, second_ready) = MockDriver::new();
+    let first_calls = first.api_calls();
+    let second_calls = second.api_calls();
+
+    let received: Received = Default::default();
+    let main_runs = Arc::new(AtomicUsize::new(0));
+    let mut bot = Bot::build(conf(), first).add_account("second", second);
+    bot.mount_plugin(probe_plugin(received.clone(), main_runs.clone()));
+
+    let first_wait = first_ready.notified();
+    let second_wait = second_ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(first_wait).await;
+    wait_ready(second_wait).await;
+    // 等待插件注册监听
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    first_tx.send(event("reply")).await.expect("send");
+    second_tx.send(event("reply")).await.expect("send");
+    second_tx.send(event("for_event")).await.expect("send");
+    first_tx.send(event("to_second")).await.expect("send");
+    second_tx.send(event("default")).await.expect("send");
+
+    wait_until(|| received.lock().expect("received").len() == 5).await;
+    wait_until(|| first_calls.lock().expect("calls").len() == 2).await;
+    wait_until(|| second_calls.lock().expect("calls").len() == 3).await;
+
+    let mut received = received.lock().expect("received").clone();
+    received.sort();
+    assert_eq!(
+        received,
+        [
+            ("default".to_string(), "reply".to_string()),
+            ("default".to_string(), "to_second".to_string()),
+            ("second".to_string(), "default".to_string()),
+            ("second".to_string(), "for_event".to_string()),
+            ("second".to_string(), "reply".to_string()),
+        ]
+    );
+
+    let mut first_calls = first_calls.lock().expect("calls").clone();
+    first_calls.sort();
+    assert_eq!(first_calls, ["default", "reply"]);
+    let mut second_calls = second_calls.lock().expect("calls").clone();
+    second_calls.sort();
+    assert_eq!(second_calls, ["for_event", "reply", "to_second"]);
+
+    // 插件只运行一次，而不是每个账号一份
+    assert_eq!(main_runs.load(Ordering::SeqCst), 1);
+
+    handle.abort();
+}
+
+/// AccountEvent 中的账号在 `tokio::spawn()` 创建的线程中依然可以取得。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn account_event_keeps_account_across_spawn() {
+    let (first, first_tx, first_ready) = MockDriver::new();
+    let (second, second_tx, second_ready) = MockDriver::new();
+    let second_calls = second.api_calls();
+
+    let received: Received = Default::default();
+    let mut bot = Bot::build(conf(), first).add_account("second", second);
+    bot.mount_plugin(kovi::plugin::Plugin::new(
+        "probe",
+        "0.0.0",
+        Arc::new({
+            let received = received.clone();
+            move || {
+                let received = received.clone();
+                Box::pin(async move {
+                    PluginBuilder::on(move |event: Arc<AccountEvent<RawEvent>>| {
+                        let received = received.clone();
+                        async move {
+                            tokio::spawn(async move {
+                                assert!(Account::current().is_none());
+                                let text = event.value["text"].as_str().unwrap_or_default();
+                                received
+                                    .lock()
+                                    .expect("received poisoned")
+                                    .push((event.account().name().to_string(), text.to_string()));
+                                event.send_api("reply", json!({}));
+                            });
+                        }
+                    });
+                })
+            }
+        }),
+    ));
+
+    let first_wait = first_ready.notified();
+    let second_wait = second_ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(first_wait).await;
+    wait_ready(second_wait).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    first_tx.send(event("a")).await.expect("send");
+    second_tx.send(event("b")).await.expect("send");
+
+    wait_until(|| received.lock().expect("received").len() == 2).await;
+    wait_until(|| second_calls.lock().expect("calls").len() == 1).await;
+    let mut received = received.lock().expect("received").clone();
+    received.sort();
+    assert_eq!(
+        received,
+        [
+            ("default".to_string(), "a".to_string()),
+            ("second".to_string(), "b".to_string()),
+        ]
+    );
+
+    handle.abort();
+}
+
+/// 一个账号的驱动退出时 Bot 继续运行，所有账号都退出后才退出。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn bot_exits_after_all_accounts_exit() {
+    let _guard = status_file_guard();
+    let (first, first_tx, first_ready) = MockDriver::new();
+    let (second, second_tx, second_ready) = MockDriver::new();
+
+    let bot = Bot::build(conf(), first).add_account("second", second);
+    let first_wait = first_ready.notified();
+    let second_wait = second_ready.notified();
+    let mut handle = tokio::spawn(bot.run());
+    wait_ready(first_wait).await;
+    wait_ready(second_wait).await;
+
+    drop(second_tx);
+    let still_running = tokio::time::timeout(Duration::from_millis(300), &mut handle).await;
+    assert!(
+        still_running.is_err(),
+        "one account exiting must not stop the bot"
+    );
+
+    drop(first_tx);
+    assert_eq!(observe_run(handle).await, RunOutcome::ExitedFromDrive);
+}
+
+/// 找不到账号时返回 AccountNotFound。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn unknown_account_is_an_error() {
+    let (driver, _tx, ready) = MockDriver::new();
+    let result: Arc<std::sync::Mutex<Option<String>>> = Default::default();
+
+    let mut bot = Bot::build(conf(), driver);
+    bot.mount_plugin(kovi::plugin::Plugin::new(
+        "probe",
+        "0.0.0",
+        Arc::new({
+            let result = result.clone();
+            move || {
+                let result = result.clone();
+                Box::pin(async move {
+                    let bot = PluginBuilder::get_runtime_bot();
+                    let err = bot.account("missing").err().map(|e| e.to_string());
+                    let accounts = bot.get_accounts().expect("accounts");
+                    assert_eq!(accounts, [Account::new("default")]);
+                    *result.lock().expect("result") = err;
+                })
+            }
+        }),
+    ));
+
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    wait_until(|| result.lock().expect("result").is_some()).await;
+
+    assert_eq!(
+        result.lock().expect("result").as_deref(),
+        Some("Account not found: missing")
+    );
+    handle.abort();
+}
@@ -0,0 +1,187 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use kovi::command::args::{Arg, Rest};
+use kovi::command::{Command, CommandSet, Permission};
+use kovi::event::RepliableEvent;
+use kovi::event::id::ID;
+use kovi::{Bot, PluginBuilder};
+
+use crate::harness::{
+    MockDriver, TextMsg, conf, status_file_guard, text_msg, wait_ready, wait_replies,
+};
+
+fn command_plugin() -> kovi::plugin::Plugin {
+    kovi::plugin::Plugin::new(
+        "commands",
+        "0.0.0",
+        Arc::new(|| {
+            Box::pin(async {
+                PluginBuilder::commands(
+                    CommandSet::<TextMsg>::new()
+                        .prefixes(["/", "!"])
+                        .command(
+                            Command::new("echo")
+                                .alias("say")
+                                .about("Repeat text")
+                                .arg(Arg::rest("text")),
+                            |event, args| async move {
+                                event.reply(args.str("text").unwrap_or_default());
+                            },
+                        )
+                        .command(
+                            Command::new("add").arg(Arg::int("a")).arg(Arg::int("b")),
+                            |event, args| async move {
+                                let sum = args.int("a").unwrap_or(0) + args.int("b").unwrap_or(0);
+                                event.reply(sum.to_string());
+                            },
+                        )
+                        .command(
+                            Command::new("ban")
+                                .arg(Arg::id("user"))
+                                .permission(Permission::Admin),
+                            |event, args| async move {
+                                let user = args.id("user").map(ToString::to_string);
+                                event.reply(format!("banned {}", user.unwrap_or_default()));
+                            },
+                        ),
+                );
+            })
+        }),
+    )
+}
+
+/// 命令按前缀与别名匹配，参数错误、权限不足与帮助都通过回复告知发送者。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn commands_parse_and_reply() {
+    let _guard = status_file_guard();
+    let (driver, tx, ready) = MockDriver::new();
+    let requests = driver.api_requests();
+
+    // conf() 中的主管理员为 1
+    let mut bot = Bot::build(conf(), driver);
+    bot.mount_plugin(command_plugin());
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let inputs = [
+        (2, "/echo hello  world"),
+        (2, "!say hi"),
+        (2, "/add 1 2"),
+        (2, "/add 1 x"),
+        (2, "/ban 5"),
+        (1, "/ban 5"),
+        (2, "/help"),
+        (2, "/help say"),
+        (2, "not a command"),
+        (2, "/unknown"),
+        (1, "/ban \"foo"),
+        (2, "/unknown \"foo"),
+    ];
+    for (user_id, text) in inputs {
+        tx.send(text_msg(user_id, text)).await.expect("send");
+    }
+
+    let expected = [
+        "hello  world",
+        "hi",
+        "3",
+        "Argument <b> must be an integer, got `x`\nUsage: /add <a> <b>",
+        "You do not have permission to use this command.",
+        "banned 5",
+        "Commands:\n/echo <text...> - Repeat text\n/add <a> <b>\n/help [command] - Show help",
+        "Usage: /echo <text...>\nRepeat text\nAliases: /say",
+        "Unclosed quote\nUsage: /ban <user>",
+    ];
+    let mut replies = wait_replies(&requests, expected.len()).await;
+    replies.sort();
+    let mut expected = expected.map(String::from).to_vec();
+    expected.sort();
+    assert_eq!(replies, expected);
+
+    handle.abort();
+}
+
+/// 复读
+///
+/// 这一段不会出现在帮助中。
+#[kovi::command("echo", alias = "say")]
+async fn echo_command(event: Arc<TextMsg>, text: Rest) {
+    event.reply(text.to_string());
+}
+
+#[kovi::command(about = "Add two numbers")]
+async fn add(event: Arc<TextMsg>, a: i64, b: Option<i64>) {
+    event.reply((a + b.unwrap_or(10)).to_string());
+}
+
+#[kovi::command("kick", main_admin)]
+async fn kick(event: Arc<TextMsg>, user: ID, _reason: Option<String>) {
+    event.reply(format!("kicked {user}"));
+}
+
+fn macro_plugin() -> kovi::plugin::Plugin {
+    kovi::plugin::Plugin::new(
+        "macro-commands",
+        "0.0.0",
+        Arc::new(|| {
+            Box::pin(async {
+                PluginBuilder::commands(CommandSet::new().register(echo_command()).register(add()));
+                PluginBuilder::command(kick());
+            })
+        }),
+    )
+}
+
+/// `#[kovi::command]` 由函数签名生成参数，由文档注释生成说明。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn command_macro_builds_commands() {
+    let def = echo_command();
+    assert_eq!(def.command.usage("/"), "/echo <text...>");
+    assert_eq!(def.command.about, "复读");
+    assert_eq!(def.command.aliases, ["say"]);
+    let def = kick();
+    assert_eq!(def.command.usage("/"), "/kick <user> [reason]");
+    assert_eq!(def.command.permission, Permission::MainAdmin);
+
+    let _guard = status_file_guard();
+    let (driver, tx, ready) = MockDriver::new();
+    let requests = driver.api_requests();
+
+    let mut bot = Bot::build(conf(), driver);
+    bot.mount_plugin(macro_plugin());
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let inputs = [
+        (2, "/say a  b"),
+        (2, "/add 1"),
+        (2, "/add 1 2"),
+        (2, "/kick 3"),
+        (1, "/kick 3 spam"),
+        (2, "/help"),
+    ];
+    for (user_id, text) in inputs {
+        tx.send(text_msg(user_id, text)).await.expect("send");
+    }
+
+    let expected = [
+        "a  b",
+        "11",
+        "3",
+        "You do not have permission to use this command.",
+        "kicked 3",
+        "Commands:\n/echo <text...> - 复读\n/add <a> [b] - Add two numbers\n/help [command] - Show help",
+    ];
+    let mut replies = wait_replies(&requests, expected.len()).await;
+    replies.sort();
+    let mut expected = expected.map(String::from).to_vec();
+    expected.sort();
+    assert_eq!(replies, expected);
+
+    handle.abort();
+}
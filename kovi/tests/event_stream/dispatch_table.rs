@@ -0,0 +1,153 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use kovi::bot::runtimebot::kovi_api::{AccessControlMode, SetAccessControlList};
+use kovi::event::id::ID;
+use kovi::event::{MessageEventTrait, RepliableEvent};
+use kovi::{Bot, PluginBuilder};
+
+use crate::harness::{
+    MockDriver, TextMsg, conf, status_file_guard, text_msg, wait_ready, wait_replies,
+};
+
+/// 回复 `echo: <文本>`
+fn echo_plugin() -> kovi::plugin::Plugin {
+    kovi::plugin::Plugin::new(
+        "echo",
+        "0.0.0",
+        Arc::new(|| {
+            Box::pin(async {
+                PluginBuilder::on(|event: Arc<TextMsg>| async move {
+                    let text = event.get_message().to_human_string();
+                    event.reply(format!("echo: {text}"));
+                });
+            })
+        }),
+    )
+}
+
+/// 收到 `acl` 时只允许用户 2 使用 echo，收到 `off` 时关闭 echo，收到 `on` 时开启 echo
+fn admin_plugin() -> kovi::plugin::Plugin {
+    kovi::plugin::Plugin::new(
+        "admin",
+        "0.0.0",
+        Arc::new(|| {
+            Box::pin(async {
+                let bot = PluginBuilder::get_runtime_bot();
+                PluginBuilder::on(move |event: Arc<TextMsg>| {
+                    let bot = bot.clone();
+                    async move {
+                        match event.get_message().to_human_string().as_str() {
+                            "acl" => {
+                                bot.set_plugin_access_control("echo", true).expect("acl");
+                                bot.set_plugin_access_control_mode(
+                                    "echo",
+                                    AccessControlMode::WhiteList,
+                                )
+                                .expect("acl");
+                                bot.set_plugin_access_control_list(
+                                    "echo",
+                                    false,
+                                    SetAccessControlList::Add(ID::new(2)),
+                                )
+                                .expect("acl");
+                            }
+                            "off" => {
+                                let join = bot.disable_plugin("echo").expect("disable");
+                                if let Some(join) = join {
+                                    join.await.expect("disable");
+                                }
+                            }
+                            "on" => bot.enable_plugin("echo").expect("enable"),
+                            _ => return,
+                        }
+                        event.reply("admin: done");
+                    }
+                });
+            })
+        }),
+    )
+}
+
+/// 运行中修改名单、关闭和开启插件后，之后的事件按新的设置分发。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn runtime_plugin_changes_update_dispatch() {
+    let _guard = status_file_guard();
+    let (driver, tx, ready) = MockDriver::new();
+    let requests = driver.api_requests();
+
+    let mut bot = Bot::build(conf(), driver);
+    bot.mount_plugin(echo_plugin());
+    bot.mount_plugin(admin_plugin());
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    tx.send(text_msg(3, "a")).await.expect("send");
+    assert_eq!(wait_replies(&requests, 1).await, ["echo: a"]);
+
+    tx.send(text_msg(2, "acl")).await.expect("send");
+    let replies = wait_replies(&requests, 3).await;
+    assert!(replies[1..].contains(&"admin: done".to_string()));
+
+    tx.send(text_msg(3, "b")).await.expect("send");
+    tx.send(text_msg(2, "c")).await.expect("send");
+    let replies = wait_replies(&requests, 4).await;
+    assert_eq!(replies[3], "echo: c");
+
+    // 用户 3 不在名单中，echo 不会回复
+    tx.send(text_msg(3, "off")).await.expect("send");
+    let replies = wait_replies(&requests, 5).await;
+    assert_eq!(replies[4], "admin: done");
+    tx.send(text_msg(2, "d")).await.expect("send");
+    tx.send(text_msg(3, "on")).await.expect("send");
+    let replies = wait_replies(&requests, 6).await;
+    assert_eq!(replies[5], "admin: done");
+
+    // 重新开启后 echo 的 main 再次注册监听
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    tx.send(text_msg(2, "e")).await.expect("send");
+    let replies = wait_replies(&requests, 7).await;
+    assert_eq!(replies[6], "echo: e");
+
+    handle.abort();
+}
+
+/// `main` 返回前注册的监听同样能收到事件。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn listeners_work_before_main_returns() {
+    let _guard = status_file_guard();
+    let (driver, tx, ready) = MockDriver::new();
+    let requests = driver.api_requests();
+
+    let plugin = kovi::plugin::Plugin::new(
+        "pending",
+        "0.0.0",
+        Arc::new(|| {
+            Box::pin(async {
+                for i in 0..3 {
+                    PluginBuilder::on_with_priority(i, move |event: Arc<TextMsg>| async move {
+                        if i == 0 {
+                            let text = event.get_message().to_human_string();
+                            event.reply(format!("pending: {text}"));
+                        }
+                    });
+                }
+                std::future::pending::<()>().await;
+            })
+        }),
+    );
+
+    let mut bot = Bot::build(conf(), driver);
+    bot.mount_plugin(plugin);
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    tx.send(text_msg(2, "a")).await.expect("send");
+    assert_eq!(wait_replies(&requests, 1).await, ["pending: a"]);
+
+    handle.abort();
+}
@@ -0,0 +1,98 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use kovi::bot::dry_run::dry_run_command;
+use kovi::command::CommandSet;
+use kovi::event::{MessageEventTrait, RepliableEvent};
+use kovi::serde_json::json;
+use kovi::{Bot, PluginBuilder};
+
+use crate::harness::{
+    MockDriver, TextMsg, conf, status_file_guard, text_msg, wait_ready, wait_replies,
+};
+
+/// 主管理员通过 `/dry-run` 切换试运行，其余消息发出 `kick`、`send_like` 与 `get_members`，
+/// 并回复 `kick` 的结果与 `send_like` 是否得到了消息 ID
+fn admin_plugin() -> kovi::plugin::Plugin {
+    kovi::plugin::Plugin::new(
+        "admin",
+        "0.0.0",
+        Arc::new(|| {
+            Box::pin(async {
+                PluginBuilder::commands(CommandSet::<TextMsg>::new().register(dry_run_command()));
+                let bot = PluginBuilder::get_runtime_bot();
+                PluginBuilder::on(move |event: Arc<TextMsg>| {
+                    let bot = bot.clone();
+                    async move {
+                        let text = event.get_message().to_human_string();
+                        if text.starts_with('/') {
+                            return;
+                        }
+                        let kick = bot.send_api_return("kick", json!({ "user_id": 3 })).await;
+                        let like = bot
+                            .send_api_return("send_like", json!({ "user_id": 3 }))
+                            .await;
+                        bot.send_api("get_members", json!({}));
+                        let like_has_id = like.is_ok_and(|v| !v.data["message_id"].is_null());
+                        event.reply(format!("kick: {}, like: {like_has_id}", kick.is_ok()));
+                    }
+                });
+            })
+        }),
+    )
+}
+
+/// 试运行时配置的 API 不会到达驱动而是直接成功，其余 API 照常发出，主管理员可以随时切换。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn dry_run_intercepts_mutating_api() {
+    let _guard = status_file_guard();
+    let (driver, tx, ready) = MockDriver::new();
+    let requests = driver.api_requests();
+    let calls = driver.api_calls();
+
+    let mut bot = Bot::build(conf(), driver)
+        .set_dry_run_actions(["kick", "send_like"])
+        .set_dry_run(true);
+    bot.mount_plugin(admin_plugin());
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let actions = || -> Vec<String> {
+        calls
+            .lock()
+            .expect("calls")
+            .iter()
+            .filter(|v| *v != "reply")
+            .cloned()
+            .collect()
+    };
+
+    tx.send(text_msg(2, "a")).await.expect("send");
+    assert_eq!(
+        wait_replies(&requests, 1).await,
+        ["kick: true, like: false"]
+    );
+    assert_eq!(actions(), ["get_members"]);
+
+    // 只有主管理员可以关闭
+    tx.send(text_msg(2, "/dry-run off")).await.expect("send");
+    assert_eq!(
+        wait_replies(&requests, 2).await[1],
+        "You do not have permission to use this command."
+    );
+
+    tx.send(text_msg(1, "/dry-run off")).await.expect("send");
+    assert_eq!(wait_replies(&requests, 3).await[2], "dry-run: off");
+
+    tx.send(text_msg(2, "b")).await.expect("send");
+    assert_eq!(
+        wait_replies(&requests, 4).await[3],
+        "kick: true, like: false"
+    );
+    let actions = actions();
+    assert_eq!(actions[1..], ["kick", "send_like", "get_members"]);
+
+    handle.abort();
+}
@@ -0,0 +1,180 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use kovi::bot::failure::{FailureKind, FailurePolicy, PluginFailureEvent};
+use kovi::event::{MessageEventTrait, RepliableEvent};
+use kovi::{Bot, PluginBuilder};
+
+use crate::harness::{
+    MockDriver, TextMsg, conf, status_file_guard, text_msg, wait_ready, wait_replies,
+};
+
+/// 收到 `err` 时返回错误，收到 `panic` 时 panic，否则回复 `flaky: <文本>`
+fn flaky_plugin() -> kovi::plugin::Plugin {
+    kovi::plugin::Plugin::new(
+        "flaky",
+        "0.0.0",
+        Arc::new(|| {
+            Box::pin(async {
+                PluginBuilder::try_on(|event: Arc<TextMsg>| async move {
+                    let text = event.get_message().to_human_string();
+                    match text.as_str() {
+                        "err" => return Err("bad input"),
+                        "panic" => panic!("boom"),
+                        _ => event.reply(format!("flaky: {text}")),
+                    }
+                    Ok(())
+                });
+            })
+        }),
+    )
+}
+
+type Seen = Arc<std::sync::Mutex<Vec<(String, FailureKind, bool)>>>;
+
+/// 记录收到的 [`PluginFailureEvent`]
+fn watcher_plugin(seen: Seen) -> kovi::plugin::Plugin {
+    kovi::plugin::Plugin::new(
+        "watcher",
+        "0.0.0",
+        Arc::new(move || {
+            let seen = seen.clone();
+            Box::pin(async move {
+                PluginBuilder::on(move |event: Arc<PluginFailureEvent>| {
+                    let seen = seen.clone();
+                    async move {
+                        seen.lock().expect("seen").push((
+                            event.plugin.clone(),
+                            event.kind.clone(),
+                            event.disabled,
+                        ));
+                    }
+                });
+            })
+        }),
+    )
+}
+
+async fn wait_seen(seen: &Seen, n: usize) {
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while seen.lock().expect("seen").len() < n {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("failure events did not arrive");
+}
+
+/// 监听的错误与 panic 记在插件名下并发出事件，达到次数后插件被关闭，其他插件不受影响。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn failures_are_reported_and_disable_plugin() {
+    let _guard = status_file_guard();
+    let (driver, tx, ready) = MockDriver::new();
+    let requests = driver.api_requests();
+    let seen: Seen = Default::default();
+
+    let mut bot = Bot::build(conf(), driver).set_failure_policy(FailurePolicy {
+        max_failures: 2,
+        window: Duration::from_secs(60),
+        notify_main_admin: true,
+    });
+    bot.mount_plugin(flaky_plugin());
+    bot.mount_plugin(watcher_plugin(seen.clone()));
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    tx.send(text_msg(2, "a")).await.expect("send");
+    assert_eq!(wait_replies(&requests, 1).await, ["flaky: a"]);
+
+    tx.send(text_msg(2, "err")).await.expect("send");
+    wait_seen(&seen, 1).await;
+    tx.send(text_msg(2, "panic")).await.expect("send");
+    wait_seen(&seen, 2).await;
+
+    assert_eq!(
+        *seen.lock().expect("seen"),
+        [
+            (
+                "flaky".to_string(),
+                FailureKind::Error("bad input".to_string()),
+                false
+            ),
+            (
+                "flaky".to_string(),
+                FailureKind::Panic("boom".to_string()),
+                true
+            ),
+        ]
+    );
+
+    // 每次失败都私聊通知主管理员
+    let notified: Vec<_> = requests
+        .lock()
+        .expect("requests")
+        .iter()
+        .filter(|api| api.action == "notify")
+        .map(|api| api.params["user_id"].clone())
+        .collect();
+    assert_eq!(notified, [1, 1]);
+
+    // 关闭后 flaky 不再回复
+    tx.send(text_msg(2, "b")).await.expect("send");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(wait_replies(&requests, 1).await, ["flaky: a"]);
+
+    handle.abort();
+}
+
+/// 处理 PluginFailureEvent 时的失败不再发出事件或通知，不会循环。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn failures_while_handling_failure_event_do_not_loop() {
+    let _guard = status_file_guard();
+    let (driver, tx, ready) = MockDriver::new();
+    let requests = driver.api_requests();
+    let handled = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+    let mut bot = Bot::build(conf(), driver).set_failure_policy(FailurePolicy {
+        notify_main_admin: true,
+        ..Default::default()
+    });
+    bot.mount_plugin(flaky_plugin());
+    bot.mount_plugin(kovi::plugin::Plugin::new(
+        "failing-watcher",
+        "0.0.0",
+        Arc::new({
+            let handled = handled.clone();
+            move || {
+                let handled = handled.clone();
+                Box::pin(async move {
+                    PluginBuilder::try_on(move |_: Arc<PluginFailureEvent>| {
+                        let handled = handled.clone();
+                        async move {
+                            handled.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                            Err::<(), _>("watcher failed")
+                        }
+                    });
+                })
+            }
+        }),
+    ));
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    tx.send(text_msg(2, "err")).await.expect("send");
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    assert_eq!(handled.load(std::sync::atomic::Ordering::SeqCst), 1);
+    let notified = requests
+        .lock()
+        .expect("requests")
+        .iter()
+        .filter(|api| api.action == "notify")
+        .count();
+    assert_eq!(notified, 1);
+
+    handle.abort();
+}
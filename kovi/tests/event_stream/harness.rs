@@ -2,13 +2,18 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
-use kovi::ExitEvent;
+use kovi::bot::runtimebot::send_api_request_with_forget;
 use kovi::bot::{ApiReturn, Bot, SendApi};
 use kovi::config::kovi_conf::KoviConf;
 use kovi::driver::{AnyError, ApiHandlerResult, Driver, DriverEvent, MessageEventRegister};
 use kovi::event::id::ID;
-use kovi::event::{Event, MessageEventTrait};
+use kovi::event::id::ref_id::RefID;
+use kovi::event::{Event, InternalEvent, MessageEventTrait, RepliableEvent};
 use kovi::futures_util::stream;
+use kovi::serde_json::json;
+use kovi::types::ApiAndOptOneshot;
+use kovi::{ExitEvent, Message};
+use serde::Serialize;
 use tokio::sync::{Mutex, Notify, mpsc};
 
 pub(crate) const HANG_TIMEOUT: Duration = Duration::from_millis(800);
@@ -51,9 +56,121 @@ impl MessageEventTrait for DummyMsg {
     }
 }
 
+/// 只有纯文本、发送者与可选群号的消息事件，回复以 `reply` API 发出
+pub(crate) struct TextMsg {
+    user_id: i64,
+    group_id: Option<i64>,
+    message: Message,
+    api_tx: mpsc::Sender<ApiAndOptOneshot>,
+}
+
+impl Event for TextMsg {
+    fn de(
+        event: &InternalEvent,
+        _: &kovi::bot::BotInformation,
+        api_tx: &mpsc::Sender<ApiAndOptOneshot>,
+    ) -> Option<Self> {
+        let InternalEvent::DriverEvent(value) = event else {
+            return None;
+        };
+        Some(TextMsg {
+            user_id: value["user_id"].as_i64()?,
+            group_id: value["group_id"].as_i64(),
+            message: Message::from(value["text"].as_str()?),
+            api_tx: api_tx.clone(),
+        })
+    }
+}
+
+impl MessageEventTrait for TextMsg {
+    fn get_sender_name(&self) -> Option<&str> {
+        None
+    }
+    fn get_sender_id(&self) -> RefID<'_> {
+        RefID::new(&self.user_id)
+    }
+    fn get_message(&self) -> &Message {
+        &self.message
+    }
+    fn get_message_type_str(&self) -> Option<&str> {
+        Some(if self.group_id.is_some() {
+            "group"
+        } else {
+            "private"
+        })
+    }
+    fn get_group_id(&self) -> Option<RefID<'_>> {
+        self.group_id.as_ref().map(RefID::new)
+    }
+}
+
+impl RepliableEvent for TextMsg {
+    fn reply<T>(&self, msg: T)
+    where
+        Message: From<T>,
+        T: Serialize,
+    {
+        let text = Message::from(msg).to_human_string();
+        send_api_request_with_forget(&self.api_tx, SendApi::new("reply", json!({ "text": text })));
+    }
+
+    fn reply_and_quote<T>(&self, msg: T)
+    where
+        Message: From<T>,
+        T: Serialize,
+    {
+        self.reply(msg)
+    }
+}
+
+/// 私聊中的一条 [`TextMsg`]
+pub(crate) fn text_msg(user_id: i64, text: &str) -> Result<DriverEvent, AnyError> {
+    Ok(DriverEvent::Normal(
+        json!({ "user_id": user_id, "text": text }),
+    ))
+}
+
+/// 群中的一条 [`TextMsg`]
+pub(crate) fn group_text_msg(
+    group_id: i64,
+    user_id: i64,
+    text: &str,
+) -> Result<DriverEvent, AnyError> {
+    Ok(DriverEvent::Normal(
+        json!({ "group_id": group_id, "user_id": user_id, "text": text }),
+    ))
+}
+
+/// 等待至少 `n` 条 `reply`，再稍等片刻以收下多余的回复，按发出顺序返回文本
+pub(crate) async fn wait_replies(
+    requests: &std::sync::Mutex<Vec<SendApi>>,
+    n: usize,
+) -> Vec<String> {
+    let replies = || -> Vec<String> {
+        requests
+            .lock()
+            .expect("api_requests poisoned")
+            .iter()
+            .filter(|api| api.action == "reply")
+            .map(|api| api.params["text"].as_str().unwrap_or_default().to_string())
+            .collect()
+    };
+    tokio::time::timeout(Duration::from_secs(3), async {
+        while replies().len() < n {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("replies did not arrive");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    replies()
+}
+
 pub(crate) struct MockDriver {
     event_rx: Mutex<Option<mpsc::Receiver<Result<DriverEvent, AnyError>>>>,
     ready: Arc<Notify>,
+    api_calls: Arc<std::sync::Mutex<Vec<String>>>,
+    api_requests: Arc<std::sync::Mutex<Vec<SendApi>>>,
 }
 
 impl MockDriver {
@@ -68,11 +185,23 @@ impl MockDriver {
             Self {
                 event_rx: Mutex::new(Some(rx)),
                 ready: ready.clone(),
+                api_calls: Default::default(),
+                api_requests: Default::default(),
             },
             tx,
             ready,
         )
     }
+
+    /// 经此驱动发出的 API 的 action
+    pub(crate) fn api_calls(&self) -> Arc<std::sync::Mutex<Vec<String>>> {
+        self.api_calls.clone()
+    }
+
+    /// 经此驱动发出的完整 API 请求
+    pub(crate) fn api_requests(&self) -> Arc<std::sync::Mutex<Vec<SendApi>>> {
+        self.api_requests.clone()
+    }
 }
 
 #[async_trait]
@@ -98,7 +227,15 @@ impl Driver for MockDriver {
         Ok(Box::pin(stream))
     }
 
-    fn api_handler(&self, _value: SendApi) -> ApiHandlerResult {
+    fn api_handler(&self, value: SendApi) -> ApiHandlerResult {
+        self.api_calls
+            .lock()
+            .expect("api_calls poisoned")
+            .push(value.action.clone());
+        self.api_requests
+            .lock()
+            .expect("api_requests poisoned")
+            .push(value);
         Box::pin(async {
             Ok(Ok(ApiReturn {
                 status: "ok".into(),
@@ -110,7 +247,14 @@ impl Driver for MockDriver {
     }
 
     fn message_event_register(&self) -> MessageEventRegister {
-        MessageEventRegister::register::<DummyMsg>()
+        MessageEventRegister::register::<TextMsg>()
+    }
+
+    fn private_message_api(&self, user_id: &ID, text: &str) -> Option<SendApi> {
+        Some(SendApi::new(
+            "notify",
+            json!({ "user_id": user_id, "text": text }),
+        ))
     }
 }
 
@@ -145,11 +289,18 @@ impl Driver for FailingChannelDriver {
     }
 }
 
+/// 测试中的配置与数据都在当前目录中
+fn use_current_dir() {
+    kovi::config::paths::KoviPaths::in_dir(".").set_current();
+}
+
 pub(crate) fn conf() -> KoviConf {
+    use_current_dir();
     KoviConf::new(ID::new(1i64), None, false)
 }
 
 pub(crate) fn status_file_guard() -> StatusFileGuard {
+    use_current_dir();
     StatusFileGuard {
         plugin_existed: std::path::Path::new("kovi.plugin.toml").exists(),
         conf_existed: std::path::Path::new("kovi.conf.toml").exists(),
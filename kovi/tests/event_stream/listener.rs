@@ -0,0 +1,132 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use kovi::event::{MessageEventTrait, RepliableEvent, stop_propagation};
+use kovi::plugin::listener::ListenerHandle;
+use kovi::{Bot, PluginBuilder};
+
+use crate::harness::{
+    MockDriver, TextMsg, conf, status_file_guard, text_msg, wait_ready, wait_replies,
+};
+
+fn text(event: &TextMsg) -> String {
+    event.get_message().to_human_string()
+}
+
+/// 以 `/` 开头的消息在运行时注册或注销监听
+fn dynamic_plugin() -> kovi::plugin::Plugin {
+    kovi::plugin::Plugin::new(
+        "dynamic",
+        "0.0.0",
+        Arc::new(|| {
+            Box::pin(async {
+                let subscription: Arc<Mutex<Option<ListenerHandle>>> = Default::default();
+                PluginBuilder::on(move |event: Arc<TextMsg>| {
+                    let subscription = subscription.clone();
+                    async move {
+                        match text(&event).as_str() {
+                            "/subscribe" => {
+                                let handle = PluginBuilder::on(|event: Arc<TextMsg>| async move {
+                                    let text = text(&event);
+                                    if !text.starts_with('/') {
+                                        event.reply(format!("sub: {text}"));
+                                    }
+                                });
+                                *subscription.lock().expect("subscription") = Some(handle);
+                                event.reply("subscribed");
+                            }
+                            "/unsubscribe" => {
+                                let handle = subscription.lock().expect("subscription").take();
+                                let handle = handle.expect("not subscribed");
+                                handle.unregister();
+                                event.reply(format!("registered: {}", handle.is_registered()));
+                            }
+                            "/once" => {
+                                PluginBuilder::once(|event: Arc<TextMsg>| async move {
+                                    event.reply(format!("once: {}", text(&event)));
+                                });
+                                event.reply("armed");
+                            }
+                            "/first" => {
+                                PluginBuilder::once_with_priority(
+                                    10,
+                                    |event: Arc<TextMsg>| async move {
+                                        stop_propagation();
+                                        event.reply(format!("first: {}", text(&event)));
+                                    },
+                                );
+                                event.reply("armed first");
+                            }
+                            "/spawn" => {
+                                kovi::spawn(async {
+                                    PluginBuilder::once(|event: Arc<TextMsg>| async move {
+                                        event.reply(format!("spawned: {}", text(&event)));
+                                    });
+                                });
+                                event.reply("spawning");
+                            }
+                            _ => {}
+                        }
+                    }
+                });
+            })
+        }),
+    )
+}
+
+fn sorted(mut replies: Vec<String>) -> Vec<String> {
+    replies.sort();
+    replies
+}
+
+/// 监听中与 `kovi::spawn()` 的任务中都可以注册监听，返回的句柄可以注销，一次性的监听只执行一次。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn listeners_register_and_unregister_at_runtime() {
+    let _guard = status_file_guard();
+    let (driver, tx, ready) = MockDriver::new();
+    let requests = driver.api_requests();
+
+    let mut bot = Bot::build(conf(), driver);
+    bot.mount_plugin(dynamic_plugin());
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    tx.send(text_msg(2, "/subscribe")).await.expect("send");
+    assert_eq!(wait_replies(&requests, 1).await, ["subscribed"]);
+    tx.send(text_msg(2, "a")).await.expect("send");
+    assert_eq!(wait_replies(&requests, 2).await[1..], ["sub: a"]);
+
+    tx.send(text_msg(2, "/once")).await.expect("send");
+    assert_eq!(wait_replies(&requests, 3).await[2..], ["armed"]);
+    tx.send(text_msg(2, "b")).await.expect("send");
+    let replies = wait_replies(&requests, 5).await;
+    assert_eq!(sorted(replies[3..].to_vec()), ["once: b", "sub: b"]);
+    tx.send(text_msg(2, "c")).await.expect("send");
+    assert_eq!(wait_replies(&requests, 6).await[5..], ["sub: c"]);
+
+    tx.send(text_msg(2, "/unsubscribe")).await.expect("send");
+    assert_eq!(wait_replies(&requests, 7).await[6..], ["registered: false"]);
+    tx.send(text_msg(2, "d")).await.expect("send");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(wait_replies(&requests, 7).await.len(), 7);
+
+    tx.send(text_msg(2, "/spawn")).await.expect("send");
+    assert_eq!(wait_replies(&requests, 8).await[7..], ["spawning"]);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    tx.send(text_msg(2, "e")).await.expect("send");
+    tx.send(text_msg(2, "f")).await.expect("send");
+    let replies = wait_replies(&requests, 9).await;
+    assert_eq!(replies[8..], ["spawned: e"]);
+
+    // 优先级更高的一次性监听先执行，停止传播后 `/once` 不会被处理
+    tx.send(text_msg(2, "/first")).await.expect("send");
+    assert_eq!(wait_replies(&requests, 10).await[9..], ["armed first"]);
+    tx.send(text_msg(2, "/once")).await.expect("send");
+    assert_eq!(wait_replies(&requests, 11).await[10..], ["first: /once"]);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(wait_replies(&requests, 11).await.len(), 11);
+
+    handle.abort();
+}
@@ -1,3 +1,17 @@
+mod accounts;
 mod channel;
+mod command;
+mod dispatch_table;
+mod dry_run;
 mod exit;
+mod failure;
 mod harness;
+mod listener;
+mod middleware;
+mod ordered;
+mod parse_cache;
+mod priority;
+mod record;
+mod session;
+mod state;
+mod subscribe;
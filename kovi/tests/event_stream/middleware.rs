@@ -0,0 +1,136 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use kovi::bot::middleware::{Annotations, ApiContext, EventContext, Flow, VETOED_RETCODE};
+use kovi::event::{InternalEvent, MessageEventTrait, RepliableEvent};
+use kovi::serde_json::json;
+use kovi::{Bot, PluginBuilder};
+
+use crate::harness::{
+    MockDriver, TextMsg, conf, group_text_msg, status_file_guard, text_msg, wait_ready,
+    wait_replies,
+};
+
+/// 中间件给事件添加的标注
+struct Tag(&'static str);
+
+/// 回复 `<插件名>: <文本> [标注]`，收到 `call` 时调用 `forbidden` API 并回复返回码
+fn echo_plugin(name: &'static str) -> kovi::plugin::Plugin {
+    kovi::plugin::Plugin::new(
+        name,
+        "0.0.0",
+        Arc::new(move || {
+            Box::pin(async move {
+                let bot = PluginBuilder::get_runtime_bot();
+                PluginBuilder::on(move |event: Arc<TextMsg>| {
+                    let bot = bot.clone();
+                    async move {
+                        let text = event.get_message().to_human_string();
+                        if text == "call" {
+                            let retcode = match bot.send_api_return("forbidden", json!({})).await {
+                                Ok(v) | Err(v) => v.retcode,
+                            };
+                            event.reply(format!("{name}: retcode {retcode}"));
+                            return;
+                        }
+                        let tag = Annotations::current()
+                            .and_then(|annotations| annotations.get::<Tag>().map(|tag| tag.0))
+                            .unwrap_or("none");
+                        event.reply(format!("{name}: {text} [{tag}]"));
+                    }
+                });
+            })
+        }),
+    )
+}
+
+fn bot_with_middlewares(driver: MockDriver) -> Bot {
+    let mut bot = Bot::build(conf(), driver)
+        // 屏蔽用户 9
+        .middleware(|ctx: &mut EventContext| {
+            let blocked = ctx
+                .message()
+                .is_some_and(|msg| msg.get_sender_id().to_string() == "9");
+            if blocked { Flow::Stop } else { Flow::Continue }
+        })
+        // 把 hi 改写为 hello
+        .middleware(|ctx: &mut EventContext| {
+            if let InternalEvent::DriverEvent(value) = ctx.event_mut()
+                && value["text"] == "hi"
+            {
+                Arc::make_mut(value)["text"] = json!("hello");
+            }
+            Flow::Continue
+        })
+        // 标注群消息，并在群 200 中关闭插件 second
+        .middleware(|ctx: &mut EventContext| {
+            let group = ctx
+                .message()
+                .and_then(|msg| msg.get_group_id())
+                .map(|id| id.to_string());
+            if group.is_some() {
+                ctx.annotations_mut().insert(Tag("group"));
+            }
+            if group.as_deref() == Some("200") {
+                ctx.skip_plugin("second");
+            }
+            Flow::Continue
+        })
+        .api_middleware(|ctx: &mut ApiContext| {
+            if ctx.api().action == "forbidden" {
+                return Flow::Stop;
+            }
+            if ctx.api().action == "reply" {
+                let text = ctx.api().params["text"].as_str().unwrap_or_default();
+                ctx.api_mut().params["text"] = json!(format!("{text}!"));
+            }
+            Flow::Continue
+        });
+    bot.mount_plugin(echo_plugin("first"));
+    bot.mount_plugin(echo_plugin("second"));
+    bot
+}
+
+/// 事件中间件可以拦截、改写、标注事件，也可以对某个插件隐藏事件；API 中间件可以改写或拦截 API。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn middlewares_intercept_events_and_api() {
+    let _guard = status_file_guard();
+    let (driver, tx, ready) = MockDriver::new();
+    let requests = driver.api_requests();
+
+    let bot = bot_with_middlewares(driver);
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    tx.send(text_msg(9, "blocked")).await.expect("send");
+    tx.send(text_msg(2, "hi")).await.expect("send");
+    tx.send(group_text_msg(100, 2, "a")).await.expect("send");
+    tx.send(group_text_msg(200, 2, "b")).await.expect("send");
+
+    let mut replies = wait_replies(&requests, 5).await;
+    replies.sort();
+    assert_eq!(
+        replies,
+        [
+            "first: a [group]!",
+            "first: b [group]!",
+            "first: hello [none]!",
+            "second: a [group]!",
+            "second: hello [none]!",
+        ]
+    );
+
+    tx.send(group_text_msg(200, 2, "call")).await.expect("send");
+    let replies = wait_replies(&requests, 6).await;
+    assert_eq!(replies[5], format!("first: retcode {VETOED_RETCODE}!"));
+    let forbidden_sent = requests
+        .lock()
+        .expect("requests")
+        .iter()
+        .any(|api| api.action == "forbidden");
+    assert!(!forbidden_sent);
+
+    handle.abort();
+}
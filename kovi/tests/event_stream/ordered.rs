@@ -0,0 +1,92 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use kovi::bot::dispatch::DispatchMode;
+use kovi::event::{MessageEventTrait, RepliableEvent};
+use kovi::{Bot, PluginBuilder};
+
+use crate::harness::{
+    MockDriver, TextMsg, conf, status_file_guard, text_msg, wait_ready, wait_replies,
+};
+
+/// 回复 `<插件名>: <文本>`，文本为 `slow` 时等待的时间更长
+fn plugin(name: &'static str, mode: Option<DispatchMode>) -> kovi::plugin::Plugin {
+    kovi::plugin::Plugin::new(
+        name,
+        "0.0.0",
+        Arc::new(move || {
+            Box::pin(async move {
+                if let Some(mode) = mode {
+                    PluginBuilder::set_dispatch_mode(mode);
+                }
+                PluginBuilder::on(move |event: Arc<TextMsg>| async move {
+                    let text = event.get_message().to_human_string();
+                    let delay = if text == "slow" { 200 } else { 20 };
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                    event.reply(format!("{name}: {text}"));
+                });
+            })
+        }),
+    )
+}
+
+fn position(replies: &[String], reply: &str) -> usize {
+    replies
+        .iter()
+        .position(|v| v == reply)
+        .unwrap_or_else(|| panic!("missing reply {reply:?} in {replies:?}"))
+}
+
+/// 按顺序处理的插件在同一会话中按收到的顺序回复，不同会话和并发处理的插件不受影响。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn ordered_plugin_keeps_conversation_order() {
+    let _guard = status_file_guard();
+    let (driver, tx, ready) = MockDriver::new();
+    let requests = driver.api_requests();
+
+    let mut bot = Bot::build(conf(), driver);
+    bot.mount_plugin(plugin("ordered", Some(DispatchMode::Ordered)));
+    bot.mount_plugin(plugin("concurrent", None));
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    tx.send(text_msg(2, "slow")).await.expect("send");
+    tx.send(text_msg(2, "fast")).await.expect("send");
+    tx.send(text_msg(5, "other")).await.expect("send");
+    let replies = wait_replies(&requests, 6).await;
+
+    assert!(position(&replies, "ordered: slow") < position(&replies, "ordered: fast"));
+    assert!(position(&replies, "ordered: other") < position(&replies, "ordered: slow"));
+    assert!(position(&replies, "concurrent: fast") < position(&replies, "concurrent: slow"));
+    // 并发处理的插件不等待按顺序处理的插件
+    assert!(position(&replies, "concurrent: fast") < position(&replies, "ordered: slow"));
+
+    handle.abort();
+}
+
+/// Bot 的分发方式对所有插件生效，插件自己设置的分发方式优先。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn bot_dispatch_mode_is_default_for_plugins() {
+    let _guard = status_file_guard();
+    let (driver, tx, ready) = MockDriver::new();
+    let requests = driver.api_requests();
+
+    let mut bot = Bot::build(conf(), driver).set_dispatch_mode(DispatchMode::Ordered);
+    bot.mount_plugin(plugin("ordered", None));
+    bot.mount_plugin(plugin("concurrent", Some(DispatchMode::Concurrent)));
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    tx.send(text_msg(2, "slow")).await.expect("send");
+    tx.send(text_msg(2, "fast")).await.expect("send");
+    let replies = wait_replies(&requests, 4).await;
+
+    assert!(position(&replies, "ordered: slow") < position(&replies, "ordered: fast"));
+    assert!(position(&replies, "concurrent: fast") < position(&replies, "concurrent: slow"));
+
+    handle.abort();
+}
@@ -0,0 +1,106 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use kovi::bot::BotInformation;
+use kovi::event::{Event, InternalEvent, parse_cached};
+use kovi::types::ApiAndOptOneshot;
+use kovi::{Bot, PluginBuilder};
+use tokio::sync::mpsc;
+
+use crate::harness::{MockDriver, conf, status_file_guard, text_msg, wait_ready};
+
+/// [`Base`] 被解析的次数
+static BASE_PARSED: AtomicUsize = AtomicUsize::new(0);
+
+/// 基础事件，每次解析都会计数
+struct Base {
+    text: String,
+}
+
+impl Event for Base {
+    fn de(
+        event: &InternalEvent,
+        _: &BotInformation,
+        _: &mpsc::Sender<ApiAndOptOneshot>,
+    ) -> Option<Self> {
+        let InternalEvent::DriverEvent(value) = event else {
+            return None;
+        };
+        BASE_PARSED.fetch_add(1, Ordering::SeqCst);
+        Some(Base {
+            text: value["text"].as_str()?.to_string(),
+        })
+    }
+}
+
+/// 由 [`Base`] 派生的事件
+struct Shouted {
+    text: String,
+}
+
+impl Event for Shouted {
+    fn de(
+        event: &InternalEvent,
+        bot_info: &BotInformation,
+        api_tx: &mpsc::Sender<ApiAndOptOneshot>,
+    ) -> Option<Self> {
+        let base = parse_cached::<Base>(event, bot_info, api_tx)?;
+        Some(Shouted {
+            text: base.text.to_uppercase(),
+        })
+    }
+}
+
+fn plugin(seen: Arc<std::sync::Mutex<Vec<String>>>) -> kovi::plugin::Plugin {
+    kovi::plugin::Plugin::new(
+        "parse",
+        "0.0.0",
+        Arc::new(move || {
+            let seen = seen.clone();
+            Box::pin(async move {
+                let on_base = seen.clone();
+                PluginBuilder::on(move |event: Arc<Base>| {
+                    let seen = on_base.clone();
+                    async move { seen.lock().expect("seen").push(event.text.clone()) }
+                });
+                // 不同优先级的监听之间也共用解析结果
+                let on_shouted = seen.clone();
+                PluginBuilder::on_with_priority(10, move |event: Arc<Shouted>| {
+                    let seen = on_shouted.clone();
+                    async move { seen.lock().expect("seen").push(event.text.clone()) }
+                });
+            })
+        }),
+    )
+}
+
+/// 一个事件中，派生事件与基础事件共用一次解析。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn derived_events_reuse_parsed_base() {
+    let _guard = status_file_guard();
+    let (driver, tx, ready) = MockDriver::new();
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+    let mut bot = Bot::build(conf(), driver);
+    bot.mount_plugin(plugin(seen.clone()));
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    BASE_PARSED.store(0, Ordering::SeqCst);
+    tx.send(text_msg(2, "hi")).await.expect("send");
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while seen.lock().expect("seen").len() < 2 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("listeners did not run");
+
+    assert_eq!(*seen.lock().expect("seen"), ["HI", "hi"]);
+    assert_eq!(BASE_PARSED.load(Ordering::SeqCst), 1);
+
+    handle.abort();
+}
@@ -0,0 +1,73 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use kovi::event::{MessageEventTrait, RepliableEvent, stop_propagation};
+use kovi::{Bot, PluginBuilder};
+
+use crate::harness::{
+    MockDriver, TextMsg, conf, status_file_guard, text_msg, wait_ready, wait_replies,
+};
+
+/// 以 `priority` 监听，回复 `<插件名>: <文本>`，文本以 `stop_on` 开头时停止传播
+fn plugin(
+    name: &'static str,
+    priority: i32,
+    stop_on: Option<&'static str>,
+) -> kovi::plugin::Plugin {
+    kovi::plugin::Plugin::new(
+        name,
+        "0.0.0",
+        Arc::new(move || {
+            Box::pin(async move {
+                PluginBuilder::on_with_priority(priority, move |event: Arc<TextMsg>| async move {
+                    let text = event.get_message().to_human_string();
+                    if stop_on.is_some_and(|prefix| text.starts_with(prefix)) {
+                        stop_propagation();
+                    }
+                    // 让低优先级的监听有机会抢先，以验证它们确实在等待
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    event.reply(format!("{name}: {text}"));
+                });
+            })
+        }),
+    )
+}
+
+/// 监听按优先级从高到低执行，停止传播后优先级更低的监听不再执行，同一优先级不受影响。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn priorities_order_and_stop_propagation() {
+    let _guard = status_file_guard();
+    let (driver, tx, ready) = MockDriver::new();
+    let requests = driver.api_requests();
+
+    let mut bot = Bot::build(conf(), driver);
+    bot.mount_plugin(plugin("moderation", 100, Some("spam")));
+    bot.mount_plugin(plugin("help", 10, Some("/help")));
+    bot.mount_plugin(plugin("same", 10, None));
+    bot.mount_plugin(plugin("fun", 0, None));
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    tx.send(text_msg(2, "spam")).await.expect("send");
+    assert_eq!(wait_replies(&requests, 1).await, ["moderation: spam"]);
+
+    tx.send(text_msg(2, "/help")).await.expect("send");
+    let replies = wait_replies(&requests, 4).await;
+    assert_eq!(replies[0], "moderation: spam");
+    assert_eq!(replies[1], "moderation: /help");
+    let mut same_priority = replies[2..].to_vec();
+    same_priority.sort();
+    assert_eq!(same_priority, ["help: /help", "same: /help"]);
+
+    tx.send(text_msg(2, "hi")).await.expect("send");
+    let replies = wait_replies(&requests, 8).await;
+    assert_eq!(replies[4], "moderation: hi");
+    let mut same_priority = replies[5..7].to_vec();
+    same_priority.sort();
+    assert_eq!(same_priority, ["help: hi", "same: hi"]);
+    assert_eq!(replies[7], "fun: hi");
+
+    handle.abort();
+}
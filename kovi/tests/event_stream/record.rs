@@ -0,0 +1,152 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use kovi::driver::MessageEventRegister;
+use kovi::driver::record::{Record, RecordEntry, RecordingDriver, ReplayDriver, ReplayPace};
+use kovi::event::{MessageEventTrait, RepliableEvent};
+use kovi::serde_json::json;
+use kovi::{Bot, PluginBuilder};
+
+use crate::harness::{
+    MockDriver, TextMsg, conf, status_file_guard, text_msg, wait_ready, wait_replies,
+};
+
+/// 收到 `who` 时调用 `get_name` 并回复返回的名字或返回码，其他文本原样回复
+fn plugin() -> kovi::plugin::Plugin {
+    kovi::plugin::Plugin::new(
+        "record",
+        "0.0.0",
+        Arc::new(|| {
+            Box::pin(async {
+                let bot = PluginBuilder::get_runtime_bot();
+                PluginBuilder::on(move |event: Arc<TextMsg>| {
+                    let bot = bot.clone();
+                    async move {
+                        let text = event.get_message().to_human_string();
+                        if text != "who" && text != "what" {
+                            event.reply(text);
+                            return;
+                        }
+                        let action = if text == "who" {
+                            "get_name"
+                        } else {
+                            "get_what"
+                        };
+                        match bot.send_api_return(action, json!({})).await {
+                            Ok(ret) => event.reply(ret.data["name"].as_str().unwrap_or_default()),
+                            Err(ret) => event.reply(ret.retcode.to_string()),
+                        }
+                    }
+                });
+            })
+        }),
+    )
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("kovi-{}-{name}.jsonl", std::process::id()))
+}
+
+fn read_records(path: &PathBuf) -> Vec<Record> {
+    std::fs::read_to_string(path)
+        .expect("read recording")
+        .lines()
+        .map(|line| kovi::serde_json::from_str(line).expect("parse record"))
+        .collect()
+}
+
+/// 录制的文件按顺序包含事件、API 与返回。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn recording_captures_events_and_api() {
+    let _guard = status_file_guard();
+    let path = temp_path("capture");
+    let (driver, tx, ready) = MockDriver::new();
+    let requests = driver.api_requests();
+
+    let mut bot = Bot::build(conf(), RecordingDriver::new(driver, &path).expect("create"));
+    bot.mount_plugin(plugin());
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    tx.send(text_msg(2, "a")).await.expect("send");
+    assert_eq!(wait_replies(&requests, 1).await, ["a"]);
+    handle.abort();
+
+    let is_reply = |v: &Record| {
+        matches!(&v.entry, RecordEntry::Api { api, result: Ok(_) }
+            if api.action == "reply" && api.params["text"] == "a")
+    };
+    // 文件在单独的线程中写入
+    let records = tokio::time::timeout(Duration::from_secs(2), async {
+        loop {
+            let records = read_records(&path);
+            if records.iter().any(is_reply) {
+                break records;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("api was not recorded");
+    let _ = std::fs::remove_file(&path);
+    assert!(
+        records
+            .windows(2)
+            .all(|v| v[0].elapsed_ms <= v[1].elapsed_ms)
+    );
+    let event = records
+        .iter()
+        .position(|v| matches!(&v.entry, RecordEntry::Event { event } if event["text"] == "a"))
+        .expect("event recorded");
+    let reply = records.iter().position(is_reply).expect("api recorded");
+    assert!(event < reply);
+}
+
+/// 回放推送录制的事件，API 使用录制的返回，没有录制的 API 返回错误。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn replay_feeds_events_and_recorded_returns() {
+    let _guard = status_file_guard();
+    let recording = temp_path("replay-in");
+    let output = temp_path("replay-out");
+    let lines = [
+        json!({ "elapsed_ms": 0, "time": 0, "kind": "event",
+            "event": { "user_id": 2, "text": "who" } }),
+        json!({ "elapsed_ms": 5, "time": 0, "kind": "api",
+            "api": { "action": "get_name", "params": {} },
+            "result": { "Ok": { "status": "ok", "retcode": 0, "message": null,
+                "data": { "name": "kovi" } } } }),
+        json!({ "elapsed_ms": 10, "time": 0, "kind": "event",
+            "event": { "user_id": 2, "text": "what" } }),
+    ];
+    let text: Vec<String> = lines.iter().map(|v| v.to_string()).collect();
+    std::fs::write(&recording, text.join("\n")).expect("write recording");
+
+    let replay = ReplayDriver::open(&recording, MessageEventRegister::register::<TextMsg>())
+        .expect("open")
+        .set_pace(ReplayPace::Fast)
+        .set_linger(Duration::from_millis(300));
+    // 回放时再录制一次，查看新插件发出的 API
+    let driver = RecordingDriver::new(replay, &output).expect("create");
+    let mut bot = Bot::build(conf(), driver);
+    bot.mount_plugin(plugin());
+    tokio::time::timeout(Duration::from_secs(5), bot.run())
+        .await
+        .expect("replay did not finish");
+
+    let mut replies: Vec<String> = read_records(&output)
+        .into_iter()
+        .filter_map(|v| match v.entry {
+            RecordEntry::Api { api, .. } if api.action == "reply" => {
+                Some(api.params["text"].as_str()?.to_string())
+            }
+            _ => None,
+        })
+        .collect();
+    replies.sort();
+    let _ = std::fs::remove_file(&recording);
+    let _ = std::fs::remove_file(&output);
+    assert_eq!(replies, ["-404", "kovi"]);
+}
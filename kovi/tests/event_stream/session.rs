@@ -0,0 +1,210 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use kovi::bot::runtimebot::kovi_api::AccessControlMode;
+use kovi::error::SessionError;
+use kovi::event::{MessageEventTrait, RepliableEvent};
+use kovi::session::{Session, WaitNext};
+use kovi::{Bot, PluginBuilder, RuntimeBot};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::harness::{
+    MockDriver, TextMsg, conf, group_text_msg, status_file_guard, text_msg, wait_ready,
+    wait_replies,
+};
+
+fn text(event: &TextMsg) -> String {
+    event.get_message().to_human_string()
+}
+
+/// `weather` 询问城市并等待回答，其余消息回复 `seen <text>`
+fn weather_plugin(timeout: Duration) -> kovi::plugin::Plugin {
+    kovi::plugin::Plugin::new(
+        "weather",
+        "0.0.0",
+        Arc::new(move || {
+            Box::pin(async move {
+                PluginBuilder::on(move |event: Arc<TextMsg>| async move {
+                    if text(&event) != "weather" {
+                        event.reply(format!("seen {}", text(&event)));
+                        return;
+                    }
+                    event.reply("city?");
+                    match event.wait_next(|_| true, timeout).await {
+                        Ok(answer) => event.reply(format!("sunny in {}", text(&answer))),
+                        Err(e) => event.reply(e.to_string()),
+                    }
+                });
+            })
+        }),
+    )
+}
+
+/// 只有同一发送者在同一群中的下一条消息会交给会话，且不再触发插件的监听。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn session_waits_for_same_sender_and_group() {
+    let _guard = status_file_guard();
+    let (driver, tx, ready) = MockDriver::new();
+    let requests = driver.api_requests();
+
+    let mut bot = Bot::build(conf(), driver);
+    bot.mount_plugin(weather_plugin(Duration::from_secs(3)));
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    tx.send(group_text_msg(100, 2, "weather"))
+        .await
+        .expect("send");
+    assert_eq!(wait_replies(&requests, 1).await, ["city?"]);
+
+    tx.send(group_text_msg(100, 3, "Paris"))
+        .await
+        .expect("send");
+    tx.send(text_msg(2, "London")).await.expect("send");
+    tx.send(group_text_msg(100, 2, "Tokyo"))
+        .await
+        .expect("send");
+
+    let mut replies = wait_replies(&requests, 4).await;
+    replies.sort();
+    assert_eq!(
+        replies,
+        ["city?", "seen London", "seen Paris", "sunny in Tokyo"]
+    );
+
+    handle.abort();
+}
+
+/// 超时后等待返回 `SessionError::Timeout`，之后的消息照常交给监听。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn session_times_out() {
+    let _guard = status_file_guard();
+    let (driver, tx, ready) = MockDriver::new();
+    let requests = driver.api_requests();
+
+    let mut bot = Bot::build(conf(), driver);
+    bot.mount_plugin(weather_plugin(Duration::from_millis(200)));
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    tx.send(text_msg(2, "weather")).await.expect("send");
+    let replies = wait_replies(&requests, 2).await;
+    assert_eq!(replies, ["city?", &SessionError::Timeout.to_string()]);
+
+    tx.send(text_msg(2, "Tokyo")).await.expect("send");
+    assert_eq!(wait_replies(&requests, 3).await[2], "seen Tokyo");
+
+    handle.abort();
+}
+
+/// 插件被关闭时，正在进行的等待返回 `SessionError::Cancelled`。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn session_cancelled_when_plugin_disabled() {
+    let _guard = status_file_guard();
+    let (driver, tx, ready) = MockDriver::new();
+
+    let (bot_tx, mut bot_rx) = mpsc::channel::<Arc<RuntimeBot>>(1);
+    let (result_tx, result_rx) = oneshot::channel();
+    let result_tx = Arc::new(std::sync::Mutex::new(Some(result_tx)));
+    let plugin = kovi::plugin::Plugin::new(
+        "waiting",
+        "0.0.0",
+        Arc::new(move || {
+            let bot_tx = bot_tx.clone();
+            let result_tx = result_tx.clone();
+            Box::pin(async move {
+                let _ = bot_tx.send(PluginBuilder::get_runtime_bot()).await;
+                PluginBuilder::on(move |event: Arc<TextMsg>| {
+                    let result_tx = result_tx.clone();
+                    async move {
+                        let session = Session::new(&*event);
+                        // 不受 Kovi 管理的线程不会随插件关闭被中止，可以观察到等待的结果
+                        tokio::spawn(async move {
+                            let result = session.next().await.map(|_| ());
+                            if let Some(tx) = result_tx.lock().expect("result_tx").take() {
+                                let _ = tx.send(result);
+                            }
+                        });
+                    }
+                });
+            })
+        }),
+    );
+
+    let mut bot = Bot::build(conf(), driver);
+    bot.mount_plugin(plugin);
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    let runtime_bot = bot_rx.recv().await.expect("runtime bot");
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    tx.send(text_msg(2, "start")).await.expect("send");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    runtime_bot.disable_plugin("waiting").expect("disable");
+
+    let result = tokio::time::timeout(Duration::from_secs(3), result_rx)
+        .await
+        .expect("session was not cancelled")
+        .expect("result");
+    assert_eq!(result, Err(SessionError::Cancelled));
+
+    handle.abort();
+}
+
+/// 会话与监听一样受插件黑白名单限制，名单不允许的消息不会交给会话。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn session_respects_access_control() {
+    let _guard = status_file_guard();
+    let (driver, tx, ready) = MockDriver::new();
+    let requests = driver.api_requests();
+
+    let (bot_tx, mut bot_rx) = mpsc::channel::<Arc<RuntimeBot>>(1);
+    let admin = kovi::plugin::Plugin::new(
+        "admin",
+        "0.0.0",
+        Arc::new(move || {
+            let bot_tx = bot_tx.clone();
+            Box::pin(async move {
+                let _ = bot_tx.send(PluginBuilder::get_runtime_bot()).await;
+            })
+        }),
+    );
+
+    let mut bot = Bot::build(conf(), driver);
+    bot.mount_plugin(weather_plugin(Duration::from_secs(3)));
+    bot.mount_plugin(admin);
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    let runtime_bot = bot_rx.recv().await.expect("runtime bot");
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    tx.send(text_msg(2, "weather")).await.expect("send");
+    assert_eq!(wait_replies(&requests, 1).await, ["city?"]);
+
+    // 空白名单，用户 2 不能再使用 weather
+    runtime_bot
+        .set_plugin_access_control_mode("weather", AccessControlMode::WhiteList)
+        .expect("acl");
+    runtime_bot
+        .set_plugin_access_control("weather", true)
+        .expect("acl");
+    tx.send(text_msg(2, "Tokyo")).await.expect("send");
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    runtime_bot
+        .set_plugin_access_control("weather", false)
+        .expect("acl");
+    tx.send(text_msg(2, "Paris")).await.expect("send");
+    assert_eq!(
+        wait_replies(&requests, 2).await,
+        ["city?", "sunny in Paris"]
+    );
+
+    handle.abort();
+}
@@ -0,0 +1,137 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use kovi::event::{MessageEventTrait, RepliableEvent};
+use kovi::plugin::state::PluginState;
+use kovi::{Bot, PluginBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::harness::{
+    MockDriver, TextMsg, conf, status_file_guard, text_msg, wait_ready, wait_replies,
+};
+
+const PLUGIN: &str = "state_migration";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct User {
+    id: i64,
+    greeting: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Data {
+    users: Vec<User>,
+}
+
+type Slot = Arc<Mutex<Option<PluginState<Data>>>>;
+
+fn data_dir() -> PathBuf {
+    kovi::utils::get_data_root_path().join(PLUGIN)
+}
+
+fn read_json(path: &std::path::Path) -> Value {
+    serde_json::from_slice(&std::fs::read(path).expect("read")).expect("json")
+}
+
+/// 加载 v1 的数据并迁移到 v3，收到 `stop` 时关闭自己
+fn state_plugin(slot: Slot) -> kovi::plugin::Plugin {
+    kovi::plugin::Plugin::new(
+        PLUGIN,
+        "0.0.0",
+        Arc::new(move || {
+            let slot = slot.clone();
+            Box::pin(async move {
+                let state = PluginState::<Data>::builder("data.json")
+                    .set_version(3)
+                    // v1 -> v2: `users` 由 ID 列表改为对象列表
+                    .migrate(1, |mut v| {
+                        v["users"] = v["users"]
+                            .as_array()
+                            .into_iter()
+                            .flatten()
+                            .map(|id| json!({ "id": id }))
+                            .collect();
+                        Ok(v)
+                    })
+                    // v2 -> v3: 增加 `greeting`
+                    .migrate(2, |mut v| {
+                        for user in v["users"].as_array_mut().ok_or("users")? {
+                            user["greeting"] = json!("hi");
+                        }
+                        Ok(v)
+                    })
+                    .set_debounce(Duration::from_millis(200))
+                    .load(Data::default)
+                    .expect("load state");
+                *slot.lock().expect("slot") = Some(state);
+
+                let bot = PluginBuilder::get_runtime_bot();
+                PluginBuilder::on(move |event: Arc<TextMsg>| {
+                    let bot = bot.clone();
+                    async move {
+                        if event.get_message().to_human_string() == "stop" {
+                            bot.disable_plugin(PLUGIN).expect("disable");
+                            event.reply("stopped");
+                        }
+                    }
+                });
+            })
+        }),
+    )
+}
+
+/// 旧版本的文件被依次迁移并备份，修改在延迟后保存，插件关闭时也会保存。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn state_migrates_and_saves() {
+    let _guard = status_file_guard();
+    let dir = data_dir();
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create data dir");
+    let file = dir.join("data.json");
+    std::fs::write(&file, r#"{"users":[1,2]}"#).expect("write v1");
+
+    let (driver, tx, ready) = MockDriver::new();
+    let requests = driver.api_requests();
+    let slot = Slot::default();
+
+    let mut bot = Bot::build(conf(), driver);
+    bot.mount_plugin(state_plugin(slot.clone()));
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let state = slot.lock().expect("slot").clone().expect("state loaded");
+    assert_eq!(state.read().users.len(), 2);
+    assert_eq!(state.read().users[1].greeting, "hi");
+    assert_eq!(
+        read_json(&file),
+        json!({
+            "kovi_state_version": 3,
+            "data": { "users": [{ "id": 1, "greeting": "hi" }, { "id": 2, "greeting": "hi" }] }
+        })
+    );
+    assert_eq!(
+        read_json(&dir.join("data.json.v1.bak")),
+        json!({ "users": [1, 2] })
+    );
+
+    state.write().users[0].greeting = "hello".to_string();
+    assert_eq!(read_json(&file)["data"]["users"][0]["greeting"], "hi");
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    assert_eq!(read_json(&file)["data"]["users"][0]["greeting"], "hello");
+
+    state.write().users.pop();
+    tx.send(text_msg(2, "stop")).await.expect("send");
+    assert_eq!(wait_replies(&requests, 1).await, ["stopped"]);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(
+        read_json(&file)["data"]["users"].as_array().map(Vec::len),
+        Some(1)
+    );
+
+    handle.abort();
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,108 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use kovi::event::{MessageEventTrait, RepliableEvent};
+use kovi::futures_util::StreamExt as _;
+use kovi::{Bot, PluginBuilder};
+use tokio::sync::Notify;
+
+use crate::harness::{
+    MockDriver, TextMsg, conf, status_file_guard, text_msg, wait_ready, wait_replies,
+};
+
+/// 以流取得消息并回复 `stream: <文本>`，收到 `stop` 时关闭自己，流结束时通知 `ended`
+fn streaming_plugin(ended: Arc<Notify>) -> kovi::plugin::Plugin {
+    kovi::plugin::Plugin::new(
+        "streaming",
+        "0.0.0",
+        Arc::new(move || {
+            let ended = ended.clone();
+            Box::pin(async move {
+                let bot = PluginBuilder::get_runtime_bot();
+                let mut events = PluginBuilder::subscribe::<TextMsg>()
+                    .filter(|e| std::future::ready(e.get_message().to_human_string() != "skip"));
+                tokio::spawn(async move {
+                    while let Some(event) = events.next().await {
+                        let text = event.get_message().to_human_string();
+                        if text == "stop" {
+                            let _ = bot.disable_plugin("streaming");
+                        } else {
+                            event.reply(format!("stream: {text}"));
+                        }
+                    }
+                    ended.notify_one();
+                });
+            })
+        }),
+    )
+}
+
+/// 订阅的事件依次进入流，插件关闭后流结束。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn subscription_ends_with_plugin() {
+    let _guard = status_file_guard();
+    let (driver, tx, ready) = MockDriver::new();
+    let requests = driver.api_requests();
+    let ended = Arc::new(Notify::new());
+
+    let mut bot = Bot::build(conf(), driver);
+    bot.mount_plugin(streaming_plugin(ended.clone()));
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    tx.send(text_msg(2, "a")).await.expect("send");
+    assert_eq!(wait_replies(&requests, 1).await, ["stream: a"]);
+    tx.send(text_msg(2, "skip")).await.expect("send");
+    tx.send(text_msg(2, "b")).await.expect("send");
+    assert_eq!(wait_replies(&requests, 2).await, ["stream: a", "stream: b"]);
+
+    let ended_wait = ended.notified();
+    tx.send(text_msg(2, "stop")).await.expect("send");
+    tokio::time::timeout(Duration::from_secs(2), ended_wait)
+        .await
+        .expect("stream did not end");
+
+    handle.abort();
+}
+
+/// 流被丢弃时注销监听。
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn dropping_stream_unregisters_listener() {
+    let (driver, _tx, ready) = MockDriver::new();
+    let result: Arc<std::sync::Mutex<Option<(bool, bool)>>> = Default::default();
+
+    let mut bot = Bot::build(conf(), driver);
+    bot.mount_plugin(kovi::plugin::Plugin::new(
+        "dropping",
+        "0.0.0",
+        Arc::new({
+            let result = result.clone();
+            move || {
+                let result = result.clone();
+                Box::pin(async move {
+                    let events = PluginBuilder::subscribe::<TextMsg>();
+                    let handle = events.listener_handle().clone();
+                    let before = handle.is_registered();
+                    drop(events);
+                    *result.lock().expect("result") = Some((before, handle.is_registered()));
+                })
+            }
+        }),
+    ));
+    let ready_wait = ready.notified();
+    let handle = tokio::spawn(bot.run());
+    wait_ready(ready_wait).await;
+
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while result.lock().expect("result").is_none() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("plugin did not run");
+    assert_eq!(*result.lock().expect("result"), Some((true, false)));
+
+    handle.abort();
+}